@@ -1,8 +1,34 @@
-use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
-    types::encounter_types::{Encounter, EncounterPlayer, PlayableStatBlock},
-    utils::supabase_util::init_supabase,
+    database::{
+        local_db::{
+            delete_local_encounter, fetch_local_encounter, fetch_local_encounter_players,
+            fetch_local_encounters, fetch_local_playable_statblocks, has_pending_encounter_changes,
+            local_last_modified, remap_temporary_id, replace_local_encounter_players,
+            replace_local_encounters, replace_local_playable_statblocks, save_local_encounter,
+            update_local_combatant,
+        },
+        repository::{
+            offline_fallback, EncounterRepository, StatBlockRepository, SupabaseRepository,
+        },
+        statblock_db::{fetch_statblocks_by_ids, last_modified_filter},
+    },
+    types::{
+        combat_types::CombatantRef,
+        condition_types::ActiveCondition,
+        conflict_types::EncounterConflict,
+        encounter_types::{Encounter, EncounterPlayer, HpMode, PlayableStatBlock},
+        error_types::AppError,
+        sync_types::SyncOperation,
+    },
+    utils::{
+        dice_utils::{dice_rng, roll_hit_points},
+        sync_utils::queue_offline_change,
+    },
 };
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -11,6 +37,11 @@ pub struct SaveEncounterResponse {
     pub status: u16,
     pub message: String,
     pub was_updated: bool,
+    // Set by the server on every save, the last_seen to send with the next one
+    pub last_modified: DateTime<Utc>,
+    // Set when the Encounter changed on the server since last_seen, nothing was written
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflict: Option<EncounterConflict>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -26,22 +57,16 @@ pub struct SaveEncounterPlayersResponse {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FetchEncountersResponse {
     pub encounters: Vec<Encounter>,
-    pub status: u16,
-    pub message: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FetchPlayableStatBlocksForEncounterResponse {
     pub playable_stat_blocks: Vec<PlayableStatBlock>,
-    pub status: u16,
-    pub message: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FetchEncounterPlayersForEncounterResponse {
     pub encounter_players: Vec<EncounterPlayer>,
-    pub status: u16,
-    pub message: String,
 }
 
 //? GET
@@ -50,175 +75,119 @@ pub struct FetchEncounterPlayersForEncounterResponse {
 pub async fn fetch_encounter_players_for_encounter(
     encounter_id: i64,
     access_token: String,
-) -> Result<FetchEncounterPlayersForEncounterResponse, FetchEncounterPlayersForEncounterResponse> {
-    let config = init_supabase()
-        .await
-        .map_err(|e| FetchEncounterPlayersForEncounterResponse {
-            encounter_players: Vec::new(),
-            status: 500,
-            message: format!("EncounterPlayer fetch failed: {}", e),
-        })?;
-    let client = reqwest::Client::new();
-    let url = format!(
-        "{}/rest/v1/EncounterPlayer?encounter_id=eq.{}",
-        config.url, encounter_id
-    );
-
-    let response = client
-        .get(&url)
-        .header("apikey", &config.anon_key)
-        .header("Authorization", format!("Bearer {}", &access_token))
-        .header("Content-Type", "application/json")
-        .send()
-        .await
-        .map_err(|e| FetchEncounterPlayersForEncounterResponse {
-            encounter_players: Vec::new(),
-            status: 500,
-            message: format!("EncounterPlayer fetch failed: {}", e),
-        })?;
-
-    let status = response.status();
-
-    if !status.is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(FetchEncounterPlayersForEncounterResponse {
-            encounter_players: Vec::new(),
-            status: status.as_u16(),
-            message: format!("EncounterPlayer fetch failed: {}", error_text),
-        });
-    }
+) -> Result<FetchEncounterPlayersForEncounterResponse, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
 
-    let encounter_players =
-        response
-            .json()
-            .await
-            .map_err(|e| FetchEncounterPlayersForEncounterResponse {
-                encounter_players: Vec::new(),
-                status: 500,
-                message: format!(
-                    "Failed to parse EncounterPlayer response: {}",
-                    e.to_string()
-                ),
-            })?;
-
-    Ok(FetchEncounterPlayersForEncounterResponse {
-        encounter_players,
-        status: status.as_u16(),
-        message: format!("Successfully fetched EncounterPlayers"),
-    })
+    fetch_encounter_players_from(&repository, encounter_id).await
 }
 
 #[tauri::command]
 pub async fn fetch_playable_statblocks_for_encounter(
     encounter_id: i64,
     access_token: String,
-) -> Result<FetchPlayableStatBlocksForEncounterResponse, FetchPlayableStatBlocksForEncounterResponse>
-{
-    let config =
-        init_supabase()
+) -> Result<FetchPlayableStatBlocksForEncounterResponse, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
+
+    fetch_playable_statblocks_from(&repository, encounter_id).await
+}
+
+#[tauri::command]
+pub async fn fetch_encounters(access_token: String) -> Result<FetchEncountersResponse, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
+
+    fetch_encounters_from(&repository).await
+}
+
+pub async fn fetch_encounter_players_from(
+    repository: &impl EncounterRepository,
+    encounter_id: i64,
+) -> Result<FetchEncounterPlayersForEncounterResponse, AppError> {
+    match repository.fetch_encounter_players(encounter_id).await {
+        Ok(encounter_players) => {
+            // Unsynced local changes win over the server copy until they are replayed
+            let cached = has_pending_encounter_changes(encounter_id).and_then(|pending| {
+                if pending {
+                    return Ok(());
+                }
+                replace_local_encounter_players(encounter_id, &encounter_players)
+            });
+            if let Err(e) = cached {
+                eprintln!("Failed to cache EncounterPlayers locally: {}", e);
+            }
+            Ok(FetchEncounterPlayersForEncounterResponse { encounter_players })
+        }
+        Err(e) => {
+            offline_fallback(repository, e, || {
+                let encounter_players = fetch_local_encounter_players(encounter_id)?;
+
+                Ok(FetchEncounterPlayersForEncounterResponse { encounter_players })
+            })
             .await
-            .map_err(|e| FetchPlayableStatBlocksForEncounterResponse {
-                playable_stat_blocks: Vec::new(),
-                status: 500,
-                message: format!("PlayableStatBlock fetch failed: {}", e),
-            })?;
-    let client = reqwest::Client::new();
-    let url = format!(
-        "{}/rest/v1/PlayableStatBlock?encounter_id=eq.{}",
-        config.url, encounter_id
-    );
-
-    let response = client
-        .get(&url)
-        .header("apikey", &config.anon_key)
-        .header("Authorization", format!("Bearer {}", &access_token))
-        .header("Content-Type", "application/json")
-        .send()
-        .await
-        .map_err(|e| FetchPlayableStatBlocksForEncounterResponse {
-            playable_stat_blocks: Vec::new(),
-            status: 500,
-            message: format!("PlayableStatBlock fetch failed: {}", e),
-        })?;
-
-    let status = response.status();
-
-    if !status.is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(FetchPlayableStatBlocksForEncounterResponse {
-            playable_stat_blocks: Vec::new(),
-            status: status.as_u16(),
-            message: format!("PlayableStatBlock fetch failed: {}", error_text),
-        });
+        }
     }
+}
 
-    let playable_stat_blocks =
-        response
-            .json()
-            .await
-            .map_err(|e| FetchPlayableStatBlocksForEncounterResponse {
-                playable_stat_blocks: Vec::new(),
-                status: 500,
-                message: format!(
-                    "Failed to parse PlayableStatBlock response: {}",
-                    e.to_string()
-                ),
-            })?;
+pub async fn fetch_playable_statblocks_from(
+    repository: &impl EncounterRepository,
+    encounter_id: i64,
+) -> Result<FetchPlayableStatBlocksForEncounterResponse, AppError> {
+    match repository.fetch_playable_statblocks(encounter_id).await {
+        Ok(mut playable_stat_blocks) => {
+            let cached = has_pending_encounter_changes(encounter_id).and_then(|pending| {
+                if pending {
+                    return Ok(());
+                }
+                replace_local_playable_statblocks(encounter_id, &mut playable_stat_blocks)
+            });
+            if let Err(e) = cached {
+                eprintln!("Failed to cache PlayableStatBlocks locally: {}", e);
+            }
+            Ok(FetchPlayableStatBlocksForEncounterResponse {
+                playable_stat_blocks,
+            })
+        }
+        Err(e) => {
+            offline_fallback(repository, e, || {
+                let playable_stat_blocks = fetch_local_playable_statblocks(encounter_id)?;
 
-    Ok(FetchPlayableStatBlocksForEncounterResponse {
-        playable_stat_blocks,
-        status: status.as_u16(),
-        message: format!("Successfully fetched PlayableStatBlocks"),
-    })
+                Ok(FetchPlayableStatBlocksForEncounterResponse {
+                    playable_stat_blocks,
+                })
+            })
+            .await
+        }
+    }
 }
 
-#[tauri::command]
-pub async fn fetch_encounters(
-    access_token: String,
-) -> Result<FetchEncountersResponse, FetchEncountersResponse> {
-    let config = init_supabase().await.map_err(|e| FetchEncountersResponse {
-        encounters: Vec::new(),
-        status: 500,
-        message: format!("Encounter fetch failed: {}", e),
-    })?;
-    let client = reqwest::Client::new();
-    let url = format!("{}/rest/v1/Encounter", config.url);
-
-    let response = client
-        .get(&url)
-        .header("apikey", &config.anon_key)
-        .header("Authorization", format!("Bearer {}", &access_token))
-        .header("Content-Type", "application/json")
-        .send()
-        .await
-        .map_err(|e| FetchEncountersResponse {
-            encounters: Vec::new(),
-            status: 500,
-            message: format!("Encounter fetch failed: {}", e),
-        })?;
-
-    let status = response.status();
-
-    if !status.is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(FetchEncountersResponse {
-            encounters: Vec::new(),
-            status: status.as_u16(),
-            message: format!("Encounter fetch failed: {}", error_text),
-        });
-    }
+pub async fn fetch_encounters_from(
+    repository: &impl EncounterRepository,
+) -> Result<FetchEncountersResponse, AppError> {
+    match repository.fetch_encounters().await {
+        Ok(encounters) => {
+            if let Err(e) = replace_local_encounters(&encounters) {
+                eprintln!("Failed to cache Encounters locally: {}", e);
+            }
+            Ok(FetchEncountersResponse { encounters })
+        }
+        Err(e) => {
+            offline_fallback(repository, e, || {
+                let encounters = fetch_local_encounters()?;
 
-    let encounters = response.json().await.map_err(|e| FetchEncountersResponse {
-        encounters: Vec::new(),
-        status: 500,
-        message: format!("Failed to parse Encounter response: {}", e.to_string()),
-    })?;
+                Ok(FetchEncountersResponse { encounters })
+            })
+            .await
+        }
+    }
+}
 
-    Ok(FetchEncountersResponse {
-        encounters,
-        status: status.as_u16(),
-        message: format!("Successfully fetched Encounters"),
-    })
+pub async fn fetch_encounter_by_id(
+    repository: &impl EncounterRepository,
+    encounter_id: i64,
+) -> Result<Option<Encounter>, AppError> {
+    match repository.fetch_encounter(encounter_id).await {
+        Ok(encounter) => Ok(encounter),
+        Err(e) => offline_fallback(repository, e, || fetch_local_encounter(encounter_id)).await,
+    }
 }
 
 //? UPSERT
@@ -227,214 +196,858 @@ pub async fn fetch_encounters(
 pub async fn save_encounter_players(
     encounter_players: Vec<EncounterPlayer>,
     access_token: String,
-) -> Result<SaveEncounterPlayersResponse, String> {
-    let config = init_supabase().await.map_err(|e| e.to_string())?;
-    let client = reqwest::Client::new();
+) -> Result<SaveEncounterPlayersResponse, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
 
-    if let Some(first_player) = encounter_players.first() {
-        let delete_url = format!(
-            "{}/rest/v1/EncounterPlayer?encounter_id=eq.{}",
-            config.url, first_player.encounter_id
-        );
+    save_encounter_players_to(&repository, encounter_players).await
+}
 
-        let delete_response = client
-            .delete(&delete_url)
-            .header("apikey", &config.anon_key)
-            .header("Authorization", format!("Bearer {}", &access_token))
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
+#[tauri::command]
+pub async fn save_playable_statblocks(
+    playable_stat_blocks: Vec<PlayableStatBlock>,
+    access_token: String,
+) -> Result<SavePlayableStatBlocksResponse, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
 
-        if !delete_response.status().is_success() {
-            let error_text = delete_response.text().await.unwrap_or_default();
-            return Err(format!(
-                "Failed to delete existing encounter players: {}",
-                error_text
-            ));
+    save_playable_statblocks_to(&repository, playable_stat_blocks).await
+}
+
+#[tauri::command]
+pub async fn save_encounter(
+    encounter: Encounter,
+    access_token: String,
+    last_seen: Option<String>,
+) -> Result<SaveEncounterResponse, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
+
+    save_encounter_to(&repository, encounter, last_seen).await
+}
+
+pub async fn save_encounter_players_to(
+    repository: &impl EncounterRepository,
+    encounter_players: Vec<EncounterPlayer>,
+) -> Result<SaveEncounterPlayersResponse, AppError> {
+    let saved = SaveEncounterPlayersResponse {
+        message: "EncounterPlayers saved successfully".to_string(),
+    };
+    let Some(encounter_id) = encounter_players.first().map(|player| player.encounter_id) else {
+        return Ok(saved);
+    };
+
+    // Players of an Encounter that only exists locally wait for it to sync first
+    let result = if encounter_id < 0 {
+        None
+    } else {
+        Some(
+            repository
+                .save_encounter_players(encounter_players.clone())
+                .await,
+        )
+    };
+
+    match result {
+        Some(Ok(())) => {
+            if let Err(e) = replace_local_encounter_players(encounter_id, &encounter_players) {
+                eprintln!("Failed to cache EncounterPlayers locally: {}", e);
+            }
+            Ok(saved)
+        }
+        Some(Err(e)) if !repository.is_offline().await => Err(e),
+        _ => {
+            replace_local_encounter_players(encounter_id, &encounter_players)?;
+            queue_offline_change(SyncOperation::SaveEncounterPlayers { encounter_id })?;
+
+            Ok(SaveEncounterPlayersResponse {
+                message: "EncounterPlayers saved locally while offline".to_string(),
+            })
         }
     }
+}
 
-    for player in encounter_players {
-        let insert_obj = player.clone();
-        let insert_url = format!("{}/rest/v1/EncounterPlayer", config.url);
-
-        let response = client
-            .post(&insert_url)
-            .header("apikey", &config.anon_key)
-            .header("Authorization", format!("Bearer {}", &access_token))
-            .header("Content-Type", "application/json")
-            .body(serde_json::to_string(&vec![insert_obj]).map_err(|e| e.to_string())?)
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
+pub async fn save_playable_statblocks_to(
+    repository: &(impl EncounterRepository + StatBlockRepository),
+    mut playable_stat_blocks: Vec<PlayableStatBlock>,
+) -> Result<SavePlayableStatBlocksResponse, AppError> {
+    let saved = SavePlayableStatBlocksResponse {
+        message: "PlayableStatBlocks saved successfully".to_string(),
+    };
 
-        let status = response.status();
-        let text = response.text().await.map_err(|e| e.to_string())?;
+    // Hit points are settled before anything is deleted so a bad hit dice string can't drop the encounter's monsters
+    assign_playable_statblock_hp(repository, &mut playable_stat_blocks).await?;
 
-        if !status.is_success() {
-            return Err(format!("Supabase insert error {}: {}", status, text));
+    let Some(encounter_id) = playable_stat_blocks
+        .first()
+        .map(|playable| playable.encounter_id)
+    else {
+        return Ok(saved);
+    };
+
+    // Anything pointing at a row that only exists locally waits for that row to sync first
+    let has_local_references = encounter_id < 0
+        || playable_stat_blocks
+            .iter()
+            .any(|playable| playable.statblock_id < 0);
+
+    let result = if has_local_references {
+        None
+    } else {
+        Some(
+            repository
+                .save_playable_statblocks(playable_stat_blocks.clone())
+                .await,
+        )
+    };
+
+    match result {
+        Some(Ok(())) => {
+            // Existing instances keep their ids, but new ones only get theirs from Supabase, so the cache is refreshed from the server copy
+            if let Err(e) = fetch_playable_statblocks_from(repository, encounter_id).await {
+                eprintln!("Failed to cache PlayableStatBlocks locally: {}", e);
+            }
+            Ok(saved)
+        }
+        Some(Err(e)) if !repository.is_offline().await => Err(e),
+        _ => {
+            replace_local_playable_statblocks(encounter_id, &mut playable_stat_blocks)?;
+            queue_offline_change(SyncOperation::SavePlayableStatBlocks { encounter_id })?;
+
+            Ok(SavePlayableStatBlocksResponse {
+                message: "PlayableStatBlocks saved locally while offline".to_string(),
+            })
         }
     }
-    return Ok(SaveEncounterPlayersResponse {
-        message: "EncounterPlayers saved successfully".to_string(),
+}
+
+pub async fn save_encounter_to(
+    repository: &impl EncounterRepository,
+    mut encounter: Encounter,
+    last_seen: Option<String>,
+) -> Result<SaveEncounterResponse, AppError> {
+    let temporary_id = encounter.id.filter(|id| *id < 0);
+
+    match repository
+        .save_encounter(encounter.clone(), last_seen.as_deref())
+        .await
+    {
+        Ok(response) if response.conflict.is_some() => Ok(response),
+        Ok(response) => {
+            let cached = match temporary_id {
+                Some(temporary_id) => remap_temporary_id("Encounter", temporary_id, response.id),
+                None => Ok(()),
+            };
+            encounter.id = Some(response.id);
+            encounter.last_modified = response.last_modified;
+            if let Err(e) = cached.and_then(|_| save_local_encounter(&mut encounter)) {
+                eprintln!("Failed to cache Encounter locally: {}", e);
+            }
+            Ok(response)
+        }
+        Err(e) => {
+            offline_fallback(repository, e, || {
+                let was_updated = encounter.id.is_some();
+                let base_last_modified = match encounter.id.filter(|id| *id > 0) {
+                    Some(_) if last_seen.is_some() => last_seen,
+                    Some(id) => local_last_modified("Encounter", id)?,
+                    None => None,
+                };
+                let id = save_local_encounter(&mut encounter)?;
+                queue_offline_change(SyncOperation::SaveEncounter {
+                    encounter_id: id,
+                    base_last_modified,
+                })?;
+
+                Ok(SaveEncounterResponse {
+                    id,
+                    status: 202,
+                    message: "Encounter saved locally while offline".to_string(),
+                    was_updated,
+                    last_modified: encounter.last_modified,
+                    conflict: None,
+                })
+            })
+            .await
+        }
+    }
+}
+
+pub async fn update_combatant_hp(
+    repository: &impl EncounterRepository,
+    combatant: &CombatantRef,
+    encounter_id: i64,
+    current_hp: u16,
+    temporary_hp: u16,
+) -> Result<String, AppError> {
+    let body = serde_json::json!({
+        "current_hp": current_hp,
+        "temporary_hp": temporary_hp,
     });
+
+    patch_combatant(repository, combatant, encounter_id, &body).await?;
+
+    Ok(format!("{} hit points updated", combatant.table_name()))
+}
+
+pub async fn update_combatant_conditions(
+    repository: &impl EncounterRepository,
+    combatant: &CombatantRef,
+    encounter_id: i64,
+    conditions: &[ActiveCondition],
+) -> Result<String, AppError> {
+    let body = serde_json::json!({ "conditions": conditions });
+
+    patch_combatant(repository, combatant, encounter_id, &body).await?;
+
+    Ok(format!("{} conditions updated", combatant.table_name()))
 }
 
+//? Delete
+
 #[tauri::command]
-pub async fn save_playable_statblocks(
-    playable_stat_blocks: Vec<PlayableStatBlock>,
-    access_token: String,
-) -> Result<SavePlayableStatBlocksResponse, String> {
-    let config = init_supabase().await.map_err(|e| e.to_string())?;
-    let client = reqwest::Client::new();
+pub async fn delete_encounter(encounter_id: i64, access_token: String) -> Result<String, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
+
+    delete_encounter_from(&repository, encounter_id).await
+}
 
-    if let Some(first_statblock) = playable_stat_blocks.first() {
+pub async fn delete_encounter_from(
+    repository: &impl EncounterRepository,
+    encounter_id: i64,
+) -> Result<String, AppError> {
+    let offline = if encounter_id < 0 {
+        // Never reached Supabase, so there is nothing to delete there
+        true
+    } else {
+        match repository.delete_encounter(encounter_id).await {
+            Ok(_) => false,
+            Err(_) if repository.is_offline().await => true,
+            Err(e) => return Err(e),
+        }
+    };
+
+    if offline {
+        queue_offline_change(SyncOperation::DeleteEncounter { encounter_id })?;
+        delete_local_encounter(encounter_id)?;
+        return Ok("Encounter deleted locally".to_string());
+    }
+
+    if let Err(e) = delete_local_encounter(encounter_id) {
+        eprintln!("Failed to delete local Encounter: {}", e);
+    }
+
+    Ok("Encounter deleted successfully".to_string())
+}
+
+//? Supabase
+
+impl EncounterRepository for SupabaseRepository {
+    async fn fetch_encounters(&self) -> Result<Vec<Encounter>, AppError> {
+        let url = format!("{}/rest/v1/Encounter", self.client.config.url);
+
+        self.fetch_rows("Encounter", &url).await
+    }
+
+    async fn fetch_encounter(&self, encounter_id: i64) -> Result<Option<Encounter>, AppError> {
+        let url = format!(
+            "{}/rest/v1/Encounter?id=eq.{}",
+            self.client.config.url, encounter_id
+        );
+
+        let encounters: Vec<Encounter> = self.fetch_rows("Encounter", &url).await?;
+        Ok(encounters.into_iter().next())
+    }
+
+    // With last_seen the PATCH only matches while the row is unchanged
+    async fn save_encounter(
+        &self,
+        mut encounter: Encounter,
+        last_seen: Option<&str>,
+    ) -> Result<SaveEncounterResponse, AppError> {
+        // Temporary ids from the local store have no row on the server yet
+        let existing_id = encounter.id.filter(|id| *id > 0);
+        if existing_id.is_none() {
+            encounter.id = None;
+        }
+        let insert_obj = encounter.clone();
+
+        let (method, url) = if let Some(id) = existing_id {
+            (
+                reqwest::Method::PATCH,
+                format!(
+                    "{}/rest/v1/Encounter?id=eq.{}{}",
+                    self.client.config.url,
+                    id,
+                    last_modified_filter(last_seen)
+                ),
+            )
+        } else {
+            (
+                reqwest::Method::POST,
+                format!("{}/rest/v1/Encounter", self.client.config.url),
+            )
+        };
+
+        let response = self
+            .client
+            .send(|client| {
+                client
+                    .request(method.clone(), &url)
+                    .header("Prefer", "return=representation")
+                    .json(&vec![&insert_obj])
+            })
+            .await?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let context = if method == reqwest::Method::PATCH {
+                "Encounter update failed"
+            } else {
+                "Encounter insert failed"
+            };
+            return Err(AppError::from_response(context, response).await);
+        }
+
+        // last_modified comes back as the trigger on Encounter stamped it
+        let returned_records: Vec<Encounter> = response
+            .json()
+            .await
+            .map_err(|e| AppError::parse(format!("Failed to parse response: {}", e)))?;
+
+        if let Some(record) = returned_records.first() {
+            let id = record
+                .id
+                .ok_or(AppError::parse("No ID returned from Supabase"))?;
+
+            return Ok(SaveEncounterResponse {
+                id,
+                status: status.as_u16(),
+                message: if method == reqwest::Method::PATCH {
+                    "Encounter updated successfully".to_string()
+                } else {
+                    "Encounter created successfully".to_string()
+                },
+                was_updated: method == reqwest::Method::PATCH,
+                last_modified: record.last_modified,
+                conflict: None,
+            });
+        }
+
+        // Nothing written for an existing id means the row is gone or changed since last_seen
+        if let Some(id) = existing_id {
+            if last_seen.is_none() {
+                return Err(AppError::not_found(format!(
+                    "Encounter {} no longer exists on the server",
+                    id
+                )));
+            }
+
+            let server = self
+                .fetch_encounter(id)
+                .await?
+                .ok_or(AppError::not_found(format!(
+                    "Encounter {} no longer exists on the server",
+                    id
+                )))?;
+
+            return encounter_conflict(id, encounter, server);
+        }
+
+        Err(AppError::storage("No data returned from Supabase"))
+    }
+
+    async fn delete_encounter(&self, encounter_id: i64) -> Result<(), AppError> {
         let delete_url = format!(
+            "{}/rest/v1/Encounter?id=eq.{}",
+            self.client.config.url, encounter_id
+        );
+
+        let response = self
+            .client
+            .send(|client| client.delete(&delete_url))
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(AppError::from_response("Encounter delete failed", response).await);
+        }
+
+        Ok(())
+    }
+
+    async fn fetch_encounter_players(
+        &self,
+        encounter_id: i64,
+    ) -> Result<Vec<EncounterPlayer>, AppError> {
+        let url = format!(
+            "{}/rest/v1/EncounterPlayer?encounter_id=eq.{}",
+            self.client.config.url, encounter_id
+        );
+
+        self.fetch_rows("EncounterPlayer", &url).await
+    }
+
+    async fn save_encounter_players(
+        &self,
+        encounter_players: Vec<EncounterPlayer>,
+    ) -> Result<(), AppError> {
+        let Some(first_player) = encounter_players.first() else {
+            return Ok(());
+        };
+
+        self.replace_rows(
+            "EncounterPlayer",
+            first_player.encounter_id,
+            &encounter_players,
+        )
+        .await
+    }
+
+    async fn fetch_playable_statblocks(
+        &self,
+        encounter_id: i64,
+    ) -> Result<Vec<PlayableStatBlock>, AppError> {
+        let url = format!(
             "{}/rest/v1/PlayableStatBlock?encounter_id=eq.{}",
-            config.url, first_statblock.encounter_id
+            self.client.config.url, encounter_id
         );
 
-        let delete_response = client
-            .delete(&delete_url)
-            .header("apikey", &config.anon_key)
-            .header("Authorization", format!("Bearer {}", &access_token))
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
+        self.fetch_rows("PlayableStatBlock", &url).await
+    }
 
-        if !delete_response.status().is_success() {
-            let error_text = delete_response.text().await.unwrap_or_default();
-            return Err(format!(
-                "Failed to delete existing playable statblocks: {}",
-                error_text
-            ));
+    async fn save_playable_statblocks(
+        &self,
+        playable_stat_blocks: Vec<PlayableStatBlock>,
+    ) -> Result<(), AppError> {
+        let Some(first_statblock) = playable_stat_blocks.first() else {
+            return Ok(());
+        };
+
+        self.replace_rows(
+            "PlayableStatBlock",
+            first_statblock.encounter_id,
+            &playable_stat_blocks,
+        )
+        .await
+    }
+
+    async fn patch_combatant(
+        &self,
+        combatant: &CombatantRef,
+        encounter_id: i64,
+        body: &serde_json::Value,
+    ) -> Result<(), AppError> {
+        let table_name = combatant.table_name();
+        let filter = match combatant {
+            CombatantRef::Player(name) => format!(
+                "encounter_id=eq.{}&name=eq.{}",
+                encounter_id,
+                urlencoding::encode(name)
+            ),
+            CombatantRef::Monster(id) => format!("id=eq.{}", id),
+        };
+
+        let url = format!(
+            "{}/rest/v1/{}?{}",
+            self.client.config.url, table_name, filter
+        );
+
+        let response = self
+            .client
+            .send(|client| client.patch(&url).json(body))
+            .await?;
+
+        if !response.status().is_success() {
+            let context = format!("{} update failed", table_name);
+            return Err(AppError::from_response(&context, response).await);
         }
+
+        Ok(())
     }
+}
+
+impl SupabaseRepository {
+    async fn fetch_rows<T: DeserializeOwned>(
+        &self,
+        table_name: &str,
+        url: &str,
+    ) -> Result<Vec<T>, AppError> {
+        let response = self.client.send(|client| client.get(url)).await?;
+
+        if !response.status().is_success() {
+            let context = format!("{} fetch failed", table_name);
+            return Err(AppError::from_response(&context, response).await);
+        }
 
-    for statblock in playable_stat_blocks {
-        let insert_obj = statblock.clone();
-        let insert_url = format!("{}/rest/v1/PlayableStatBlock", config.url);
-
-        let response = client
-            .post(&insert_url)
-            .header("apikey", &config.anon_key)
-            .header("Authorization", format!("Bearer {}", &access_token))
-            .header("Content-Type", "application/json")
-            .body(serde_json::to_string(&vec![insert_obj]).map_err(|e| e.to_string())?)
-            .send()
+        response
+            .json()
             .await
-            .map_err(|e| format!("Request failed: {}", e))?;
+            .map_err(|e| AppError::parse(format!("Failed to parse {} response: {}", table_name, e)))
+    }
 
-        let status = response.status();
-        let text = response.text().await.map_err(|e| e.to_string())?;
+    // Deletes every row of the Encounter before inserting the new ones in a single request
+    async fn replace_rows<T: Serialize>(
+        &self,
+        table_name: &str,
+        encounter_id: i64,
+        rows: &[T],
+    ) -> Result<(), AppError> {
+        let delete_url = format!(
+            "{}/rest/v1/{}?encounter_id=eq.{}",
+            self.client.config.url, table_name, encounter_id
+        );
 
-        if !status.is_success() {
-            return Err(format!("Supabase insert error {}: {}", status, text));
+        let delete_response = self
+            .client
+            .send(|client| client.delete(&delete_url))
+            .await?;
+
+        if !delete_response.status().is_success() {
+            let context = format!("Failed to delete existing {} rows", table_name);
+            return Err(AppError::from_response(&context, delete_response).await);
+        }
+
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let rows = serde_json::to_value(rows).map_err(|e| AppError::parse(e.to_string()))?;
+
+        // PostgREST takes a bulk insert's columns from the first row unless they are listed, so
+        // every key of every row is listed and the keys a row leaves out fall back to their defaults
+        let columns: BTreeSet<&str> = rows
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|row| row.as_object())
+            .flat_map(|row| row.keys().map(String::as_str))
+            .collect();
+
+        let insert_url = format!(
+            "{}/rest/v1/{}?columns={}",
+            self.client.config.url,
+            table_name,
+            columns.into_iter().collect::<Vec<_>>().join(",")
+        );
+        let response = self
+            .client
+            .send(|client| {
+                client
+                    .post(&insert_url)
+                    .header("Prefer", "missing=default")
+                    .json(&rows)
+            })
+            .await?;
+
+        if !response.status().is_success() {
+            let context = format!("{} insert failed", table_name);
+            return Err(AppError::from_response(&context, response).await);
         }
+
+        Ok(())
     }
-    return Ok(SavePlayableStatBlocksResponse {
-        message: "PlayableStatBlocks saved successfully".to_string(),
-    });
 }
 
-#[tauri::command]
-pub async fn save_encounter(
-    mut encounter: Encounter,
-    access_token: String,
-) -> Result<SaveEncounterResponse, String> {
-    let config = init_supabase().await.map_err(|e| e.to_string())?;
-    let client = reqwest::Client::new();
-    let insert_obj = encounter.clone();
-
-    let (method, url) = if let Some(id) = encounter.id {
-        (
-            reqwest::Method::PATCH,
-            format!("{}/rest/v1/Encounter?id=eq.{}", config.url, id),
-        )
-    } else {
-        (
-            reqwest::Method::POST,
-            format!("{}/rest/v1/Encounter", config.url),
-        )
+//? Helper Util
+
+pub fn encounter_conflict(
+    id: i64,
+    local: Encounter,
+    server: Encounter,
+) -> Result<SaveEncounterResponse, AppError> {
+    Ok(SaveEncounterResponse {
+        id,
+        status: 409,
+        message: format!(
+            "Encounter was changed by someone else at {}",
+            server.last_modified
+        ),
+        was_updated: false,
+        last_modified: server.last_modified,
+        conflict: Some(EncounterConflict::new(local, server)?),
+    })
+}
+
+async fn fetch_encounter_hp_mode(
+    repository: &impl EncounterRepository,
+    encounter_id: i64,
+) -> Result<HpMode, AppError> {
+    let encounter = fetch_encounter_by_id(repository, encounter_id).await?;
+
+    Ok(encounter
+        .and_then(|encounter| encounter.hp_mode)
+        .unwrap_or_default())
+}
+
+async fn assign_playable_statblock_hp(
+    repository: &(impl EncounterRepository + StatBlockRepository),
+    playable_stat_blocks: &mut [PlayableStatBlock],
+) -> Result<(), AppError> {
+    // Only instances that have never been saved get hit points, existing rows keep their stored values
+    let mut new_statblock_ids: Vec<i64> = playable_stat_blocks
+        .iter()
+        .filter(|playable| playable.id.is_none() && playable.max_hp.is_none())
+        .map(|playable| playable.statblock_id)
+        .collect();
+
+    let Some(first_statblock) = playable_stat_blocks.first() else {
+        return Ok(());
     };
 
-    let response = client
-        .request(method.clone(), &url)
-        .header("apikey", &config.anon_key)
-        .header("Authorization", format!("Bearer {}", &access_token))
-        .header("Content-Type", "application/json")
-        .header("Prefer", "return=representation")
-        .body(serde_json::to_string(&vec![insert_obj]).map_err(|e| e.to_string())?)
-        .send()
+    if new_statblock_ids.is_empty() {
+        return Ok(());
+    }
+
+    new_statblock_ids.sort_unstable();
+    new_statblock_ids.dedup();
+
+    let hp_mode = fetch_encounter_hp_mode(repository, first_statblock.encounter_id).await?;
+    let statblocks: HashMap<i64, _> = fetch_statblocks_by_ids(repository, &new_statblock_ids)
+        .await?
+        .into_iter()
+        .filter_map(|statblock| statblock.id.map(|id| (id, statblock)))
+        .collect();
+
+    let mut rng = dice_rng(None);
+
+    for playable in playable_stat_blocks
+        .iter_mut()
+        .filter(|playable| playable.id.is_none() && playable.max_hp.is_none())
+    {
+        let statblock = statblocks
+            .get(&playable.statblock_id)
+            .ok_or(AppError::not_found(format!(
+                "PlayableStatBlock save failed: StatBlock {} not found",
+                playable.statblock_id
+            )))?;
+
+        let hp = roll_hit_points(statblock, hp_mode, &mut rng)?;
+        playable.max_hp = Some(hp);
+        playable.current_hp = hp;
+    }
+
+    Ok(())
+}
+
+async fn patch_combatant(
+    repository: &impl EncounterRepository,
+    combatant: &CombatantRef,
+    encounter_id: i64,
+    body: &serde_json::Value,
+) -> Result<(), AppError> {
+    match repository
+        .patch_combatant(combatant, encounter_id, body)
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+    {
+        Ok(()) => {
+            if let Err(e) = update_local_combatant(combatant, encounter_id, body) {
+                eprintln!("Failed to update local {}: {}", combatant.table_name(), e);
+            }
+            Ok(())
+        }
+        Err(e) => {
+            offline_fallback(repository, e, || {
+                update_local_combatant(combatant, encounter_id, body)?;
+                queue_offline_change(SyncOperation::UpdateCombatant {
+                    encounter_id,
+                    combatant_key: combatant.key(),
+                })
+            })
+            .await
+        }
+    }
+}
 
-    let status = response.status();
-    let text = response.text().await.map_err(|e| e.to_string())?;
+#[cfg(test)]
+mod tests {
+    use mockito::Matcher;
+    use serde_json::json;
 
-    if !status.is_success() {
-        return Err(format!(
-            "Supabase {} error {}: {}",
-            if method == reqwest::Method::PATCH {
-                "update"
-            } else {
-                "insert"
-            },
-            status,
-            text
-        ));
+    use super::*;
+    use crate::database::memory_repository::InMemoryRepository;
+
+    fn encounter_row(name: &str, last_modified: &str) -> serde_json::Value {
+        json!({
+            "id": 7,
+            "name": name,
+            "user_id": "user",
+            "last_modified": last_modified,
+            "hp_mode": "Average",
+        })
+    }
+
+    fn local_encounter(name: &str) -> Encounter {
+        serde_json::from_value(encounter_row(name, "2026-10-18T12:00:00Z")).unwrap()
     }
 
-    let returned_records: Vec<serde_json::Value> =
-        serde_json::from_str(&text).map_err(|e| format!("Failed to parse response: {}", e))?;
+    #[tokio::test]
+    async fn update_returns_the_last_modified_the_server_stamped() {
+        let mut server = mockito::Server::new_async().await;
+        let patch = server
+            .mock("PATCH", "/rest/v1/Encounter")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("id".into(), "eq.7".into()),
+                Matcher::UrlEncoded("last_modified".into(), "eq.2026-10-18T12:00:00Z".into()),
+            ]))
+            .with_body(json!([encounter_row("Goblin Camp", "2026-10-18T12:30:00Z")]).to_string())
+            .create_async()
+            .await;
+        let repository = SupabaseRepository::at(server.url());
 
-    if let Some(record) = returned_records.first() {
-        let id = record
-            .get("id")
-            .and_then(|v| v.as_i64())
-            .ok_or("No ID returned from Supabase")?;
+        let response = repository
+            .save_encounter(local_encounter("Goblin Camp"), Some("2026-10-18T12:00:00Z"))
+            .await
+            .unwrap();
 
-        //? Encounter must exist now
+        patch.assert_async().await;
+        assert!(response.was_updated);
+        assert!(response.conflict.is_none());
+        assert_eq!(
+            response.last_modified.to_rfc3339(),
+            "2026-10-18T12:30:00+00:00"
+        );
+    }
 
-        encounter.id = Some(id);
+    #[tokio::test]
+    async fn stale_update_returns_a_conflict_with_the_server_copy() {
+        let mut server = mockito::Server::new_async().await;
+        // Nothing matched the last_modified filter, so nothing was written
+        server
+            .mock("PATCH", "/rest/v1/Encounter")
+            .match_query(Matcher::Any)
+            .with_body("[]")
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/rest/v1/Encounter")
+            .match_query(Matcher::UrlEncoded("id".into(), "eq.7".into()))
+            .with_body(json!([encounter_row("Goblin Camp", "2026-10-18T12:05:00Z")]).to_string())
+            .create_async()
+            .await;
+        let repository = SupabaseRepository::at(server.url());
 
-        return Ok(SaveEncounterResponse {
-            id,
-            status: status.as_u16(),
-            message: if method == reqwest::Method::PATCH {
-                "Encounter updated successfully".to_string()
-            } else {
-                "Encounter created successfully".to_string()
-            },
-            was_updated: method == reqwest::Method::PATCH,
-        });
+        let response = repository
+            .save_encounter(
+                local_encounter("Goblin Ambush"),
+                Some("2026-10-18T12:00:00Z"),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, 409);
+        assert!(!response.was_updated);
+        assert_eq!(
+            response.last_modified.to_rfc3339(),
+            "2026-10-18T12:05:00+00:00"
+        );
+
+        let conflict = response.conflict.unwrap();
+        assert_eq!(conflict.local.name, "Goblin Ambush");
+        assert_eq!(conflict.server.name, "Goblin Camp");
+        assert_eq!(conflict.differences.len(), 1);
+        assert_eq!(conflict.differences[0].field, "name");
     }
 
-    Err("No data returned from Supabase".to_string())
-}
+    #[tokio::test]
+    async fn stale_update_of_a_deleted_encounter_is_not_found() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("PATCH", "/rest/v1/Encounter")
+            .match_query(Matcher::Any)
+            .with_body("[]")
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/rest/v1/Encounter")
+            .match_query(Matcher::Any)
+            .with_body("[]")
+            .create_async()
+            .await;
+        let repository = SupabaseRepository::at(server.url());
 
-//? Delete
+        let result = repository
+            .save_encounter(
+                local_encounter("Goblin Ambush"),
+                Some("2026-10-18T12:00:00Z"),
+            )
+            .await;
 
-#[tauri::command]
-pub async fn delete_encounter(encounter_id: i64, access_token: String) -> Result<String, String> {
-    let config = init_supabase().await.map_err(|e| e.to_string())?;
-    let client = reqwest::Client::new();
-    let delete_url = format!("{}/rest/v1/Encounter?id=eq.{}", config.url, encounter_id);
-
-    let response = client
-        .delete(&delete_url)
-        .header("apikey", &config.anon_key)
-        .header("Authorization", format!("Bearer {}", &access_token))
-        .header("Content-Type", "application/json")
-        .send()
-        .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+        assert!(matches!(result, Err(AppError::NotFound { .. })));
+    }
 
-    let status = response.status();
+    #[tokio::test]
+    async fn update_of_a_deleted_encounter_is_not_found() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("PATCH", "/rest/v1/Encounter")
+            .match_query(Matcher::Any)
+            .with_body("[]")
+            .create_async()
+            .await;
+        let repository = SupabaseRepository::at(server.url());
 
-    if !status.is_success() {
-        return Err("Supabase delete error {}: {}".to_string());
+        let result = repository
+            .save_encounter(local_encounter("Goblin Ambush"), None)
+            .await;
+
+        assert!(matches!(result, Err(AppError::NotFound { .. })));
     }
 
-    return Ok("Encounter deleted successfully".to_string());
+    #[tokio::test]
+    async fn replacing_rows_lists_the_columns_of_every_row() {
+        let mut server = mockito::Server::new_async().await;
+        let delete = server
+            .mock("DELETE", "/rest/v1/PlayableStatBlock")
+            .match_query(Matcher::UrlEncoded("encounter_id".into(), "eq.7".into()))
+            .expect(1)
+            .create_async()
+            .await;
+        let insert = server
+            .mock("POST", "/rest/v1/PlayableStatBlock")
+            .match_query(Matcher::UrlEncoded(
+                "columns".into(),
+                "conditions,current_hp,encounter_id,id,max_hp,statblock_id,temporary_hp".into(),
+            ))
+            .match_header("Prefer", "missing=default")
+            .expect(1)
+            .create_async()
+            .await;
+        let repository = SupabaseRepository::at(server.url());
+
+        // The first row has no id yet, the second keeps its own
+        let playable = |id: Option<i64>| PlayableStatBlock {
+            id,
+            max_hp: Some(7),
+            current_hp: 7,
+            temporary_hp: 0,
+            initiative: None,
+            name: None,
+            conditions: vec![],
+            statblock_id: 3,
+            encounter_id: 7,
+        };
+
+        repository
+            .save_playable_statblocks(vec![playable(None), playable(Some(12))])
+            .await
+            .unwrap();
+
+        delete.assert_async().await;
+        insert.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn fetch_responses_carry_only_their_rows() {
+        let repository = InMemoryRepository::new();
+        let mut encounter = local_encounter("Goblin Camp");
+        encounter.id = None;
+        repository.save_encounter(encounter, None).await.unwrap();
+
+        let response = fetch_encounters_from(&repository).await.unwrap();
+        let fields = serde_json::to_value(&response).unwrap();
+
+        assert_eq!(
+            fields.as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["encounters"]
+        );
+        assert_eq!(response.encounters[0].name, "Goblin Camp");
+    }
 }
@@ -0,0 +1,851 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, OnceLock},
+};
+
+use rusqlite::{
+    params_from_iter,
+    types::{Value as SqlValue, ValueRef},
+    Connection, Params,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use chrono::Utc;
+
+use crate::types::{
+    combat_types::CombatantRef,
+    encounter_types::{Encounter, EncounterPlayer, PlayableStatBlock},
+    error_types::AppError,
+    statblock_types::{StatBlock, StatBlockFromDB},
+    sync_types::{PendingSyncOperation, SyncOperation},
+};
+
+static LOCAL_STORE: OnceLock<Mutex<LocalStore>> = OnceLock::new();
+
+const LOCAL_SCHEMA: &str = include_str!("local_schema.sql");
+
+// Stored as JSON text, every other column holds a plain value
+const JSON_COLUMNS: [&str; 3] = ["attack", "conditions", "operation"];
+
+// Same shape as STATBLOCK_JOIN_QUERY so rows deserialize through StatBlockFromDB
+const STATBLOCK_CHILD_TABLES: [(&str, &str); 12] = [
+    ("Action", "name, description, attack"),
+    ("BonusAction", "name, description, attack"),
+    ("Reaction", "name, description, attack"),
+    ("LegendaryAction", "name, description, attack"),
+    ("DamageResistance", "damage_type"),
+    ("DamageImmunity", "damage_type"),
+    ("DamageVulnerability", "damage_type"),
+    ("ConditionImmunity", "condition_type"),
+    ("Trait", "name, description"),
+    ("SaveProficiency", "score, level"),
+    ("SkillProficiency", "ability, level"),
+    ("Spells", "name, spell_list"),
+];
+
+// Every user gets their own database file, only the logged in user's is open
+struct LocalStore {
+    dir: PathBuf,
+    open: Option<(String, Connection)>,
+}
+
+pub fn init_local_store(dir: &Path) -> Result<(), AppError> {
+    fs::create_dir_all(dir)
+        .map_err(|e| AppError::storage(format!("Failed to create local store dir: {}", e)))?;
+
+    LOCAL_STORE
+        .set(Mutex::new(LocalStore {
+            dir: dir.to_path_buf(),
+            open: None,
+        }))
+        .map_err(|_| AppError::storage("Local store is already open"))
+}
+
+// Switches to user_id's database, creating it the first time that user is seen
+pub fn open_user_store(user_id: &str) -> Result<(), AppError> {
+    if user_id.is_empty()
+        || !user_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return Err(AppError::validation(
+            "user_id",
+            "Local store can't be opened for this user",
+        ));
+    }
+
+    let mut store = lock_store()?;
+    if store
+        .open
+        .as_ref()
+        .is_some_and(|(open_user_id, _)| open_user_id == user_id)
+    {
+        return Ok(());
+    }
+
+    let conn = open_connection(&store.dir.join(format!("{}.db", user_id)))?;
+    store.open = Some((user_id.to_string(), conn));
+    Ok(())
+}
+
+// Called on logout so nothing reads the previous user's rows until the next login opens theirs
+pub fn close_local_store() -> Result<(), AppError> {
+    lock_store()?.open = None;
+    Ok(())
+}
+
+//? StatBlock
+
+// Rows without an id get a temporary negative one until they reach Supabase
+pub fn save_local_statblock(stat_block: &mut StatBlock) -> Result<i64, AppError> {
+    with_store(|conn| {
+        let tx = conn.transaction().map_err(sql_error)?;
+
+        let id = match stat_block.id {
+            Some(id) => id,
+            None => next_temporary_id(&tx, "StatBlock")?,
+        };
+        stat_block.id = Some(id);
+
+        write_statblock(&tx, stat_block)?;
+        tx.commit().map_err(sql_error)?;
+
+        Ok(id)
+    })
+}
+
+// Mirrors a full fetch from Supabase, keeping rows that only exist locally or have unsynced changes
+pub fn replace_local_statblocks(statblocks: &[StatBlock]) -> Result<(), AppError> {
+    with_store(|conn| {
+        let tx = conn.transaction().map_err(sql_error)?;
+        let pending = pending_ids(&tx, "StatBlock")?;
+
+        for id in select_ids(&tx, "select id from \"StatBlock\" where id > 0")? {
+            if !pending.contains(&id) {
+                tx.execute("delete from \"StatBlock\" where id = ?1", [id])
+                    .map_err(sql_error)?;
+            }
+        }
+        for stat_block in statblocks {
+            if !stat_block.id.is_some_and(|id| pending.contains(&id)) {
+                write_statblock(&tx, stat_block)?;
+            }
+        }
+
+        tx.commit().map_err(sql_error)
+    })
+}
+
+pub fn fetch_local_statblocks() -> Result<Vec<StatBlock>, AppError> {
+    with_store(|conn| read_statblocks(conn, "", []))
+}
+
+pub fn fetch_local_statblocks_by_ids(statblock_ids: &[i64]) -> Result<Vec<StatBlock>, AppError> {
+    if statblock_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let placeholders = vec!["?"; statblock_ids.len()].join(", ");
+
+    with_store(|conn| {
+        read_statblocks(
+            conn,
+            &format!(" where id in ({})", placeholders),
+            params_from_iter(statblock_ids),
+        )
+    })
+}
+
+pub fn delete_local_statblock(statblock_id: i64) -> Result<(), AppError> {
+    with_store(|conn| {
+        conn.execute("delete from \"StatBlock\" where id = ?1", [statblock_id])
+            .map_err(sql_error)?;
+        Ok(())
+    })
+}
+
+//? Encounter
+
+pub fn save_local_encounter(encounter: &mut Encounter) -> Result<i64, AppError> {
+    with_store(|conn| {
+        let id = match encounter.id {
+            Some(id) => id,
+            None => next_temporary_id(conn, "Encounter")?,
+        };
+        encounter.id = Some(id);
+
+        // insert or replace drops columns missing from the row, so an unset hp_mode keeps the cached one
+        if encounter.hp_mode.is_none() {
+            let cached: Vec<Encounter> =
+                select_as(conn, "select * from \"Encounter\" where id = ?1", [id])?;
+            encounter.hp_mode = cached.into_iter().next().and_then(|cached| cached.hp_mode);
+        }
+
+        insert_row(conn, "Encounter", encounter)?;
+
+        Ok(id)
+    })
+}
+
+pub fn replace_local_encounters(encounters: &[Encounter]) -> Result<(), AppError> {
+    with_store(|conn| {
+        let tx = conn.transaction().map_err(sql_error)?;
+        let pending = pending_ids(&tx, "Encounter")?;
+
+        for id in select_ids(&tx, "select id from \"Encounter\" where id > 0")? {
+            if !pending.contains(&id) {
+                tx.execute("delete from \"Encounter\" where id = ?1", [id])
+                    .map_err(sql_error)?;
+            }
+        }
+        for encounter in encounters {
+            if !encounter.id.is_some_and(|id| pending.contains(&id)) {
+                insert_row(&tx, "Encounter", encounter)?;
+            }
+        }
+
+        tx.commit().map_err(sql_error)
+    })
+}
+
+pub fn fetch_local_encounters() -> Result<Vec<Encounter>, AppError> {
+    with_store(|conn| select_as(conn, "select * from \"Encounter\"", []))
+}
+
+pub fn fetch_local_encounter(encounter_id: i64) -> Result<Option<Encounter>, AppError> {
+    with_store(|conn| {
+        select_as(
+            conn,
+            "select * from \"Encounter\" where id = ?1",
+            [encounter_id],
+        )
+        .map(|encounters: Vec<Encounter>| encounters.into_iter().next())
+    })
+}
+
+pub fn delete_local_encounter(encounter_id: i64) -> Result<(), AppError> {
+    with_store(|conn| {
+        let tx = conn.transaction().map_err(sql_error)?;
+
+        for table_name in ["EncounterPlayer", "PlayableStatBlock"] {
+            tx.execute(
+                &format!("delete from \"{}\" where encounter_id = ?1", table_name),
+                [encounter_id],
+            )
+            .map_err(sql_error)?;
+        }
+        tx.execute("delete from \"Encounter\" where id = ?1", [encounter_id])
+            .map_err(sql_error)?;
+
+        tx.commit().map_err(sql_error)
+    })
+}
+
+//? EncounterPlayer
+
+pub fn replace_local_encounter_players(
+    encounter_id: i64,
+    encounter_players: &[EncounterPlayer],
+) -> Result<(), AppError> {
+    with_store(|conn| {
+        let tx = conn.transaction().map_err(sql_error)?;
+
+        tx.execute(
+            "delete from \"EncounterPlayer\" where encounter_id = ?1",
+            [encounter_id],
+        )
+        .map_err(sql_error)?;
+        for player in encounter_players {
+            insert_row(&tx, "EncounterPlayer", player)?;
+        }
+
+        tx.commit().map_err(sql_error)
+    })
+}
+
+pub fn fetch_local_encounter_players(encounter_id: i64) -> Result<Vec<EncounterPlayer>, AppError> {
+    with_store(|conn| {
+        select_as(
+            conn,
+            "select * from \"EncounterPlayer\" where encounter_id = ?1",
+            [encounter_id],
+        )
+    })
+}
+
+//? PlayableStatBlock
+
+pub fn replace_local_playable_statblocks(
+    encounter_id: i64,
+    playable_stat_blocks: &mut [PlayableStatBlock],
+) -> Result<(), AppError> {
+    with_store(|conn| {
+        let tx = conn.transaction().map_err(sql_error)?;
+
+        tx.execute(
+            "delete from \"PlayableStatBlock\" where encounter_id = ?1",
+            [encounter_id],
+        )
+        .map_err(sql_error)?;
+        for playable in playable_stat_blocks.iter_mut() {
+            if playable.id.is_none() {
+                playable.id = Some(next_temporary_id(&tx, "PlayableStatBlock")?);
+            }
+            insert_row(&tx, "PlayableStatBlock", playable)?;
+        }
+
+        tx.commit().map_err(sql_error)
+    })
+}
+
+pub fn fetch_local_playable_statblocks(
+    encounter_id: i64,
+) -> Result<Vec<PlayableStatBlock>, AppError> {
+    with_store(|conn| {
+        select_as(
+            conn,
+            "select * from \"PlayableStatBlock\" where encounter_id = ?1",
+            [encounter_id],
+        )
+    })
+}
+
+// Applies the same column changes as a Supabase PATCH on a single combatant
+pub fn update_local_combatant(
+    combatant: &CombatantRef,
+    encounter_id: i64,
+    body: &Value,
+) -> Result<(), AppError> {
+    let Value::Object(changes) = body else {
+        return Err(AppError::validation(
+            "body",
+            "Local update failed: body is not an object",
+        ));
+    };
+
+    let assignments = changes
+        .keys()
+        .map(|column| format!("\"{}\" = ?", column))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let mut values: Vec<SqlValue> = changes.values().map(to_sql_value).collect();
+
+    let (table_name, filter) = match combatant {
+        CombatantRef::Player(name) => {
+            values.push(SqlValue::Integer(encounter_id));
+            values.push(SqlValue::Text(name.clone()));
+            ("EncounterPlayer", "encounter_id = ? and name = ?")
+        }
+        CombatantRef::Monster(id) => {
+            values.push(SqlValue::Integer(*id));
+            ("PlayableStatBlock", "id = ?")
+        }
+    };
+
+    with_store(|conn| {
+        conn.execute(
+            &format!(
+                "update \"{}\" set {} where {}",
+                table_name, assignments, filter
+            ),
+            params_from_iter(values),
+        )
+        .map_err(sql_error)?;
+        Ok(())
+    })
+}
+
+//? Sync Queue
+
+pub fn queue_sync_operation(operation: SyncOperation) -> Result<(), AppError> {
+    with_user_store(|user_id, conn| queue_operation(conn, user_id, operation))
+}
+
+// The logged in user's queued changes
+pub fn fetch_pending_sync_operations() -> Result<Vec<PendingSyncOperation>, AppError> {
+    with_user_store(|user_id, conn| read_user_operations(conn, user_id))
+}
+
+pub fn fetch_user_sync_operations(user_id: &str) -> Result<Vec<PendingSyncOperation>, AppError> {
+    with_store(|conn| read_user_operations(conn, user_id))
+}
+
+pub fn has_pending_encounter_changes(encounter_id: i64) -> Result<bool, AppError> {
+    with_store(|conn| Ok(pending_ids(conn, "Encounter")?.contains(&encounter_id)))
+}
+
+pub fn remove_sync_operation(operation_id: i64) -> Result<(), AppError> {
+    with_user_store(|user_id, conn| {
+        conn.execute(
+            "delete from \"SyncQueue\" where id = ?1 and user_id = ?2",
+            rusqlite::params![operation_id, user_id],
+        )
+        .map_err(sql_error)?;
+        Ok(())
+    })
+}
+
+pub fn fail_sync_operation(operation_id: i64, error: &str) -> Result<(), AppError> {
+    with_user_store(|user_id, conn| {
+        conn.execute(
+            "update \"SyncQueue\" set attempts = attempts + 1, last_error = ?2 where id = ?1 and user_id = ?3",
+            rusqlite::params![operation_id, error, user_id],
+        )
+        .map_err(sql_error)?;
+        Ok(())
+    })
+}
+
+// Moves a row from its temporary id to the one Supabase assigned, along with everything pointing at it
+pub fn remap_temporary_id(
+    table_name: &str,
+    temporary_id: i64,
+    server_id: i64,
+) -> Result<(), AppError> {
+    let references: &[(&str, &str)] = match table_name {
+        "StatBlock" => &[("PlayableStatBlock", "statblock_id")],
+        "Encounter" => &[
+            ("EncounterPlayer", "encounter_id"),
+            ("PlayableStatBlock", "encounter_id"),
+        ],
+        _ => {
+            return Err(AppError::validation(
+                "table_name",
+                format!("Cannot remap ids for {}", table_name),
+            ))
+        }
+    };
+
+    with_store(|conn| {
+        let tx = conn.transaction().map_err(sql_error)?;
+
+        tx.execute(
+            &format!("delete from \"{}\" where id = ?1", table_name),
+            [server_id],
+        )
+        .map_err(sql_error)?;
+        tx.execute(
+            &format!("update \"{}\" set id = ?2 where id = ?1", table_name),
+            [temporary_id, server_id],
+        )
+        .map_err(sql_error)?;
+
+        for (reference_table, column) in references {
+            tx.execute(
+                &format!(
+                    "update \"{}\" set {} = ?2 where {} = ?1",
+                    reference_table, column, column
+                ),
+                [temporary_id, server_id],
+            )
+            .map_err(sql_error)?;
+        }
+
+        for mut queued in read_pending_operations(&tx)? {
+            let original = queued.operation.clone();
+            queued
+                .operation
+                .remap_id(table_name, temporary_id, server_id);
+
+            if queued.operation != original {
+                tx.execute(
+                    "update \"SyncQueue\" set operation = ?2 where id = ?1",
+                    rusqlite::params![
+                        queued.id,
+                        serde_json::to_string(&queued.operation)
+                            .map_err(|e| AppError::parse(e.to_string()))?
+                    ],
+                )
+                .map_err(sql_error)?;
+            }
+        }
+
+        tx.commit().map_err(sql_error)
+    })
+}
+
+pub fn local_last_modified(table_name: &str, id: i64) -> Result<Option<String>, AppError> {
+    with_store(|conn| {
+        let rows = select_rows(
+            conn,
+            &format!("select last_modified from \"{}\" where id = ?1", table_name),
+            [id],
+        )?;
+
+        Ok(rows
+            .first()
+            .and_then(|row| row["last_modified"].as_str())
+            .map(|last_modified| last_modified.to_string()))
+    })
+}
+
+pub fn fetch_sync_state(key: &str) -> Result<Option<String>, AppError> {
+    with_store(|conn| {
+        let rows = select_rows(
+            conn,
+            "select value from \"SyncState\" where key = ?1",
+            [key],
+        )?;
+
+        Ok(rows
+            .first()
+            .and_then(|row| row["value"].as_str())
+            .map(|value| value.to_string()))
+    })
+}
+
+pub fn save_sync_state(key: &str, value: &str) -> Result<(), AppError> {
+    with_store(|conn| {
+        conn.execute(
+            "insert or replace into \"SyncState\" (key, value) values (?1, ?2)",
+            [key, value],
+        )
+        .map_err(sql_error)?;
+        Ok(())
+    })
+}
+
+//? Helper Util
+
+fn with_store<T>(f: impl FnOnce(&mut Connection) -> Result<T, AppError>) -> Result<T, AppError> {
+    with_user_store(|_, conn| f(conn))
+}
+
+fn with_user_store<T>(
+    f: impl FnOnce(&str, &mut Connection) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    let mut store = lock_store()?;
+    let (user_id, conn) = store
+        .open
+        .as_mut()
+        .ok_or(AppError::storage("Local store has not been opened"))?;
+
+    f(user_id, conn)
+}
+
+fn lock_store() -> Result<MutexGuard<'static, LocalStore>, AppError> {
+    LOCAL_STORE
+        .get()
+        .ok_or(AppError::storage("Local store has not been opened"))?
+        .lock()
+        .map_err(|_| AppError::storage("Local store is unavailable"))
+}
+
+fn open_connection(path: &Path) -> Result<Connection, AppError> {
+    let conn = Connection::open(path)
+        .map_err(|e| AppError::storage(format!("Failed to open local store: {}", e)))?;
+    conn.execute_batch(LOCAL_SCHEMA)
+        .map_err(|e| AppError::storage(format!("Failed to create local store schema: {}", e)))?;
+
+    Ok(conn)
+}
+
+fn sql_error(e: rusqlite::Error) -> AppError {
+    AppError::storage(format!("Local store error: {}", e))
+}
+
+fn next_temporary_id(conn: &Connection, table_name: &str) -> Result<i64, AppError> {
+    conn.query_row(
+        &format!(
+            "select min(coalesce(min(id), 0), 0) - 1 from \"{}\"",
+            table_name
+        ),
+        [],
+        |row| row.get(0),
+    )
+    .map_err(sql_error)
+}
+
+// Deletes cancel the user's queued changes to the same row, and rows that never reached Supabase need no delete at all
+fn queue_operation(
+    conn: &mut Connection,
+    user_id: &str,
+    operation: SyncOperation,
+) -> Result<(), AppError> {
+    let tx = conn.transaction().map_err(sql_error)?;
+    let pending = read_user_operations(&tx, user_id)?;
+
+    if operation.is_delete() {
+        for queued in pending
+            .iter()
+            .filter(|queued| queued.operation.row() == operation.row())
+        {
+            tx.execute("delete from \"SyncQueue\" where id = ?1", [queued.id])
+                .map_err(sql_error)?;
+        }
+        if operation.row().1 < 0 {
+            return tx.commit().map_err(sql_error);
+        }
+    } else if pending
+        .iter()
+        .any(|queued| operation.supersedes(&queued.operation))
+    {
+        return Ok(());
+    }
+
+    tx.execute(
+        "insert into \"SyncQueue\" (operation, user_id, created_at) values (?1, ?2, ?3)",
+        [
+            serde_json::to_string(&operation).map_err(|e| AppError::parse(e.to_string()))?,
+            user_id.to_string(),
+            Utc::now().to_rfc3339(),
+        ],
+    )
+    .map_err(sql_error)?;
+
+    tx.commit().map_err(sql_error)
+}
+
+fn read_user_operations(
+    conn: &Connection,
+    user_id: &str,
+) -> Result<Vec<PendingSyncOperation>, AppError> {
+    select_as(
+        conn,
+        "select id, operation, created_at, attempts, last_error from \"SyncQueue\" where user_id = ?1 order by id",
+        [user_id],
+    )
+}
+
+fn read_pending_operations(conn: &Connection) -> Result<Vec<PendingSyncOperation>, AppError> {
+    select_as(
+        conn,
+        "select id, operation, created_at, attempts, last_error from \"SyncQueue\" order by id",
+        [],
+    )
+}
+
+fn pending_ids(conn: &Connection, table_name: &str) -> Result<Vec<i64>, AppError> {
+    Ok(read_pending_operations(conn)?
+        .iter()
+        .map(|queued| queued.operation.row())
+        .filter(|(row_table, _)| *row_table == table_name)
+        .map(|(_, id)| id)
+        .collect())
+}
+
+fn select_ids(conn: &Connection, sql: &str) -> Result<Vec<i64>, AppError> {
+    Ok(select_rows(conn, sql, [])?
+        .iter()
+        .filter_map(|row| row["id"].as_i64())
+        .collect())
+}
+
+fn write_statblock(conn: &Connection, stat_block: &StatBlock) -> Result<(), AppError> {
+    let id = stat_block.id.ok_or(AppError::validation(
+        "id",
+        "Local StatBlock save failed: No StatBlock ID",
+    ))?;
+
+    // Cascades to every child table
+    conn.execute("delete from \"StatBlock\" where id = ?1", [id])
+        .map_err(sql_error)?;
+
+    let mut row = serde_json::to_value(stat_block.statblock_to_db())
+        .map_err(|e| AppError::parse(e.to_string()))?;
+    row["id"] = Value::from(id);
+    insert_row(conn, "StatBlock", &row)?;
+
+    for (table_name, rows) in stat_block.children_to_db()? {
+        for row in rows.as_array().into_iter().flatten() {
+            insert_row(conn, &table_name, row)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_statblocks(
+    conn: &Connection,
+    filter: &str,
+    params: impl Params,
+) -> Result<Vec<StatBlock>, AppError> {
+    let mut rows = select_rows(
+        conn,
+        &format!("select * from \"StatBlock\"{}", filter),
+        params,
+    )?;
+
+    for row in rows.iter_mut() {
+        let id = row["id"]
+            .as_i64()
+            .ok_or(AppError::parse("Local StatBlock has no id"))?;
+
+        for (table_name, columns) in STATBLOCK_CHILD_TABLES {
+            row[table_name] = Value::Array(select_rows(
+                conn,
+                &format!(
+                    "select {} from \"{}\" where statblock_id = ?1",
+                    columns, table_name
+                ),
+                [id],
+            )?);
+        }
+    }
+
+    rows.into_iter()
+        .map(|row| {
+            serde_json::from_value::<StatBlockFromDB>(row)
+                .map(|db| StatBlock::statblock_from_db(&db))
+                .map_err(|e| AppError::parse(format!("Failed to parse local StatBlock: {}", e)))
+        })
+        .collect()
+}
+
+fn insert_row<T: Serialize>(conn: &Connection, table_name: &str, row: &T) -> Result<(), AppError> {
+    let Value::Object(object) =
+        serde_json::to_value(row).map_err(|e| AppError::parse(e.to_string()))?
+    else {
+        return Err(AppError::validation(
+            "row",
+            format!("Local {} save failed: row is not an object", table_name),
+        ));
+    };
+
+    let columns = object
+        .keys()
+        .map(|column| format!("\"{}\"", column))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let placeholders = vec!["?"; object.len()].join(", ");
+
+    conn.execute(
+        &format!(
+            "insert or replace into \"{}\" ({}) values ({})",
+            table_name, columns, placeholders
+        ),
+        params_from_iter(object.values().map(to_sql_value)),
+    )
+    .map_err(sql_error)?;
+
+    Ok(())
+}
+
+fn select_rows(conn: &Connection, sql: &str, params: impl Params) -> Result<Vec<Value>, AppError> {
+    let mut statement = conn.prepare(sql).map_err(sql_error)?;
+    let columns: Vec<String> = statement
+        .column_names()
+        .iter()
+        .map(|column| column.to_string())
+        .collect();
+
+    let mut rows = statement.query(params).map_err(sql_error)?;
+    let mut values = Vec::new();
+
+    while let Some(row) = rows.next().map_err(sql_error)? {
+        let mut object = Map::new();
+
+        for (index, column) in columns.iter().enumerate() {
+            let value = match row.get_ref(index).map_err(sql_error)? {
+                ValueRef::Null | ValueRef::Blob(_) => Value::Null,
+                ValueRef::Integer(integer) => Value::from(integer),
+                ValueRef::Real(real) => Value::from(real),
+                ValueRef::Text(text) => {
+                    let text = String::from_utf8_lossy(text).to_string();
+                    if JSON_COLUMNS.contains(&column.as_str()) {
+                        serde_json::from_str(&text).map_err(|e| AppError::parse(e.to_string()))?
+                    } else {
+                        Value::String(text)
+                    }
+                }
+            };
+            object.insert(column.clone(), value);
+        }
+
+        values.push(Value::Object(object));
+    }
+
+    Ok(values)
+}
+
+fn select_as<T: serde::de::DeserializeOwned>(
+    conn: &Connection,
+    sql: &str,
+    params: impl Params,
+) -> Result<Vec<T>, AppError> {
+    serde_json::from_value(Value::Array(select_rows(conn, sql, params)?))
+        .map_err(|e| AppError::parse(format!("Failed to parse local rows: {}", e)))
+}
+
+fn to_sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(flag) => SqlValue::Integer(*flag as i64),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => SqlValue::Integer(integer),
+            None => SqlValue::Real(number.as_f64().unwrap_or_default()),
+        },
+        Value::String(text) => SqlValue::Text(text.clone()),
+        Value::Array(_) | Value::Object(_) => SqlValue::Text(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(LOCAL_SCHEMA).unwrap();
+        conn
+    }
+
+    fn operations(conn: &Connection, user_id: &str) -> Vec<SyncOperation> {
+        read_user_operations(conn, user_id)
+            .unwrap()
+            .into_iter()
+            .map(|queued| queued.operation)
+            .collect()
+    }
+
+    fn save_statblock(statblock_id: i64) -> SyncOperation {
+        SyncOperation::SaveStatBlock {
+            statblock_id,
+            base_last_modified: Some("2026-10-18T12:00:00Z".to_string()),
+        }
+    }
+
+    #[test]
+    fn queued_changes_stay_with_the_user_who_made_them() {
+        let mut conn = store();
+
+        queue_operation(&mut conn, "user-a", save_statblock(4)).unwrap();
+        // The next user on this machine saves the same row, the first user's queued save doesn't cover it
+        queue_operation(&mut conn, "user-b", save_statblock(4)).unwrap();
+        queue_operation(
+            &mut conn,
+            "user-b",
+            SyncOperation::DeleteEncounter { encounter_id: 7 },
+        )
+        .unwrap();
+
+        assert_eq!(operations(&conn, "user-a"), vec![save_statblock(4)]);
+        assert_eq!(
+            operations(&conn, "user-b"),
+            vec![
+                save_statblock(4),
+                SyncOperation::DeleteEncounter { encounter_id: 7 }
+            ]
+        );
+
+        // A delete only cancels the deleting user's own queued save
+        queue_operation(
+            &mut conn,
+            "user-b",
+            SyncOperation::DeleteStatBlock { statblock_id: 4 },
+        )
+        .unwrap();
+
+        assert_eq!(operations(&conn, "user-a"), vec![save_statblock(4)]);
+        assert_eq!(
+            operations(&conn, "user-b"),
+            vec![
+                SyncOperation::DeleteEncounter { encounter_id: 7 },
+                SyncOperation::DeleteStatBlock { statblock_id: 4 }
+            ]
+        );
+        assert!(operations(&conn, "user-c").is_empty());
+    }
+}
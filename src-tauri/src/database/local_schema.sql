@@ -0,0 +1,151 @@
+-- Local mirror of the Supabase tables, negative ids are rows that only exist locally
+pragma foreign_keys = on;
+
+create table if not exists "StatBlock" (
+    id integer primary key,
+    name text not null,
+    size text not null,
+    creature_type text not null,
+    subtype text,
+    alignment text not null,
+    ac integer not null,
+    hp integer not null,
+    initiative text not null,
+    hit_dice text not null,
+    speed text not null,
+    senses text,
+    languages text,
+    strength integer not null,
+    dexterity integer not null,
+    constitution integer not null,
+    intelligence integer not null,
+    wisdom integer not null,
+    charisma integer not null,
+    cr text not null,
+    last_modified text not null,
+    legendary_description text,
+    user_id text not null,
+    spellcasting_ability text,
+    save_dc integer,
+    spell_attack_bonus integer
+);
+
+create table if not exists "Action" (
+    statblock_id integer not null references "StatBlock" (id) on delete cascade on update cascade,
+    name text not null,
+    description text not null,
+    attack text
+);
+
+create table if not exists "BonusAction" (
+    statblock_id integer not null references "StatBlock" (id) on delete cascade on update cascade,
+    name text not null,
+    description text not null,
+    attack text
+);
+
+create table if not exists "Reaction" (
+    statblock_id integer not null references "StatBlock" (id) on delete cascade on update cascade,
+    name text not null,
+    description text not null,
+    attack text
+);
+
+create table if not exists "LegendaryAction" (
+    statblock_id integer not null references "StatBlock" (id) on delete cascade on update cascade,
+    name text not null,
+    description text not null,
+    attack text
+);
+
+create table if not exists "Trait" (
+    statblock_id integer not null references "StatBlock" (id) on delete cascade on update cascade,
+    name text not null,
+    description text not null
+);
+
+create table if not exists "DamageResistance" (
+    statblock_id integer not null references "StatBlock" (id) on delete cascade on update cascade,
+    damage_type text not null
+);
+
+create table if not exists "DamageImmunity" (
+    statblock_id integer not null references "StatBlock" (id) on delete cascade on update cascade,
+    damage_type text not null
+);
+
+create table if not exists "DamageVulnerability" (
+    statblock_id integer not null references "StatBlock" (id) on delete cascade on update cascade,
+    damage_type text not null
+);
+
+create table if not exists "ConditionImmunity" (
+    statblock_id integer not null references "StatBlock" (id) on delete cascade on update cascade,
+    condition_type text not null
+);
+
+create table if not exists "SaveProficiency" (
+    statblock_id integer not null references "StatBlock" (id) on delete cascade on update cascade,
+    score text not null,
+    level text not null
+);
+
+create table if not exists "SkillProficiency" (
+    statblock_id integer not null references "StatBlock" (id) on delete cascade on update cascade,
+    ability text not null,
+    level text not null
+);
+
+create table if not exists "Spells" (
+    statblock_id integer not null references "StatBlock" (id) on delete cascade on update cascade,
+    name text not null,
+    spell_list text not null
+);
+
+create table if not exists "Encounter" (
+    id integer primary key,
+    name text not null,
+    user_id text not null,
+    last_modified text not null,
+    hp_mode text not null default 'Average'
+);
+
+-- Encounter children are cached independently of their Encounter, so there are no foreign keys here
+create table if not exists "EncounterPlayer" (
+    encounter_id integer not null,
+    name text not null,
+    level integer not null,
+    hp integer not null,
+    current_hp integer not null,
+    temporary_hp integer not null,
+    initiative integer,
+    conditions text not null default '[]',
+    primary key (encounter_id, name)
+);
+
+create table if not exists "PlayableStatBlock" (
+    id integer primary key,
+    max_hp integer,
+    current_hp integer not null,
+    temporary_hp integer not null,
+    initiative integer,
+    name text,
+    conditions text not null default '[]',
+    statblock_id integer not null,
+    encounter_id integer not null
+);
+
+-- Changes made while offline, replayed to Supabase in id order with the session of the user who made them
+create table if not exists "SyncQueue" (
+    id integer primary key autoincrement,
+    operation text not null,
+    user_id text not null,
+    created_at text not null,
+    attempts integer not null default 0,
+    last_error text
+);
+
+create table if not exists "SyncState" (
+    key text primary key,
+    value text not null
+);
@@ -0,0 +1,706 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    database::{
+        encounter_db::{encounter_conflict, SaveEncounterResponse},
+        repository::{EncounterRepository, Repository, StatBlockRepository},
+        statblock_db::{statblock_conflict, SaveStatBlockResponse},
+    },
+    types::{
+        combat_types::CombatantRef,
+        encounter_types::{Encounter, EncounterPlayer, PlayableStatBlock},
+        error_types::AppError,
+        statblock_types::StatBlock,
+    },
+};
+
+// Behaves like the Supabase tables without a network, for tests
+#[derive(Default)]
+pub struct InMemoryRepository {
+    store: Mutex<MemoryStore>,
+    // While set every call fails like Supabase does without a connection
+    offline: AtomicBool,
+}
+
+#[derive(Default)]
+struct MemoryStore {
+    statblocks: BTreeMap<i64, StatBlock>,
+    encounters: BTreeMap<i64, Encounter>,
+    encounter_players: Vec<EncounterPlayer>,
+    playable_stat_blocks: Vec<PlayableStatBlock>,
+    // Each table counts its ids separately, like an identity column
+    last_ids: HashMap<&'static str, i64>,
+}
+
+impl MemoryStore {
+    fn next_id(&mut self, table_name: &'static str) -> i64 {
+        let last_id = self.last_ids.entry(table_name).or_insert(0);
+        *last_id += 1;
+        *last_id
+    }
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_offline(&self, offline: bool) {
+        self.offline.store(offline, Ordering::Relaxed);
+    }
+
+    fn store(&self) -> Result<MutexGuard<'_, MemoryStore>, AppError> {
+        if self.offline.load(Ordering::Relaxed) {
+            return Err(AppError::network("In-memory store is offline"));
+        }
+
+        self.store
+            .lock()
+            .map_err(|_| AppError::storage("In-memory store is poisoned"))
+    }
+}
+
+impl Repository for InMemoryRepository {
+    async fn is_offline(&self) -> bool {
+        self.offline.load(Ordering::Relaxed)
+    }
+}
+
+impl StatBlockRepository for InMemoryRepository {
+    async fn fetch_statblocks(&self) -> Result<Vec<StatBlock>, AppError> {
+        Ok(self.store()?.statblocks.values().cloned().collect())
+    }
+
+    async fn fetch_statblocks_by_ids(
+        &self,
+        statblock_ids: &[i64],
+    ) -> Result<Vec<StatBlock>, AppError> {
+        Ok(self
+            .store()?
+            .statblocks
+            .values()
+            .filter(|statblock| statblock.id.is_some_and(|id| statblock_ids.contains(&id)))
+            .cloned()
+            .collect())
+    }
+
+    async fn save_statblock(
+        &self,
+        stat_block: &mut StatBlock,
+        last_seen: Option<&str>,
+    ) -> Result<SaveStatBlockResponse, AppError> {
+        let mut store = self.store()?;
+
+        // Stamped here like the save_statblock function does on the server
+        let last_modified = Utc::now().to_rfc3339();
+
+        let Some(id) = stat_block.id.filter(|id| *id > 0) else {
+            let id = store.next_id("StatBlock");
+            stat_block.id = Some(id);
+            stat_block.last_modified = last_modified.clone();
+            store.statblocks.insert(id, stat_block.clone());
+
+            return Ok(SaveStatBlockResponse {
+                id,
+                status: 201,
+                message: "StatBlock created successfully".to_string(),
+                was_updated: false,
+                last_modified,
+                conflict: None,
+            });
+        };
+
+        let Some(stored) = store.statblocks.get(&id) else {
+            return Err(AppError::not_found(format!(
+                "StatBlock {} no longer exists on the server",
+                id
+            )));
+        };
+
+        if let Some(last_seen) = last_seen {
+            if !same_timestamp(&stored.last_modified, last_seen)? {
+                return statblock_conflict(id, stat_block.clone(), stored.clone());
+            }
+        }
+
+        stat_block.last_modified = last_modified.clone();
+        store.statblocks.insert(id, stat_block.clone());
+
+        Ok(SaveStatBlockResponse {
+            id,
+            status: 200,
+            message: "StatBlock updated successfully".to_string(),
+            was_updated: true,
+            last_modified,
+            conflict: None,
+        })
+    }
+
+    async fn delete_statblock(&self, statblock_id: i64) -> Result<(), AppError> {
+        self.store()?.statblocks.remove(&statblock_id);
+        Ok(())
+    }
+}
+
+impl EncounterRepository for InMemoryRepository {
+    async fn fetch_encounters(&self) -> Result<Vec<Encounter>, AppError> {
+        Ok(self.store()?.encounters.values().cloned().collect())
+    }
+
+    async fn fetch_encounter(&self, encounter_id: i64) -> Result<Option<Encounter>, AppError> {
+        Ok(self.store()?.encounters.get(&encounter_id).cloned())
+    }
+
+    async fn save_encounter(
+        &self,
+        mut encounter: Encounter,
+        last_seen: Option<&str>,
+    ) -> Result<SaveEncounterResponse, AppError> {
+        let mut store = self.store()?;
+
+        // Stamped here like the trigger on Encounter does on the server
+        let last_modified = Utc::now();
+
+        let Some(id) = encounter.id.filter(|id| *id > 0) else {
+            let id = store.next_id("Encounter");
+            encounter.id = Some(id);
+            encounter.last_modified = last_modified;
+            store.encounters.insert(id, encounter);
+
+            return Ok(SaveEncounterResponse {
+                id,
+                status: 201,
+                message: "Encounter created successfully".to_string(),
+                was_updated: false,
+                last_modified,
+                conflict: None,
+            });
+        };
+
+        let Some(stored) = store.encounters.get(&id) else {
+            return Err(AppError::not_found(format!(
+                "Encounter {} no longer exists on the server",
+                id
+            )));
+        };
+
+        if let Some(last_seen) = last_seen {
+            if !same_timestamp(&stored.last_modified.to_rfc3339(), last_seen)? {
+                return encounter_conflict(id, encounter, stored.clone());
+            }
+        }
+
+        encounter.last_modified = last_modified;
+        store.encounters.insert(id, encounter);
+
+        Ok(SaveEncounterResponse {
+            id,
+            status: 200,
+            message: "Encounter updated successfully".to_string(),
+            was_updated: true,
+            last_modified,
+            conflict: None,
+        })
+    }
+
+    async fn delete_encounter(&self, encounter_id: i64) -> Result<(), AppError> {
+        let mut store = self.store()?;
+
+        store.encounters.remove(&encounter_id);
+        store
+            .encounter_players
+            .retain(|player| player.encounter_id != encounter_id);
+        store
+            .playable_stat_blocks
+            .retain(|playable| playable.encounter_id != encounter_id);
+
+        Ok(())
+    }
+
+    async fn fetch_encounter_players(
+        &self,
+        encounter_id: i64,
+    ) -> Result<Vec<EncounterPlayer>, AppError> {
+        Ok(self
+            .store()?
+            .encounter_players
+            .iter()
+            .filter(|player| player.encounter_id == encounter_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn save_encounter_players(
+        &self,
+        encounter_players: Vec<EncounterPlayer>,
+    ) -> Result<(), AppError> {
+        let Some(encounter_id) = encounter_players.first().map(|player| player.encounter_id) else {
+            return Ok(());
+        };
+
+        // Players are keyed by Encounter and name
+        for (index, player) in encounter_players.iter().enumerate() {
+            if encounter_players[..index]
+                .iter()
+                .any(|other| other.encounter_id == player.encounter_id && other.name == player.name)
+            {
+                return Err(AppError::conflict(format!(
+                    "EncounterPlayer {} is already in Encounter {}",
+                    player.name, player.encounter_id
+                )));
+            }
+        }
+
+        let mut store = self.store()?;
+        store
+            .encounter_players
+            .retain(|player| player.encounter_id != encounter_id);
+        store.encounter_players.extend(encounter_players);
+
+        Ok(())
+    }
+
+    async fn fetch_playable_statblocks(
+        &self,
+        encounter_id: i64,
+    ) -> Result<Vec<PlayableStatBlock>, AppError> {
+        Ok(self
+            .store()?
+            .playable_stat_blocks
+            .iter()
+            .filter(|playable| playable.encounter_id == encounter_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn save_playable_statblocks(
+        &self,
+        playable_stat_blocks: Vec<PlayableStatBlock>,
+    ) -> Result<(), AppError> {
+        let Some(encounter_id) = playable_stat_blocks
+            .first()
+            .map(|playable| playable.encounter_id)
+        else {
+            return Ok(());
+        };
+
+        let mut store = self.store()?;
+        store
+            .playable_stat_blocks
+            .retain(|playable| playable.encounter_id != encounter_id);
+
+        for mut playable in playable_stat_blocks {
+            if playable.id.is_none() {
+                playable.id = Some(store.next_id("PlayableStatBlock"));
+            }
+            store.playable_stat_blocks.push(playable);
+        }
+
+        Ok(())
+    }
+
+    async fn patch_combatant(
+        &self,
+        combatant: &CombatantRef,
+        encounter_id: i64,
+        body: &serde_json::Value,
+    ) -> Result<(), AppError> {
+        let mut store = self.store()?;
+
+        match combatant {
+            CombatantRef::Player(name) => {
+                if let Some(player) = store
+                    .encounter_players
+                    .iter_mut()
+                    .find(|player| player.encounter_id == encounter_id && &player.name == name)
+                {
+                    patch_row(player, body)?;
+                }
+            }
+            CombatantRef::Monster(id) => {
+                if let Some(playable) = store
+                    .playable_stat_blocks
+                    .iter_mut()
+                    .find(|playable| playable.id == Some(*id))
+                {
+                    patch_row(playable, body)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//? Helper Util
+
+fn patch_row<T: Serialize + DeserializeOwned>(
+    row: &mut T,
+    body: &serde_json::Value,
+) -> Result<(), AppError> {
+    let mut value = serde_json::to_value(&*row).map_err(|e| AppError::parse(e.to_string()))?;
+
+    if let (Some(fields), Some(changes)) = (value.as_object_mut(), body.as_object()) {
+        for (column, change) in changes {
+            fields.insert(column.clone(), change.clone());
+        }
+    }
+
+    *row = serde_json::from_value(value)
+        .map_err(|e| AppError::validation("body", format!("Invalid update: {}", e)))?;
+    Ok(())
+}
+
+// Timestamps are compared as instants, the same way Postgres compares timestamptz
+fn same_timestamp(stored: &str, last_seen: &str) -> Result<bool, AppError> {
+    let last_seen = DateTime::parse_from_rfc3339(last_seen).map_err(|e| {
+        AppError::validation(
+            "last_seen",
+            format!("Invalid last_modified {}: {}", last_seen, e),
+        )
+    })?;
+
+    Ok(DateTime::parse_from_rfc3339(stored).is_ok_and(|stored| stored == last_seen))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{
+            encounter_db::{save_playable_statblocks_to, update_combatant_hp},
+            mock_supabase::MockSupabase,
+            repository::offline_fallback,
+        },
+        types::encounter_types::HpMode,
+        utils::import_utils::empty_statblock,
+    };
+
+    // Behavior every repository has to share, written against the traits so any backend can run it
+
+    async fn saves_and_fetches_statblocks(repository: &impl StatBlockRepository) {
+        let mut goblin = empty_statblock("Goblin");
+        let created = repository.save_statblock(&mut goblin, None).await.unwrap();
+        assert!(!created.was_updated);
+        assert_eq!(goblin.id, Some(created.id));
+        assert_eq!(goblin.last_modified, created.last_modified);
+
+        goblin.hp = 9;
+        let updated = repository
+            .save_statblock(&mut goblin, Some(&created.last_modified))
+            .await
+            .unwrap();
+        assert!(updated.was_updated);
+        assert_eq!(updated.id, created.id);
+
+        let fetched = repository
+            .fetch_statblocks_by_ids(&[created.id])
+            .await
+            .unwrap();
+        assert_eq!(fetched.len(), 1);
+        assert_eq!(fetched[0].hp, 9);
+
+        repository.delete_statblock(created.id).await.unwrap();
+        assert!(repository.fetch_statblocks().await.unwrap().is_empty());
+    }
+
+    async fn updating_a_missing_row_is_not_found(
+        repository: &(impl StatBlockRepository + EncounterRepository),
+    ) {
+        let mut goblin = empty_statblock("Goblin");
+        goblin.id = Some(404);
+        for last_seen in [None, Some("2026-10-18T12:00:00Z")] {
+            let error = repository
+                .save_statblock(&mut goblin, last_seen)
+                .await
+                .unwrap_err();
+            assert!(matches!(error, AppError::NotFound { .. }), "{:?}", error);
+
+            let error = repository
+                .save_encounter(encounter(Some(404)), last_seen)
+                .await
+                .unwrap_err();
+            assert!(matches!(error, AppError::NotFound { .. }), "{:?}", error);
+        }
+    }
+
+    async fn stale_last_seen_returns_a_conflict(
+        repository: &(impl StatBlockRepository + EncounterRepository),
+    ) {
+        let mut goblin = empty_statblock("Goblin");
+        repository.save_statblock(&mut goblin, None).await.unwrap();
+        let mut edited = goblin.clone();
+        edited.name = "Goblin Boss".to_string();
+
+        let response = repository
+            .save_statblock(&mut edited, Some("2000-01-01T00:00:00Z"))
+            .await
+            .unwrap();
+        assert_eq!(response.status, 409);
+        assert_eq!(response.last_modified, goblin.last_modified);
+        assert_eq!(
+            repository
+                .fetch_statblocks()
+                .await
+                .unwrap()
+                .iter()
+                .map(|statblock| statblock.name.as_str())
+                .collect::<Vec<_>>(),
+            ["Goblin"]
+        );
+
+        let saved = repository
+            .save_encounter(encounter(None), None)
+            .await
+            .unwrap();
+        let response = repository
+            .save_encounter(encounter(Some(saved.id)), Some("2000-01-01T00:00:00Z"))
+            .await
+            .unwrap();
+        assert_eq!(response.status, 409);
+        assert_eq!(response.last_modified, saved.last_modified);
+    }
+
+    async fn replaces_players_and_instances_per_encounter(repository: &impl EncounterRepository) {
+        let encounter_id = repository
+            .save_encounter(encounter(None), None)
+            .await
+            .unwrap()
+            .id;
+
+        repository
+            .save_encounter_players(vec![
+                player("Aria", encounter_id),
+                player("Bram", encounter_id),
+            ])
+            .await
+            .unwrap();
+        repository
+            .save_encounter_players(vec![player("Cleo", encounter_id)])
+            .await
+            .unwrap();
+        let names: Vec<String> = repository
+            .fetch_encounter_players(encounter_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|player| player.name)
+            .collect();
+        assert_eq!(names, ["Cleo"]);
+
+        let error = repository
+            .save_encounter_players(vec![
+                player("Aria", encounter_id),
+                player("Aria", encounter_id),
+            ])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, AppError::Conflict { .. }), "{:?}", error);
+
+        repository
+            .save_playable_statblocks(vec![playable(1, encounter_id), playable(1, encounter_id)])
+            .await
+            .unwrap();
+        let instances = repository
+            .fetch_playable_statblocks(encounter_id)
+            .await
+            .unwrap();
+        assert_eq!(instances.len(), 2);
+        assert!(instances.iter().all(|instance| instance.id.is_some()));
+        assert_ne!(instances[0].id, instances[1].id);
+
+        repository.delete_encounter(encounter_id).await.unwrap();
+        assert!(repository
+            .fetch_encounter(encounter_id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(repository
+            .fetch_encounter_players(encounter_id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(repository
+            .fetch_playable_statblocks(encounter_id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    async fn patches_a_single_combatant(repository: &impl EncounterRepository) {
+        let encounter_id = repository
+            .save_encounter(encounter(None), None)
+            .await
+            .unwrap()
+            .id;
+        repository
+            .save_encounter_players(vec![
+                player("Aria", encounter_id),
+                player("Bram", encounter_id),
+            ])
+            .await
+            .unwrap();
+
+        update_combatant_hp(
+            repository,
+            &CombatantRef::Player("Aria".to_string()),
+            encounter_id,
+            3,
+            5,
+        )
+        .await
+        .unwrap();
+
+        let players = repository
+            .fetch_encounter_players(encounter_id)
+            .await
+            .unwrap();
+        let hp: Vec<(u16, u16)> = players
+            .iter()
+            .map(|player| (player.current_hp, player.temporary_hp))
+            .collect();
+        assert_eq!(hp, [(3, 5), (20, 0)]);
+    }
+
+    fn encounter(id: Option<i64>) -> Encounter {
+        Encounter {
+            id,
+            name: "Goblin Ambush".to_string(),
+            user_id: "user".to_string(),
+            last_modified: Utc::now(),
+            hp_mode: None,
+        }
+    }
+
+    fn player(name: &str, encounter_id: i64) -> EncounterPlayer {
+        EncounterPlayer {
+            name: name.to_string(),
+            level: 3,
+            hp: 20,
+            current_hp: 20,
+            temporary_hp: 0,
+            initiative: None,
+            conditions: vec![],
+            encounter_id,
+        }
+    }
+
+    fn playable(statblock_id: i64, encounter_id: i64) -> PlayableStatBlock {
+        PlayableStatBlock {
+            id: None,
+            max_hp: None,
+            current_hp: 0,
+            temporary_hp: 0,
+            initiative: None,
+            name: None,
+            conditions: vec![],
+            statblock_id,
+            encounter_id,
+        }
+    }
+
+    #[tokio::test]
+    async fn in_memory_saves_and_fetches_statblocks() {
+        saves_and_fetches_statblocks(&InMemoryRepository::new()).await;
+    }
+
+    #[tokio::test]
+    async fn in_memory_updating_a_missing_row_is_not_found() {
+        updating_a_missing_row_is_not_found(&InMemoryRepository::new()).await;
+    }
+
+    #[tokio::test]
+    async fn in_memory_stale_last_seen_returns_a_conflict() {
+        stale_last_seen_returns_a_conflict(&InMemoryRepository::new()).await;
+    }
+
+    #[tokio::test]
+    async fn in_memory_replaces_players_and_instances_per_encounter() {
+        replaces_players_and_instances_per_encounter(&InMemoryRepository::new()).await;
+    }
+
+    #[tokio::test]
+    async fn in_memory_patches_a_single_combatant() {
+        patches_a_single_combatant(&InMemoryRepository::new()).await;
+    }
+
+    #[tokio::test]
+    async fn supabase_saves_and_fetches_statblocks() {
+        let supabase = MockSupabase::start().await;
+        saves_and_fetches_statblocks(&supabase.repository()).await;
+    }
+
+    #[tokio::test]
+    async fn supabase_updating_a_missing_row_is_not_found() {
+        let supabase = MockSupabase::start().await;
+        updating_a_missing_row_is_not_found(&supabase.repository()).await;
+    }
+
+    #[tokio::test]
+    async fn supabase_stale_last_seen_returns_a_conflict() {
+        let supabase = MockSupabase::start().await;
+        stale_last_seen_returns_a_conflict(&supabase.repository()).await;
+    }
+
+    #[tokio::test]
+    async fn supabase_replaces_players_and_instances_per_encounter() {
+        let supabase = MockSupabase::start().await;
+        replaces_players_and_instances_per_encounter(&supabase.repository()).await;
+    }
+
+    #[tokio::test]
+    async fn supabase_patches_a_single_combatant() {
+        let supabase = MockSupabase::start().await;
+        patches_a_single_combatant(&supabase.repository()).await;
+    }
+
+    #[tokio::test]
+    async fn new_instances_get_hit_points_from_the_encounter_hp_mode() {
+        let repository = InMemoryRepository::new();
+        let mut ogre = empty_statblock("Ogre");
+        ogre.hp = 59;
+        ogre.hit_dice = "7d10+21".to_string();
+        let statblock_id = repository.save_statblock(&mut ogre, None).await.unwrap().id;
+        let mut max_encounter = encounter(None);
+        max_encounter.hp_mode = Some(HpMode::Max);
+        let encounter_id = repository
+            .save_encounter(max_encounter, None)
+            .await
+            .unwrap()
+            .id;
+
+        save_playable_statblocks_to(&repository, vec![playable(statblock_id, encounter_id)])
+            .await
+            .unwrap();
+
+        let instances = repository
+            .fetch_playable_statblocks(encounter_id)
+            .await
+            .unwrap();
+        assert_eq!(instances[0].max_hp, Some(91));
+        assert_eq!(instances[0].current_hp, 91);
+    }
+
+    #[tokio::test]
+    async fn offline_errors_fall_back_to_the_local_copy() {
+        let repository = InMemoryRepository::new();
+        let refused = || AppError::validation("id", "refused");
+
+        let online = offline_fallback(&repository, refused(), || Ok("local")).await;
+        assert!(matches!(online, Err(AppError::Validation { .. })));
+
+        repository.set_offline(true);
+        assert!(repository.fetch_encounters().await.is_err());
+        let offline = offline_fallback(&repository, refused(), || Ok("local")).await;
+        assert_eq!(offline.unwrap(), "local");
+    }
+}
@@ -0,0 +1,308 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use mockito::{Matcher, Request, ServerGuard};
+use serde_json::{json, Value};
+
+use crate::database::repository::SupabaseRepository;
+
+// Tables that get their id from an identity column
+const IDENTITY_TABLES: [&str; 3] = ["StatBlock", "Encounter", "PlayableStatBlock"];
+// Deleting an Encounter cascades to these, like the foreign keys in Supabase
+const ENCOUNTER_CHILD_TABLES: [&str; 2] = ["EncounterPlayer", "PlayableStatBlock"];
+
+// Answers the REST and RPC requests SupabaseRepository sends and keeps the rows between them,
+// so the shared repository behavior can run against the real request code
+pub struct MockSupabase {
+    server: ServerGuard,
+}
+
+#[derive(Default)]
+struct Tables {
+    rows: HashMap<String, Vec<Value>>,
+    last_ids: HashMap<String, i64>,
+    // mockito asks for the status before the body, the handled request's body waits here
+    body: Vec<u8>,
+}
+
+impl MockSupabase {
+    pub async fn start() -> Self {
+        let mut server = mockito::Server::new_async().await;
+        let tables = Arc::new(Mutex::new(Tables::default()));
+
+        for method in ["GET", "POST", "PATCH", "DELETE"] {
+            let status_tables = tables.clone();
+            let body_tables = tables.clone();
+
+            server
+                .mock(method, Matcher::Any)
+                .with_status_code_from_request(move |request| {
+                    let mut tables = status_tables.lock().unwrap();
+                    let (status, body) = tables.handle(request);
+                    tables.body = body.to_string().into_bytes();
+                    status
+                })
+                .with_body_from_request(move |_| {
+                    std::mem::take(&mut body_tables.lock().unwrap().body)
+                })
+                .create_async()
+                .await;
+        }
+
+        MockSupabase { server }
+    }
+
+    pub fn repository(&self) -> SupabaseRepository {
+        SupabaseRepository::at(self.server.url())
+    }
+}
+
+impl Tables {
+    fn handle(&mut self, request: &Request) -> (usize, Value) {
+        let path = request.path();
+        let filters = filters(request.path_and_query());
+        let body: Value = request
+            .body()
+            .ok()
+            .and_then(|body| serde_json::from_slice(body).ok())
+            .unwrap_or(Value::Null);
+
+        if path == "/rest/v1/rpc/save_statblock" {
+            return (200, self.save_statblock(body));
+        }
+
+        let Some(table_name) = path.strip_prefix("/rest/v1/") else {
+            return (404, json!({ "message": "Unknown path" }));
+        };
+
+        match request.method() {
+            "GET" => (200, Value::Array(self.select(table_name, &filters))),
+            "POST" => self.insert(table_name, body),
+            "PATCH" => (200, Value::Array(self.update(table_name, &filters, body))),
+            "DELETE" => {
+                self.delete(table_name, &filters);
+                (204, Value::Null)
+            }
+            _ => (405, Value::Null),
+        }
+    }
+
+    // Mirrors the save_statblock function: null when target_id is gone or last_seen is stale
+    fn save_statblock(&mut self, body: Value) -> Value {
+        let mut row = body["statblock"].as_object().cloned().unwrap_or_default();
+        for (table_name, children) in body["children"].as_object().into_iter().flatten() {
+            row.insert(table_name.clone(), children.clone());
+        }
+
+        let id = match body["target_id"].as_i64() {
+            Some(id) => {
+                let Some(index) = self.position("StatBlock", id) else {
+                    return Value::Null;
+                };
+                if let Some(last_seen) = body["last_seen"].as_str() {
+                    if !same_time(&self.rows["StatBlock"][index]["last_modified"], last_seen) {
+                        return Value::Null;
+                    }
+                }
+                self.rows.get_mut("StatBlock").unwrap().remove(index);
+                id
+            }
+            None => self.next_id("StatBlock"),
+        };
+
+        let last_modified = stamp();
+        row.insert("id".to_string(), json!(id));
+        row.insert("last_modified".to_string(), json!(last_modified));
+        self.table("StatBlock").push(Value::Object(row));
+
+        json!({ "id": id, "last_modified": last_modified })
+    }
+
+    fn select(&self, table_name: &str, filters: &[(String, String)]) -> Vec<Value> {
+        self.rows
+            .get(table_name)
+            .into_iter()
+            .flatten()
+            .filter(|row| matches_filters(row, filters))
+            .cloned()
+            .collect()
+    }
+
+    fn insert(&mut self, table_name: &str, body: Value) -> (usize, Value) {
+        let rows: Vec<Value> = match body {
+            Value::Array(rows) => rows,
+            row => vec![row],
+        };
+
+        let mut inserted = Vec::new();
+        for row in rows {
+            let Value::Object(mut row) = row else {
+                continue;
+            };
+
+            if table_name == "EncounterPlayer" {
+                let duplicate =
+                    self.table(table_name)
+                        .iter()
+                        .chain(inserted.iter())
+                        .any(|existing| {
+                            existing["encounter_id"] == row["encounter_id"]
+                                && existing["name"] == row["name"]
+                        });
+                if duplicate {
+                    return (
+                        409,
+                        json!({ "message": "duplicate key value violates unique constraint" }),
+                    );
+                }
+            }
+
+            // Rows sent with an id keep it, as a bulk replace of existing instances does
+            if IDENTITY_TABLES.contains(&table_name) && row.get("id").is_none_or(|id| id.is_null())
+            {
+                row.insert("id".to_string(), json!(self.next_id(table_name)));
+            }
+            if table_name == "Encounter" {
+                row.insert("last_modified".to_string(), json!(stamp()));
+            }
+            inserted.push(Value::Object(row));
+        }
+
+        self.table(table_name).extend(inserted.iter().cloned());
+        (201, Value::Array(inserted))
+    }
+
+    fn update(
+        &mut self,
+        table_name: &str,
+        filters: &[(String, String)],
+        body: Value,
+    ) -> Vec<Value> {
+        // Encounter saves send the row wrapped in an array
+        let changes = match body {
+            Value::Array(rows) => rows.into_iter().next().unwrap_or(Value::Null),
+            row => row,
+        };
+        let changes = changes.as_object().cloned().unwrap_or_default();
+
+        let mut updated = Vec::new();
+        for row in self.table(table_name).iter_mut() {
+            if !matches_filters(row, filters) {
+                continue;
+            }
+
+            let id = row["id"].clone();
+            let fields = row.as_object_mut().unwrap();
+            fields.extend(changes.clone());
+            if !id.is_null() {
+                fields.insert("id".to_string(), id);
+            }
+            if table_name == "Encounter" {
+                fields.insert("last_modified".to_string(), json!(stamp()));
+            }
+            updated.push(row.clone());
+        }
+
+        updated
+    }
+
+    fn delete(&mut self, table_name: &str, filters: &[(String, String)]) {
+        let deleted = self.select(table_name, filters);
+        self.table(table_name)
+            .retain(|row| !matches_filters(row, filters));
+
+        if table_name == "Encounter" {
+            for encounter in deleted {
+                let filter = [(
+                    "encounter_id".to_string(),
+                    format!("eq.{}", encounter["id"]),
+                )];
+                for child_table in ENCOUNTER_CHILD_TABLES {
+                    self.table(child_table)
+                        .retain(|row| !matches_filters(row, &filter));
+                }
+            }
+        }
+    }
+
+    fn table(&mut self, table_name: &str) -> &mut Vec<Value> {
+        self.rows.entry(table_name.to_string()).or_default()
+    }
+
+    fn position(&mut self, table_name: &str, id: i64) -> Option<usize> {
+        self.table(table_name)
+            .iter()
+            .position(|row| row["id"].as_i64() == Some(id))
+    }
+
+    fn next_id(&mut self, table_name: &str) -> i64 {
+        let last_id = self.last_ids.entry(table_name.to_string()).or_insert(0);
+        *last_id += 1;
+        *last_id
+    }
+}
+
+// The column filters of a query, select and columns only shape the response
+fn filters(path_and_query: &str) -> Vec<(String, String)> {
+    let Some((_, query)) = path_and_query.split_once('?') else {
+        return Vec::new();
+    };
+
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|(column, _)| !["select", "columns"].contains(column))
+        .map(|(column, filter)| {
+            (
+                column.to_string(),
+                urlencoding::decode(filter)
+                    .map(|filter| filter.into_owned())
+                    .unwrap_or_else(|_| filter.to_string()),
+            )
+        })
+        .collect()
+}
+
+fn matches_filters(row: &Value, filters: &[(String, String)]) -> bool {
+    filters.iter().all(|(column, filter)| {
+        let value = &row[column.as_str()];
+
+        if let Some(expected) = filter.strip_prefix("eq.") {
+            if column == "last_modified" {
+                return same_time(value, expected);
+            }
+            *value == filter_value(expected)
+        } else if let Some(list) = filter
+            .strip_prefix("in.(")
+            .and_then(|list| list.strip_suffix(')'))
+        {
+            list.split(',')
+                .any(|expected| *value == filter_value(expected))
+        } else {
+            false
+        }
+    })
+}
+
+// Numbers are sent as text in a filter, anything that isn't JSON is a plain string
+fn filter_value(expected: &str) -> Value {
+    serde_json::from_str(expected).unwrap_or_else(|_| Value::String(expected.to_string()))
+}
+
+// Postgres compares timestamps, not the text they were sent as
+fn same_time(stored: &Value, expected: &str) -> bool {
+    let parse = |value: &str| DateTime::parse_from_rfc3339(value).ok();
+
+    stored
+        .as_str()
+        .and_then(parse)
+        .is_some_and(|stored| parse(expected) == Some(stored))
+}
+
+// Every save gets a later timestamp, like now() in a trigger
+fn stamp() -> String {
+    Utc::now().to_rfc3339()
+}
@@ -1,8 +1,8 @@
-mod action_db;
-mod condition_type_db;
-mod damage_type_db;
 pub mod encounter_db;
-mod proficiency_type_db;
-mod spell_db;
+pub mod local_db;
+#[cfg(test)]
+pub mod memory_repository;
+#[cfg(test)]
+mod mock_supabase;
+pub mod repository;
 pub mod statblock_db;
-mod trait_db;
@@ -0,0 +1,149 @@
+use std::future::Future;
+
+use crate::{
+    database::{
+        encounter_db::SaveEncounterResponse, local_db::open_user_store,
+        statblock_db::SaveStatBlockResponse,
+    },
+    types::{
+        combat_types::CombatantRef,
+        encounter_types::{Encounter, EncounterPlayer, PlayableStatBlock},
+        error_types::AppError,
+        statblock_types::StatBlock,
+    },
+    utils::{
+        auth_utils::token_user_id,
+        supabase_util::{is_offline, SupabaseClient},
+    },
+};
+
+#[cfg(test)]
+use crate::types::auth_types::SupabaseConfig;
+
+// Tells a request that failed because the storage can't be reached apart from one that was refused
+pub trait Repository: Sync {
+    fn is_offline(&self) -> impl Future<Output = bool> + Send;
+}
+
+// Storage the commands read and write through, ids of None or below zero are new rows
+pub trait StatBlockRepository: Repository {
+    fn fetch_statblocks(&self) -> impl Future<Output = Result<Vec<StatBlock>, AppError>> + Send;
+
+    fn fetch_statblocks_by_ids(
+        &self,
+        statblock_ids: &[i64],
+    ) -> impl Future<Output = Result<Vec<StatBlock>, AppError>> + Send;
+
+    // With last_seen an update is refused and returned as a conflict if the stored row changed since
+    fn save_statblock(
+        &self,
+        stat_block: &mut StatBlock,
+        last_seen: Option<&str>,
+    ) -> impl Future<Output = Result<SaveStatBlockResponse, AppError>> + Send;
+
+    fn delete_statblock(
+        &self,
+        statblock_id: i64,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+}
+
+pub trait EncounterRepository: Repository {
+    fn fetch_encounters(&self) -> impl Future<Output = Result<Vec<Encounter>, AppError>> + Send;
+
+    fn fetch_encounter(
+        &self,
+        encounter_id: i64,
+    ) -> impl Future<Output = Result<Option<Encounter>, AppError>> + Send;
+
+    fn save_encounter(
+        &self,
+        encounter: Encounter,
+        last_seen: Option<&str>,
+    ) -> impl Future<Output = Result<SaveEncounterResponse, AppError>> + Send;
+
+    // Removes the Encounter's EncounterPlayers and PlayableStatBlocks with it
+    fn delete_encounter(
+        &self,
+        encounter_id: i64,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    fn fetch_encounter_players(
+        &self,
+        encounter_id: i64,
+    ) -> impl Future<Output = Result<Vec<EncounterPlayer>, AppError>> + Send;
+
+    // Replaces every EncounterPlayer of the first player's Encounter
+    fn save_encounter_players(
+        &self,
+        encounter_players: Vec<EncounterPlayer>,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    fn fetch_playable_statblocks(
+        &self,
+        encounter_id: i64,
+    ) -> impl Future<Output = Result<Vec<PlayableStatBlock>, AppError>> + Send;
+
+    // Replaces every PlayableStatBlock of the first instance's Encounter, new instances get an id
+    fn save_playable_statblocks(
+        &self,
+        playable_stat_blocks: Vec<PlayableStatBlock>,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    // Updates only the columns in body, a combatant that doesn't exist is left alone
+    fn patch_combatant(
+        &self,
+        combatant: &CombatantRef,
+        encounter_id: i64,
+        body: &serde_json::Value,
+    ) -> impl Future<Output = Result<(), AppError>> + Send;
+}
+
+pub struct SupabaseRepository {
+    pub client: SupabaseClient,
+}
+
+impl SupabaseRepository {
+    // Also switches the local store to the token's user, so offline fallbacks only see their rows
+    pub async fn connect(access_token: &str) -> Result<Self, AppError> {
+        open_user_store(&token_user_id(access_token)?)?;
+
+        Ok(SupabaseRepository {
+            client: SupabaseClient::connect(access_token).await?,
+        })
+    }
+}
+
+impl Repository for SupabaseRepository {
+    async fn is_offline(&self) -> bool {
+        is_offline().await
+    }
+}
+
+// Serves a failed request from the local store when the repository can't be reached, any other error is returned
+pub async fn offline_fallback<T>(
+    repository: &impl Repository,
+    error: AppError,
+    local: impl FnOnce() -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    if repository.is_offline().await {
+        local()
+    } else {
+        Err(error)
+    }
+}
+
+#[cfg(test)]
+impl SupabaseRepository {
+    // Sends every request to a mock server instead of the configured project
+    pub fn at(url: String) -> Self {
+        SupabaseRepository {
+            client: SupabaseClient::new(
+                SupabaseConfig {
+                    url,
+                    anon_key: "anon".to_string(),
+                },
+                "access-token",
+            ),
+        }
+    }
+}
@@ -2,21 +2,22 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     database::{
-        action_db::save_statblock_actions_helper,
-        condition_type_db::save_statblock_condition_immunities_helper,
-        damage_type_db::save_statblock_damage_types_helper,
-        proficiency_type_db::{
-            save_statblock_save_proficiency_helper, save_statblock_skill_proficiency_helper,
+        local_db::{
+            delete_local_statblock, fetch_local_statblocks, fetch_local_statblocks_by_ids,
+            local_last_modified, remap_temporary_id, replace_local_statblocks,
+            save_local_statblock,
         },
-        spell_db::save_statblock_spells_helper,
-        trait_db::save_statblock_traits_helper,
+        repository::{offline_fallback, StatBlockRepository, SupabaseRepository},
     },
     types::{
         action_types::ActionDB,
+        conflict_types::StatBlockConflict,
         damage_types::DamageTypeDB,
+        error_types::AppError,
         statblock_types::{StatBlock, StatBlockFromDB},
+        sync_types::SyncOperation,
     },
-    utils::supabase_util::init_supabase,
+    utils::sync_utils::queue_offline_change,
 };
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -25,13 +26,23 @@ pub struct SaveStatBlockResponse {
     pub status: u16,
     pub message: String,
     pub was_updated: bool,
+    // Set by the server on every save, the last_seen to send with the next one
+    pub last_modified: String,
+    // Set when the StatBlock changed on the server since last_seen, nothing was written
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflict: Option<StatBlockConflict>,
+}
+
+// What the save_statblock function returns for a written row
+#[derive(Deserialize, Debug)]
+struct SavedStatBlock {
+    id: i64,
+    last_modified: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RetrieveStatBlockResponse {
     pub statblocks: Vec<StatBlock>,
-    pub status: u16,
-    pub message: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -49,203 +60,492 @@ pub struct RetrieveStatBlockDamageTypesResponse {
     pub vulnerabilities: Vec<DamageTypeDB>,
 }
 
+const STATBLOCK_JOIN_QUERY: &str = "select=*,\
+    Action(name, description, attack),\
+    BonusAction(name, description, attack),\
+    Reaction(name, description, attack),\
+    LegendaryAction(name, description, attack),\
+    DamageResistance(damage_type),\
+    DamageImmunity(damage_type),\
+    DamageVulnerability(damage_type),\
+    ConditionImmunity(condition_type),\
+    Trait(name, description),\
+    SaveProficiency(score, level),\
+    SkillProficiency(ability, level),\
+    Spells(name, spell_list)";
+
 //? UPSERT
 
 #[tauri::command]
 pub async fn save_statblock(
-    mut stat_block: StatBlock,
+    stat_block: StatBlock,
     access_token: String,
-) -> Result<SaveStatBlockResponse, String> {
-    let config = init_supabase().await.map_err(|e| e.to_string())?;
-    let client = reqwest::Client::new();
-    let insert_obj = stat_block.statblock_to_db();
-
-    let (method, url) = if let Some(id) = stat_block.id {
-        (
-            reqwest::Method::PATCH,
-            format!("{}/rest/v1/StatBlock?id=eq.{}", config.url, id),
-        )
-    } else {
-        (
-            reqwest::Method::POST,
-            format!("{}/rest/v1/StatBlock", config.url),
-        )
-    };
+    last_seen: Option<String>,
+) -> Result<SaveStatBlockResponse, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
 
-    let response = client
-        .request(method.clone(), &url)
-        .header("apikey", &config.anon_key)
-        .header("Authorization", format!("Bearer {}", &access_token))
-        .header("Content-Type", "application/json")
-        .header("Prefer", "return=representation")
-        .body(serde_json::to_string(&vec![insert_obj]).map_err(|e| e.to_string())?)
-        .send()
-        .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+    save_statblock_to(&repository, stat_block, last_seen).await
+}
 
-    let status = response.status();
-    let text = response.text().await.map_err(|e| e.to_string())?;
+pub async fn save_statblock_to(
+    repository: &impl StatBlockRepository,
+    mut stat_block: StatBlock,
+    last_seen: Option<String>,
+) -> Result<SaveStatBlockResponse, AppError> {
+    stat_block.validate_attacks()?;
 
-    if !status.is_success() {
-        return Err(format!(
-            "Supabase {} error {}: {}",
-            if method == reqwest::Method::PATCH {
-                "update"
-            } else {
-                "insert"
-            },
-            status,
-            text
-        ));
+    let temporary_id = stat_block.id.filter(|id| *id < 0);
+
+    match repository
+        .save_statblock(&mut stat_block, last_seen.as_deref())
+        .await
+    {
+        Ok(response) if response.conflict.is_some() => Ok(response),
+        Ok(response) => {
+            let cached = match temporary_id {
+                Some(temporary_id) => remap_temporary_id("StatBlock", temporary_id, response.id)
+                    .and_then(|_| save_local_statblock(&mut stat_block)),
+                None => save_local_statblock(&mut stat_block),
+            };
+            if let Err(e) = cached {
+                eprintln!("Failed to cache StatBlock locally: {}", e);
+            }
+            Ok(response)
+        }
+        Err(e) => {
+            offline_fallback(repository, e, || {
+                let was_updated = stat_block.id.is_some();
+                let base_last_modified = match stat_block.id.filter(|id| *id > 0) {
+                    Some(_) if last_seen.is_some() => last_seen,
+                    Some(id) => local_last_modified("StatBlock", id)?,
+                    None => None,
+                };
+                let id = save_local_statblock(&mut stat_block)?;
+                queue_offline_change(SyncOperation::SaveStatBlock {
+                    statblock_id: id,
+                    base_last_modified,
+                })?;
+
+                Ok(SaveStatBlockResponse {
+                    id,
+                    status: 202,
+                    message: "StatBlock saved locally while offline".to_string(),
+                    was_updated,
+                    last_modified: stat_block.last_modified,
+                    conflict: None,
+                })
+            })
+            .await
+        }
     }
+}
 
-    let returned_records: Vec<serde_json::Value> =
-        serde_json::from_str(&text).map_err(|e| format!("Failed to parse response: {}", e))?;
+//? GET
 
-    if let Some(record) = returned_records.first() {
-        let id = record
-            .get("id")
-            .and_then(|v| v.as_i64())
-            .ok_or("No ID returned from Supabase")?;
+#[tauri::command]
+pub async fn fetch_statblocks_with_joins(
+    access_token: String,
+) -> Result<RetrieveStatBlockResponse, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
 
-        //? StatBlock must exist now
+    fetch_statblocks_from(&repository).await
+}
 
-        stat_block.id = Some(id);
+pub async fn fetch_statblocks_from(
+    repository: &impl StatBlockRepository,
+) -> Result<RetrieveStatBlockResponse, AppError> {
+    match repository.fetch_statblocks().await {
+        Ok(statblocks) => {
+            if let Err(e) = replace_local_statblocks(&statblocks) {
+                eprintln!("Failed to cache StatBlocks locally: {}", e);
+            }
+            Ok(RetrieveStatBlockResponse { statblocks })
+        }
+        Err(e) => {
+            offline_fallback(repository, e, || {
+                let statblocks = fetch_local_statblocks()?;
 
-        save_statblock_actions_helper(&stat_block, &config, &client, &access_token).await?;
-        save_statblock_traits_helper(&stat_block, &config, &client, &access_token).await?;
-        save_statblock_damage_types_helper(&stat_block, &config, &client, &access_token).await?;
-        save_statblock_condition_immunities_helper(&stat_block, &config, &client, &access_token)
-            .await?;
-        save_statblock_save_proficiency_helper(&stat_block, &config, &client, &access_token)
-            .await?;
-        save_statblock_skill_proficiency_helper(&stat_block, &config, &client, &access_token)
-            .await?;
-        save_statblock_spells_helper(&stat_block, &config, &client, &access_token).await?;
+                Ok(RetrieveStatBlockResponse { statblocks })
+            })
+            .await
+        }
+    }
+}
 
-        return Ok(SaveStatBlockResponse {
-            id,
-            status: status.as_u16(),
-            message: if method == reqwest::Method::PATCH {
-                "StatBlock updated successfully".to_string()
-            } else {
-                "StatBlock created successfully".to_string()
-            },
-            was_updated: method == reqwest::Method::PATCH,
-        });
+pub async fn fetch_statblocks_by_ids(
+    repository: &impl StatBlockRepository,
+    statblock_ids: &[i64],
+) -> Result<Vec<StatBlock>, AppError> {
+    if statblock_ids.is_empty() {
+        return Ok(Vec::new());
     }
 
-    Err("No data returned from Supabase".to_string())
+    match repository.fetch_statblocks_by_ids(statblock_ids).await {
+        Ok(statblocks) => Ok(statblocks),
+        Err(e) => {
+            offline_fallback(repository, e, || {
+                fetch_local_statblocks_by_ids(statblock_ids)
+            })
+            .await
+        }
+    }
 }
 
-//? GET
+//? DELETE
 
 #[tauri::command]
-pub async fn fetch_statblocks_with_joins(
+pub async fn delete_statblock(
+    statblock: StatBlock,
     access_token: String,
-) -> Result<RetrieveStatBlockResponse, RetrieveStatBlockResponse> {
-    let config = init_supabase()
-        .await
-        .map_err(|e| RetrieveStatBlockResponse {
-            statblocks: Vec::new(),
-            status: 500,
-            message: e.to_string(),
-        })?;
-    let client = reqwest::Client::new();
-
-    let query = "select=*,\
-        Action(name, description),\
-        BonusAction(name, description),\
-        Reaction(name, description),\
-        LegendaryAction(name, description),\
-        DamageResistance(damage_type),\
-        DamageImmunity(damage_type),\
-        DamageVulnerability(damage_type),\
-        ConditionImmunity(condition_type),\
-        Trait(name, description),\
-        SaveProficiency(score, level),\
-        SkillProficiency(ability, level),\
-        Spells(name, spell_list)
-    ";
-
-    let get_url = format!("{}/rest/v1/StatBlock?{}", config.url, query);
-
-    let response = client
-        .get(&get_url)
-        .header("apikey", &config.anon_key)
-        .header("Authorization", format!("Bearer {}", access_token))
-        .header("Content-Type", "application/json")
-        .send()
-        .await
-        .map_err(|e| RetrieveStatBlockResponse {
-            statblocks: Vec::new(),
-            status: 500,
-            message: e.to_string(),
-        })?;
-
-    let status = response.status();
-
-    if !status.is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(RetrieveStatBlockResponse {
-            statblocks: Vec::new(),
-            status: status.as_u16(),
-            message: format!("StatBlock fetch failed: {}", error_text),
+) -> Result<String, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
+
+    delete_statblock_from(&repository, statblock).await
+}
+
+pub async fn delete_statblock_from(
+    repository: &impl StatBlockRepository,
+    statblock: StatBlock,
+) -> Result<String, AppError> {
+    let Some(statblock_id) = statblock.id else {
+        return Err(AppError::validation("id", "StatBlock has no id to delete"));
+    };
+
+    let offline = if statblock_id < 0 {
+        // Never reached Supabase, so there is nothing to delete there
+        true
+    } else {
+        match repository.delete_statblock(statblock_id).await {
+            Ok(_) => false,
+            Err(_) if repository.is_offline().await => true,
+            Err(e) => return Err(e),
+        }
+    };
+
+    if offline {
+        queue_offline_change(SyncOperation::DeleteStatBlock { statblock_id })?;
+        delete_local_statblock(statblock_id)?;
+        return Ok("StatBlock deleted locally".to_string());
+    }
+
+    if let Err(e) = delete_local_statblock(statblock_id) {
+        eprintln!("Failed to delete local StatBlock: {}", e);
+    }
+    Ok("StatBlock Delete succeeded".to_string())
+}
+
+//? Supabase
+
+impl StatBlockRepository for SupabaseRepository {
+    async fn fetch_statblocks(&self) -> Result<Vec<StatBlock>, AppError> {
+        let get_url = format!(
+            "{}/rest/v1/StatBlock?{}",
+            self.client.config.url, STATBLOCK_JOIN_QUERY
+        );
+
+        self.fetch_statblocks_at(&get_url).await
+    }
+
+    async fn fetch_statblocks_by_ids(
+        &self,
+        statblock_ids: &[i64],
+    ) -> Result<Vec<StatBlock>, AppError> {
+        let ids = statblock_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+
+        let get_url = format!(
+            "{}/rest/v1/StatBlock?{}&id=in.({})",
+            self.client.config.url, STATBLOCK_JOIN_QUERY, ids
+        );
+
+        self.fetch_statblocks_at(&get_url).await
+    }
+
+    // Goes through the save_statblock function so the StatBlock and its child rows are written in one transaction
+    async fn save_statblock(
+        &self,
+        stat_block: &mut StatBlock,
+        last_seen: Option<&str>,
+    ) -> Result<SaveStatBlockResponse, AppError> {
+        // Temporary ids from the local store have no row on the server yet
+        let existing_id = stat_block.id.filter(|id| *id > 0);
+
+        // Child rows get the StatBlock's id inside the transaction, so any id will do here
+        let mut child_source = stat_block.clone();
+        child_source.id.get_or_insert(0);
+
+        let body = serde_json::json!({
+            "statblock": stat_block.statblock_to_db(),
+            "children": child_source.children_to_db()?,
+            "target_id": existing_id,
+            "last_seen": last_seen,
         });
+
+        let rpc_url = format!("{}/rest/v1/rpc/save_statblock", self.client.config.url);
+        let response = self
+            .client
+            .send(|client| client.post(&rpc_url).json(&body))
+            .await?;
+
+        if !response.status().is_success() {
+            let context = if existing_id.is_some() {
+                "StatBlock update failed"
+            } else {
+                "StatBlock insert failed"
+            };
+            return Err(AppError::from_response(context, response).await);
+        }
+
+        let saved: Option<SavedStatBlock> = response
+            .json()
+            .await
+            .map_err(|e| AppError::parse(format!("Failed to parse response: {}", e)))?;
+
+        if let Some(SavedStatBlock { id, last_modified }) = saved {
+            stat_block.id = Some(id);
+            stat_block.last_modified = last_modified.clone();
+
+            return Ok(SaveStatBlockResponse {
+                id,
+                status: if existing_id.is_some() { 200 } else { 201 },
+                message: if existing_id.is_some() {
+                    "StatBlock updated successfully".to_string()
+                } else {
+                    "StatBlock created successfully".to_string()
+                },
+                was_updated: existing_id.is_some(),
+                last_modified,
+                conflict: None,
+            });
+        }
+
+        // Nothing written for an existing id means the row is gone or changed since last_seen
+        if let Some(id) = existing_id {
+            if last_seen.is_none() {
+                return Err(AppError::not_found(format!(
+                    "StatBlock {} no longer exists on the server",
+                    id
+                )));
+            }
+
+            let server = StatBlockRepository::fetch_statblocks_by_ids(self, &[id])
+                .await?
+                .into_iter()
+                .next()
+                .ok_or(AppError::not_found(format!(
+                    "StatBlock {} no longer exists on the server",
+                    id
+                )))?;
+
+            return statblock_conflict(id, stat_block.clone(), server);
+        }
+
+        Err(AppError::storage("No data returned from Supabase"))
+    }
+
+    async fn delete_statblock(&self, statblock_id: i64) -> Result<(), AppError> {
+        let delete_url = format!(
+            "{}/rest/v1/StatBlock?id=eq.{}",
+            self.client.config.url, statblock_id
+        );
+
+        let response = self
+            .client
+            .send(|client| client.delete(&delete_url))
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(AppError::from_response("StatBlock Delete failed", response).await);
+        }
+
+        Ok(())
     }
+}
+
+impl SupabaseRepository {
+    async fn fetch_statblocks_at(&self, get_url: &str) -> Result<Vec<StatBlock>, AppError> {
+        let response = self.client.send(|client| client.get(get_url)).await?;
 
-    let statblock_join: Vec<StatBlockFromDB> =
-        response
+        if !response.status().is_success() {
+            return Err(AppError::from_response("StatBlock fetch failed", response).await);
+        }
+
+        let rows: Vec<serde_json::Value> = response
             .json()
             .await
-            .map_err(|e| RetrieveStatBlockResponse {
-                statblocks: Vec::new(),
-                status: 500,
-                message: format!("Failed to parse StatBlock response: {}", e.to_string()),
-            })?;
-
-    let statblocks: Vec<StatBlock> = statblock_join
-        .into_iter()
-        .map(|sb_with_relations| StatBlock::statblock_from_db(&sb_with_relations))
-        .collect();
-
-    Ok(RetrieveStatBlockResponse {
-        statblocks,
-        status: status.as_u16(),
-        message: "Successfully retrieved statblocks with relations".to_string(),
+            .map_err(|e| AppError::parse(format!("Failed to parse StatBlock response: {}", e)))?;
+
+        Ok(statblocks_from_rows(rows))
+    }
+}
+
+//? Helper Util
+
+pub fn statblock_conflict(
+    id: i64,
+    local: StatBlock,
+    server: StatBlock,
+) -> Result<SaveStatBlockResponse, AppError> {
+    Ok(SaveStatBlockResponse {
+        id,
+        status: 409,
+        message: format!(
+            "StatBlock was changed by someone else at {}",
+            server.last_modified
+        ),
+        was_updated: false,
+        last_modified: server.last_modified.clone(),
+        conflict: Some(StatBlockConflict::new(local, server)?),
     })
 }
 
-//? DELETE
+// Rows are parsed one at a time so a legacy row, such as one with a free text cr, can't hide the rest of the library
+fn statblocks_from_rows(rows: Vec<serde_json::Value>) -> Vec<StatBlock> {
+    rows.into_iter()
+        .filter_map(|row| {
+            let id = row["id"].clone();
+            match serde_json::from_value::<StatBlockFromDB>(row) {
+                Ok(db) => Some(StatBlock::statblock_from_db(&db)),
+                Err(e) => {
+                    eprintln!("Skipping StatBlock {} that failed to parse: {}", id, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
 
-#[tauri::command]
-pub async fn delete_statblock(
-    statblock: StatBlock,
-    access_token: String,
-) -> Result<String, String> {
-    let config = init_supabase().await.map_err(|e| e.to_string())?;
-    let client = reqwest::Client::new();
-
-    if let Some(statblock_id) = statblock.id {
-        let delete_url = format!("{}/rest/v1/StatBlock?id=eq.{}", config.url, statblock_id);
-
-        let response = client
-            .delete(&delete_url)
-            .header("apikey", &config.anon_key)
-            .header("Authorization", format!("Bearer {}", access_token))
-            .header("Content-Type", "application/json")
-            .send()
+pub fn last_modified_filter(last_seen: Option<&str>) -> String {
+    last_seen
+        .map(|last_seen| format!("&last_modified=eq.{}", urlencoding::encode(last_seen)))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::Matcher;
+    use serde_json::json;
+
+    use super::*;
+    use crate::utils::import_utils::empty_statblock;
+
+    fn goblin(id: i64) -> StatBlock {
+        let mut stat_block = empty_statblock("Goblin");
+        stat_block.id = Some(id);
+        stat_block.last_modified = "2026-10-18T12:00:00Z".to_string();
+        stat_block
+    }
+
+    // The joined row fetch_statblocks_by_ids reads back, without any child rows
+    fn statblock_row(stat_block: &StatBlock) -> serde_json::Value {
+        let mut row = serde_json::to_value(stat_block.statblock_to_db()).unwrap();
+        row["id"] = json!(stat_block.id);
+        row
+    }
+
+    #[tokio::test]
+    async fn update_returns_the_last_modified_the_server_stamped() {
+        let mut server = mockito::Server::new_async().await;
+        let rpc = server
+            .mock("POST", "/rest/v1/rpc/save_statblock")
+            .match_body(Matcher::PartialJson(json!({
+                "target_id": 5,
+                "last_seen": "2026-10-18T12:00:00Z",
+            })))
+            .with_body(r#"{"id": 5, "last_modified": "2026-10-18T12:30:00.123+00:00"}"#)
+            .create_async()
+            .await;
+        let repository = SupabaseRepository::at(server.url());
+        let mut stat_block = goblin(5);
+
+        let response = repository
+            .save_statblock(&mut stat_block, Some("2026-10-18T12:00:00Z"))
+            .await
+            .unwrap();
+
+        rpc.assert_async().await;
+        assert!(response.was_updated);
+        assert_eq!(response.last_modified, "2026-10-18T12:30:00.123+00:00");
+        assert_eq!(stat_block.last_modified, "2026-10-18T12:30:00.123+00:00");
+    }
+
+    #[tokio::test]
+    async fn stale_update_returns_a_conflict_with_the_server_copy() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/rest/v1/rpc/save_statblock")
+            .with_body("null")
+            .create_async()
+            .await;
+
+        let mut stored = goblin(5);
+        stored.name = "Goblin Boss".to_string();
+        stored.hp = 21;
+        stored.last_modified = "2026-10-18T12:05:00Z".to_string();
+        server
+            .mock("GET", "/rest/v1/StatBlock")
+            .match_query(Matcher::UrlEncoded("id".into(), "in.(5)".into()))
+            .with_body(json!([statblock_row(&stored)]).to_string())
+            .create_async()
+            .await;
+        let repository = SupabaseRepository::at(server.url());
+        let mut stat_block = goblin(5);
+
+        let response = repository
+            .save_statblock(&mut stat_block, Some("2026-10-18T12:00:00Z"))
             .await
-            .map_err(|e| format!("StatBlock Delete failed: {}", e))?;
+            .unwrap();
+
+        assert_eq!(response.status, 409);
+        assert!(!response.was_updated);
+        assert_eq!(response.last_modified, "2026-10-18T12:05:00Z");
+        // The local copy keeps the version it was based on
+        assert_eq!(stat_block.last_modified, "2026-10-18T12:00:00Z");
+
+        let conflict = response.conflict.unwrap();
+        let fields: Vec<&str> = conflict
+            .differences
+            .iter()
+            .map(|difference| difference.field.as_str())
+            .collect();
+        assert_eq!(fields, ["hp", "name"]);
+    }
 
-        let status = response.status();
+    #[tokio::test]
+    async fn update_of_a_deleted_statblock_is_not_found() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/rest/v1/rpc/save_statblock")
+            .with_body("null")
+            .create_async()
+            .await;
+        let repository = SupabaseRepository::at(server.url());
 
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(format!("StatBlock Delete failed: {}", error_text));
-        }
+        let result = repository.save_statblock(&mut goblin(5), None).await;
+
+        assert!(matches!(result, Err(AppError::NotFound { .. })));
+    }
 
-        return Ok("StatBlock Delete succeeded".to_string());
+    #[tokio::test]
+    async fn rows_with_a_malformed_cr_are_skipped() {
+        let mut server = mockito::Server::new_async().await;
+        let mut legacy = statblock_row(&goblin(6));
+        legacy["name"] = json!("Goblin Boss");
+        legacy["cr"] = json!("CR 1/2");
+        server
+            .mock("GET", "/rest/v1/StatBlock")
+            .match_query(Matcher::Any)
+            .with_body(json!([statblock_row(&goblin(5)), legacy]).to_string())
+            .create_async()
+            .await;
+        let repository = SupabaseRepository::at(server.url());
+
+        let statblocks = repository.fetch_statblocks().await.unwrap();
+
+        let ids: Vec<Option<i64>> = statblocks.iter().map(|stat_block| stat_block.id).collect();
+        assert_eq!(ids, [Some(5)]);
     }
-    Err("StatBlock Delete failed: No StatBlock ID".to_string())
 }
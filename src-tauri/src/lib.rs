@@ -2,15 +2,34 @@ mod database;
 mod types;
 mod utils;
 
+use database::local_db::init_local_store;
 use database::statblock_db::{delete_statblock, save_statblock};
 use tauri::{Emitter, Manager};
 #[cfg(desktop)]
 use tauri_plugin_deep_link::DeepLinkExt;
+use types::error_types::AppError;
+use utils::combat_utils::{
+    delay_turn, end_combat, get_combat_session, next_turn, previous_turn, ready_action,
+    resume_delayed_turn, roll_initiative, start_combat, trigger_readied_action,
+};
+use utils::condition_utils::{apply_condition, remove_condition};
+use utils::credential_utils::migrate_plaintext_credentials;
+use utils::damage_utils::{apply_damage, apply_healing};
+use utils::dice_utils::roll_dice;
+use utils::difficulty_utils::{calculate_difficulty, calculate_encounter_difficulty};
+use utils::fivetools_utils::import_fivetools_statblocks;
+use utils::foundry_utils::{export_encounter_foundry, export_statblock_foundry};
 use utils::fs_utils::{load_encounters, load_statblocks};
+use utils::homebrewery_utils::{export_encounter_homebrewery, export_statblock_homebrewery};
+use utils::html_utils::{export_encounter_html, render_statblock_html};
+use utils::open5e_utils::{import_open5e_directory, import_open5e_statblocks};
+use utils::statblock_utils::derive_statblock_stats;
+use utils::sync_utils::{discard_sync_operation, get_sync_status, start_sync_worker, sync_now};
 
 use utils::auth_utils::{
-    get_current_user, get_stored_value, handle_discord_oauth_callback, login_with_discord,
-    login_with_email, logout_user, register_with_email, remove_stored_value, store_value,
+    complete_discord_login, get_current_user, get_stored_value, handle_discord_oauth_callback,
+    init_session, login_with_discord, login_with_email, logout_user, parse_oauth_callback,
+    refresh_session_tokens, register_with_email, remove_stored_value, store_value,
 };
 
 use crate::database::encounter_db::{
@@ -19,12 +38,11 @@ use crate::database::encounter_db::{
     save_playable_statblocks,
 };
 use crate::database::statblock_db::fetch_statblocks_with_joins;
-use crate::utils::auth_utils::refresh_access_token;
 
 #[tauri::command]
-async fn open_url(url: &str) -> Result<(), String> {
+async fn open_url(url: &str) -> Result<(), AppError> {
     tauri_plugin_opener::open_url(&url, None::<&str>)
-        .map_err(|e| format!("Failed to open URL: {}", e))?;
+        .map_err(|e| AppError::network(format!("Failed to open URL: {}", e)))?;
 
     Ok(())
 }
@@ -50,6 +68,14 @@ pub fn run() {
             }
         }))
         .setup(|app| {
+            let app_dir = app.path().app_data_dir()?;
+            init_local_store(&app_dir.join("local_store"))?;
+            init_session(app.handle().clone());
+            if let Err(e) = migrate_plaintext_credentials(app.handle()) {
+                eprintln!("Failed to migrate stored credentials: {}", e);
+            }
+            start_sync_worker(app.handle().clone());
+
             if let Ok(Some(urls)) = app.deep_link().get_current() {
                 println!("Current deep link URL: {:?}", urls);
 
@@ -93,106 +119,79 @@ pub fn run() {
             open_url,
             register_with_email,
             login_with_email,
-            refresh_access_token,
+            refresh_session_tokens,
             save_statblock,
             delete_statblock,
             fetch_statblocks_with_joins,
+            calculate_difficulty,
+            calculate_encounter_difficulty,
+            roll_dice,
+            derive_statblock_stats,
+            import_fivetools_statblocks,
+            import_open5e_statblocks,
+            import_open5e_directory,
+            export_statblock_homebrewery,
+            export_encounter_homebrewery,
+            render_statblock_html,
+            export_encounter_html,
+            export_statblock_foundry,
+            export_encounter_foundry,
+            start_combat,
+            get_combat_session,
+            next_turn,
+            previous_turn,
+            delay_turn,
+            resume_delayed_turn,
+            ready_action,
+            trigger_readied_action,
+            end_combat,
+            roll_initiative,
+            apply_damage,
+            apply_healing,
+            apply_condition,
+            remove_condition,
+            get_sync_status,
+            sync_now,
+            discard_sync_operation,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
-fn handle_deep_link_url(
-    app_handle: &tauri::AppHandle,
-    url: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    use url::Url;
-
-    let parsed_url = Url::parse(url)?;
-
-    if parsed_url.scheme() == "encounterarchitect" && parsed_url.host_str() == Some("oauth") {
-        if let Some(fragment) = parsed_url.fragment() {
-            let fragment_url = Url::parse(&format!("http://localhost?{}", fragment))?;
-            let query_pairs: std::collections::HashMap<_, _> = fragment_url.query_pairs().collect();
-
-            if let Some(access_token) = query_pairs.get("access_token") {
-                let access_token_str = access_token.to_string();
-                let refresh_token_str = query_pairs
-                    .get("refresh_token")
-                    .map(|s| s.to_string())
-                    .unwrap_or_default();
-
-                let app_handle_clone = app_handle.clone();
-                tauri::async_runtime::spawn(async move {
-                    if let Err(e) = store_value(
-                        app_handle_clone.clone(),
-                        "access_token".to_string(),
-                        access_token_str.clone(),
-                    )
-                    .await
-                    {
-                        eprintln!("Failed to store access token: {}", e);
-                    } else {
-                        println!("Successfully stored access token");
-                    }
-
-                    if !refresh_token_str.is_empty() {
-                        if let Err(e) = store_value(
-                            app_handle_clone.clone(),
-                            "refresh_token".to_string(),
-                            refresh_token_str.clone(),
-                        )
-                        .await
-                        {
-                            eprintln!("Failed to st ore refresh token: {}", e);
-                        } else {
-                            println!("Successfully stored refresh token");
-                        }
-                    }
-
-                    match get_current_user(access_token_str).await {
-                        Ok(user) => {
-                            if let Err(e) = store_value(
-                                app_handle_clone.clone(),
-                                "user".to_string(),
-                                user.to_string(),
-                            )
-                            .await
-                            {
-                                eprintln!("Failed to store user info: {}", e);
-                            } else {
-                                println!("Successfully stored user info");
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to get user info: {}", e);
-                        }
-                    }
-
-                    if let Err(e) = app_handle_clone.emit(
-                        "oauth-success",
-                        serde_json::json!({
-                           "message": "Successfully authenticated with Discord"
-                        }),
-                    ) {
-                        eprintln!("Failed to emit oauth-success event: {}", e);
-                    } else {
-                        println!("Emitted oauth-success event");
-                    }
-                });
-
-                if let Some(window) = app_handle.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
-
-                    println!("Focused app window");
+fn handle_deep_link_url(app_handle: &tauri::AppHandle, url: &str) -> Result<(), AppError> {
+    let Some(callback) = parse_oauth_callback(url)? else {
+        return Ok(());
+    };
+
+    let app_handle_clone = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        match complete_discord_login(&app_handle_clone, callback).await {
+            Ok(()) => {
+                if let Err(e) = app_handle_clone.emit(
+                    "oauth-success",
+                    serde_json::json!({
+                       "message": "Successfully authenticated with Discord"
+                    }),
+                ) {
+                    eprintln!("Failed to emit oauth-success event: {}", e);
+                } else {
+                    println!("Emitted oauth-success event");
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to complete Discord login: {}", e);
+                if let Err(emit_error) = app_handle_clone.emit("oauth-error", &e) {
+                    eprintln!("Failed to emit oauth-error event: {}", emit_error);
                 }
-            } else {
-                println!("No access token found in deep link fragment");
             }
-        } else {
-            println!("No fragment found in OAuth deep link");
         }
+    });
+
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+
+        println!("Focused app window");
     }
 
     Ok(())
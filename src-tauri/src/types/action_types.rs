@@ -1,11 +1,101 @@
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
+use crate::{
+    types::{damage_types::DamageType, error_types::AppError, statblock_types::Score},
+    utils::dice_utils::DiceExpression,
+};
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[typeshare]
+pub enum AttackKind {
+    MeleeWeapon,
+    RangedWeapon,
+    MeleeOrRangedWeapon,
+    MeleeSpell,
+    RangedSpell,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct DamageComponent {
+    pub dice: String,
+    pub damage_type: DamageType,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct SavingThrow {
+    pub score: Score,
+    pub dc: u8,
+    #[serde(default)]
+    pub half_on_success: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct Attack {
+    // None for save-only actions like breath weapons
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<AttackKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_hit: Option<i8>,
+    // Feet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reach: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub long_range: Option<u16>,
+    pub targets: u8,
+    pub damage: Vec<DamageComponent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saving_throw: Option<SavingThrow>,
+    // Lowest d6 result that recharges the action, e.g. 5 for "Recharge 5-6"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recharge: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uses_per_day: Option<u8>,
+}
+
+impl Attack {
+    pub fn validate(&self, action_name: &str) -> Result<(), AppError> {
+        if self.targets == 0 {
+            return Err(AppError::validation(
+                "attack.targets",
+                format!("{}: an attack needs at least one target", action_name),
+            ));
+        }
+
+        if let Some(recharge) = self.recharge {
+            if !(2..=6).contains(&recharge) {
+                return Err(AppError::validation(
+                    "attack.recharge",
+                    format!(
+                        "{}: recharge must be between 2 and 6, got {}",
+                        action_name, recharge
+                    ),
+                ));
+            }
+        }
+
+        for component in &self.damage {
+            component.dice.parse::<DiceExpression>().map_err(|e| {
+                AppError::validation("attack.damage", format!("{}: {}", action_name, e))
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[typeshare]
 pub struct Action {
     pub name: String,
     pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attack: Option<Attack>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -13,4 +103,5 @@ pub struct ActionDB {
     pub statblock_id: i64,
     pub name: String,
     pub description: String,
+    pub attack: Option<Attack>,
 }
@@ -50,3 +50,19 @@ pub struct RegisterRequest {
     pub email: String,
     pub password: String,
 }
+
+// A Discord login waiting for its deep link, kept until the code is exchanged
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingOAuth {
+    pub state: String,
+    pub code_verifier: String,
+    // Milliseconds, the same as token_expires_at
+    pub created_at: i64,
+}
+
+// The code and state Supabase appends to the redirect once Discord accepts the login
+#[derive(Debug, PartialEq, Eq)]
+pub struct OAuthCallback {
+    pub code: String,
+    pub state: String,
+}
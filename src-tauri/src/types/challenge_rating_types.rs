@@ -0,0 +1,214 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+// XP for CR 1 through 30, indexed by CR - 1
+const CR_XP: [u32; 30] = [
+    200, 450, 700, 1100, 1800, 2300, 2900, 3900, 5000, 5900, 7200, 8400, 10000, 11500, 13000,
+    15000, 18000, 20000, 22000, 25000, 33000, 41000, 50000, 62000, 75000, 90000, 105000, 120000,
+    135000, 155000,
+];
+
+// Serialized as "0", "1/8", "1/4", "1/2" or "1" through "30"
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(try_from = "String", into = "String")]
+#[typeshare(serialized_as = "String")]
+pub enum ChallengeRating {
+    Zero,
+    Eighth,
+    Quarter,
+    Half,
+    Whole(u8),
+}
+
+impl ChallengeRating {
+    pub fn xp(&self) -> u32 {
+        match self {
+            ChallengeRating::Zero => 10,
+            ChallengeRating::Eighth => 25,
+            ChallengeRating::Quarter => 50,
+            ChallengeRating::Half => 100,
+            ChallengeRating::Whole(cr) => CR_XP[(*cr).clamp(1, 30) as usize - 1],
+        }
+    }
+
+    pub fn proficiency_bonus(&self) -> u8 {
+        match self {
+            ChallengeRating::Whole(cr) if *cr >= 5 => 2 + (*cr - 1) / 4,
+            _ => 2,
+        }
+    }
+
+    pub fn as_f32(&self) -> f32 {
+        match self {
+            ChallengeRating::Zero => 0.0,
+            ChallengeRating::Eighth => 0.125,
+            ChallengeRating::Quarter => 0.25,
+            ChallengeRating::Half => 0.5,
+            ChallengeRating::Whole(cr) => *cr as f32,
+        }
+    }
+}
+
+impl FromStr for ChallengeRating {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "0" => Ok(ChallengeRating::Zero),
+            "1/8" => Ok(ChallengeRating::Eighth),
+            "1/4" => Ok(ChallengeRating::Quarter),
+            "1/2" => Ok(ChallengeRating::Half),
+            other => other
+                .parse::<u8>()
+                .ok()
+                .filter(|cr| (1..=30).contains(cr))
+                .map(ChallengeRating::Whole)
+                .ok_or(format!("Invalid challenge rating: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for ChallengeRating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChallengeRating::Zero => write!(f, "0"),
+            ChallengeRating::Eighth => write!(f, "1/8"),
+            ChallengeRating::Quarter => write!(f, "1/4"),
+            ChallengeRating::Half => write!(f, "1/2"),
+            ChallengeRating::Whole(cr) => write!(f, "{}", cr),
+        }
+    }
+}
+
+impl TryFrom<String> for ChallengeRating {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ChallengeRating> for String {
+    fn from(value: ChallengeRating) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cr(value: &str) -> ChallengeRating {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_fractions_and_whole_ratings() {
+        let cases = [
+            ("0", ChallengeRating::Zero),
+            ("1/8", ChallengeRating::Eighth),
+            ("1/4", ChallengeRating::Quarter),
+            ("1/2", ChallengeRating::Half),
+            ("1", ChallengeRating::Whole(1)),
+            (" 17 ", ChallengeRating::Whole(17)),
+            ("30", ChallengeRating::Whole(30)),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(cr(value), expected, "{:?}", value);
+            assert_eq!(expected.to_string(), value.trim());
+        }
+    }
+
+    #[test]
+    fn refuses_ratings_outside_the_table() {
+        for value in ["", "31", "-1", "0.5", "1/3", "CR 2", "two"] {
+            assert!(value.parse::<ChallengeRating>().is_err(), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn round_trips_through_json_as_a_string() {
+        assert_eq!(
+            serde_json::to_string(&ChallengeRating::Quarter).unwrap(),
+            r#""1/4""#
+        );
+        assert_eq!(
+            serde_json::from_str::<ChallengeRating>(r#""12""#).unwrap(),
+            ChallengeRating::Whole(12)
+        );
+        assert!(serde_json::from_str::<ChallengeRating>(r#""CR 12""#).is_err());
+    }
+
+    #[test]
+    fn orders_fractions_before_whole_ratings() {
+        let mut ratings = vec![
+            cr("2"),
+            cr("1/2"),
+            cr("10"),
+            cr("0"),
+            cr("1/8"),
+            cr("1"),
+            cr("1/4"),
+        ];
+
+        ratings.sort();
+
+        assert_eq!(
+            ratings,
+            [
+                cr("0"),
+                cr("1/8"),
+                cr("1/4"),
+                cr("1/2"),
+                cr("1"),
+                cr("2"),
+                cr("10")
+            ]
+        );
+        assert!(cr("1/2").as_f32() < cr("1").as_f32());
+    }
+
+    #[test]
+    fn xp_follows_the_dmg_table() {
+        let cases = [
+            ("0", 10),
+            ("1/8", 25),
+            ("1/4", 50),
+            ("1/2", 100),
+            ("1", 200),
+            ("5", 1800),
+            ("10", 5900),
+            ("20", 25000),
+            ("30", 155000),
+        ];
+
+        for (value, xp) in cases {
+            assert_eq!(cr(value).xp(), xp, "CR {}", value);
+        }
+    }
+
+    #[test]
+    fn proficiency_bonus_rises_every_four_ratings_from_five() {
+        let cases = [
+            ("0", 2),
+            ("1/2", 2),
+            ("4", 2),
+            ("5", 3),
+            ("8", 3),
+            ("9", 4),
+            ("13", 5),
+            ("17", 6),
+            ("21", 7),
+            ("25", 8),
+            ("29", 9),
+            ("30", 9),
+        ];
+
+        for (value, bonus) in cases {
+            assert_eq!(cr(value).proficiency_bonus(), bonus, "CR {}", value);
+        }
+    }
+}
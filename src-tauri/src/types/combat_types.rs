@@ -0,0 +1,462 @@
+use std::cmp::Reverse;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::types::{
+    dice_types::DiceRoll,
+    encounter_types::{EncounterPlayer, PlayableStatBlock},
+    error_types::AppError,
+};
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[typeshare]
+pub enum CombatantKind {
+    Player,
+    Monster,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[typeshare]
+pub enum CombatantStatus {
+    Active,
+    // Skipped in the turn order until they choose to act
+    Delaying,
+    // Holding an action until their trigger, cleared when their next turn starts
+    Readied,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CombatantRef {
+    Player(String),
+    Monster(i64),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct Combatant {
+    // "player:<name>" or "monster:<PlayableStatBlock id>"
+    pub key: String,
+    pub kind: CombatantKind,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initiative: Option<u16>,
+    // Dexterity score, breaks initiative ties
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dexterity: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub playable_statblock_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statblock_id: Option<i64>,
+    pub status: CombatantStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readied_trigger: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct InitiativeOptions {
+    // Monsters sharing a StatBlock act on one roll
+    pub group_by_statblock: bool,
+    // Players usually roll their own physical dice
+    pub include_players: bool,
+    // Higher Dexterity score wins a tie, otherwise ties keep their given order
+    pub tie_break_by_dexterity: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct InitiativeRoll {
+    pub key: String,
+    pub kind: CombatantKind,
+    pub name: String,
+    pub bonus: i8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dexterity: Option<u8>,
+    // None when the combatant shares a roll with the rest of its group
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roll: Option<DiceRoll>,
+    pub initiative: u16,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct CombatSession {
+    pub encounter_id: i64,
+    pub round: u32,
+    pub turn_index: u32,
+    pub combatants: Vec<Combatant>,
+    pub last_modified: DateTime<Utc>,
+}
+
+impl CombatantRef {
+    pub fn parse(key: &str) -> Result<Self, AppError> {
+        if let Some(name) = key.strip_prefix("player:") {
+            return Ok(CombatantRef::Player(name.to_string()));
+        }
+
+        key.strip_prefix("monster:")
+            .and_then(|id| id.parse::<i64>().ok())
+            .map(CombatantRef::Monster)
+            .ok_or(AppError::validation(
+                "combatant_key",
+                format!("Invalid combatant key: {}", key),
+            ))
+    }
+
+    pub fn key(&self) -> String {
+        match self {
+            CombatantRef::Player(name) => format!("player:{}", name),
+            CombatantRef::Monster(id) => format!("monster:{}", id),
+        }
+    }
+
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            CombatantRef::Player(_) => "EncounterPlayer",
+            CombatantRef::Monster(_) => "PlayableStatBlock",
+        }
+    }
+}
+
+impl Combatant {
+    pub fn from_player(player: &EncounterPlayer) -> Self {
+        Combatant {
+            key: format!("player:{}", player.name),
+            kind: CombatantKind::Player,
+            name: player.name.clone(),
+            initiative: player.initiative,
+            dexterity: None,
+            playable_statblock_id: None,
+            statblock_id: None,
+            status: CombatantStatus::Active,
+            readied_trigger: None,
+        }
+    }
+
+    pub fn from_playable_statblock(
+        playable: &PlayableStatBlock,
+        dexterity: Option<u8>,
+    ) -> Result<Self, AppError> {
+        let id = playable.id.ok_or(AppError::validation(
+            "id",
+            "Combatant creation failed: PlayableStatBlock has not been saved",
+        ))?;
+
+        Ok(Combatant {
+            key: format!("monster:{}", id),
+            kind: CombatantKind::Monster,
+            name: playable
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("Monster {}", id)),
+            initiative: playable.initiative,
+            dexterity,
+            playable_statblock_id: Some(id),
+            statblock_id: Some(playable.statblock_id),
+            status: CombatantStatus::Active,
+            readied_trigger: None,
+        })
+    }
+}
+
+impl CombatSession {
+    pub fn new(
+        encounter_id: i64,
+        mut combatants: Vec<Combatant>,
+        tie_break_by_dexterity: bool,
+    ) -> Result<Self, AppError> {
+        if combatants.is_empty() {
+            return Err(AppError::validation(
+                "combatants",
+                "Combat start failed: Encounter has no combatants",
+            ));
+        }
+
+        // Highest initiative first, combatants without initiative last
+        sort_by_initiative(&mut combatants, tie_break_by_dexterity);
+
+        Ok(CombatSession {
+            encounter_id,
+            round: 1,
+            turn_index: 0,
+            combatants,
+            last_modified: Utc::now(),
+        })
+    }
+
+    pub fn current(&self) -> Option<&Combatant> {
+        self.combatants.get(self.turn_index as usize)
+    }
+
+    fn position_of(&self, key: &str) -> Result<usize, AppError> {
+        self.combatants
+            .iter()
+            .position(|combatant| combatant.key == key)
+            .ok_or(AppError::not_found(format!("Combatant not found: {}", key)))
+    }
+
+    fn has_active_combatant(&self) -> bool {
+        self.combatants
+            .iter()
+            .any(|combatant| combatant.status != CombatantStatus::Delaying)
+    }
+
+    pub fn next_turn(&mut self) -> Result<(), AppError> {
+        if !self.has_active_combatant() {
+            return Err(AppError::validation("turn", "Every combatant is delaying"));
+        }
+
+        loop {
+            self.turn_index += 1;
+            if self.turn_index as usize >= self.combatants.len() {
+                self.turn_index = 0;
+                self.round += 1;
+            }
+
+            let current = &mut self.combatants[self.turn_index as usize];
+            match current.status {
+                CombatantStatus::Delaying => continue,
+                CombatantStatus::Readied => {
+                    current.status = CombatantStatus::Active;
+                    current.readied_trigger = None;
+                }
+                CombatantStatus::Active => {}
+            }
+            break;
+        }
+
+        self.last_modified = Utc::now();
+        Ok(())
+    }
+
+    pub fn previous_turn(&mut self) -> Result<(), AppError> {
+        if !self.has_active_combatant() {
+            return Err(AppError::validation("turn", "Every combatant is delaying"));
+        }
+
+        let (start_round, start_index) = (self.round, self.turn_index);
+
+        loop {
+            if self.turn_index == 0 {
+                if self.round == 1 {
+                    // Nothing earlier to step back to
+                    self.round = start_round;
+                    self.turn_index = start_index;
+                    return Err(AppError::validation(
+                        "turn",
+                        "Already at the first turn of combat",
+                    ));
+                }
+                self.round -= 1;
+                self.turn_index = self.combatants.len() as u32 - 1;
+            } else {
+                self.turn_index -= 1;
+            }
+
+            if self.combatants[self.turn_index as usize].status != CombatantStatus::Delaying {
+                break;
+            }
+        }
+
+        self.last_modified = Utc::now();
+        Ok(())
+    }
+
+    // Only the combatant whose turn it is can delay, and their turn passes to the next in line
+    pub fn delay(&mut self, key: &str) -> Result<(), AppError> {
+        let position = self.position_of(key)?;
+        if position != self.turn_index as usize {
+            return Err(AppError::validation(
+                "combatant_key",
+                format!("{} can only delay on their own turn", key),
+            ));
+        }
+
+        self.combatants[position].status = CombatantStatus::Delaying;
+        self.combatants[position].readied_trigger = None;
+
+        if self.has_active_combatant() {
+            self.next_turn()?;
+        }
+
+        self.last_modified = Utc::now();
+        Ok(())
+    }
+
+    // A delaying combatant steps back in right after the current turn and acts immediately
+    pub fn resume(&mut self, key: &str) -> Result<(), AppError> {
+        let position = self.position_of(key)?;
+        if self.combatants[position].status != CombatantStatus::Delaying {
+            return Err(AppError::validation(
+                "combatant_key",
+                format!("{} is not delaying", key),
+            ));
+        }
+
+        let current_is_active = self
+            .current()
+            .is_some_and(|current| current.status != CombatantStatus::Delaying);
+
+        let mut combatant = self.combatants.remove(position);
+        combatant.status = CombatantStatus::Active;
+
+        if position < self.turn_index as usize {
+            self.turn_index -= 1;
+        }
+
+        let insert_at = if current_is_active {
+            self.turn_index as usize + 1
+        } else {
+            self.turn_index as usize
+        }
+        .min(self.combatants.len());
+        self.combatants.insert(insert_at, combatant);
+        self.turn_index = insert_at as u32;
+
+        self.last_modified = Utc::now();
+        Ok(())
+    }
+
+    pub fn ready(&mut self, key: &str, trigger: String) -> Result<(), AppError> {
+        let position = self.position_of(key)?;
+        if position != self.turn_index as usize {
+            return Err(AppError::validation(
+                "combatant_key",
+                format!("{} can only ready an action on their own turn", key),
+            ));
+        }
+
+        self.combatants[position].status = CombatantStatus::Readied;
+        self.combatants[position].readied_trigger = Some(trigger);
+
+        self.next_turn()
+    }
+
+    // The readied action fires as a reaction, the combatant's place in the order is unchanged
+    pub fn trigger_readied(&mut self, key: &str) -> Result<(), AppError> {
+        let position = self.position_of(key)?;
+        let combatant = &mut self.combatants[position];
+
+        if combatant.status != CombatantStatus::Readied {
+            return Err(AppError::validation(
+                "combatant_key",
+                format!("{} has no readied action", key),
+            ));
+        }
+
+        combatant.status = CombatantStatus::Active;
+        combatant.readied_trigger = None;
+
+        self.last_modified = Utc::now();
+        Ok(())
+    }
+}
+
+// Player Dexterity isn't tracked, so players break ties as an average score
+const UNKNOWN_DEXTERITY: u8 = 10;
+
+// Sort key for highest initiative first, remaining ties keep their given order
+pub fn initiative_order(
+    initiative: Option<u16>,
+    dexterity: Option<u8>,
+    tie_break_by_dexterity: bool,
+) -> Reverse<(Option<u16>, u8)> {
+    let dexterity = if tie_break_by_dexterity {
+        dexterity.unwrap_or(UNKNOWN_DEXTERITY)
+    } else {
+        0
+    };
+
+    Reverse((initiative, dexterity))
+}
+
+fn sort_by_initiative(combatants: &mut [Combatant], tie_break_by_dexterity: bool) {
+    combatants.sort_by_key(|combatant| {
+        initiative_order(
+            combatant.initiative,
+            combatant.dexterity,
+            tie_break_by_dexterity,
+        )
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combatant(key: &str, initiative: Option<u16>, dexterity: Option<u8>) -> Combatant {
+        Combatant {
+            key: key.to_string(),
+            kind: if dexterity.is_some() {
+                CombatantKind::Monster
+            } else {
+                CombatantKind::Player
+            },
+            name: key.to_string(),
+            initiative,
+            dexterity,
+            playable_statblock_id: None,
+            statblock_id: None,
+            status: CombatantStatus::Active,
+            readied_trigger: None,
+        }
+    }
+
+    fn order(combatants: Vec<Combatant>, tie_break_by_dexterity: bool) -> Vec<String> {
+        CombatSession::new(1, combatants, tie_break_by_dexterity)
+            .unwrap()
+            .combatants
+            .into_iter()
+            .map(|combatant| combatant.key)
+            .collect()
+    }
+
+    #[test]
+    fn dexterity_breaks_ties_when_enabled() {
+        let combatants = vec![
+            combatant("goblin", Some(12), Some(14)),
+            combatant("ogre", Some(12), Some(8)),
+            combatant("dragon", Some(12), Some(18)),
+            combatant("wolf", Some(15), Some(15)),
+            combatant("zombie", None, Some(6)),
+        ];
+
+        assert_eq!(
+            order(combatants.clone(), true),
+            ["wolf", "dragon", "goblin", "ogre", "zombie"]
+        );
+        assert_eq!(
+            order(combatants, false),
+            ["wolf", "goblin", "ogre", "dragon", "zombie"]
+        );
+    }
+
+    #[test]
+    fn players_break_ties_as_average_dexterity() {
+        let combatants = vec![
+            combatant("ogre", Some(12), Some(8)),
+            combatant("goblin", Some(12), Some(14)),
+            combatant("guard", Some(12), Some(10)),
+            combatant("Aria", Some(12), None),
+        ];
+
+        assert_eq!(order(combatants, true), ["goblin", "guard", "Aria", "ogre"]);
+    }
+
+    #[test]
+    fn initiative_order_puts_missing_initiative_last() {
+        let mut rolls = [(None, Some(20)), (Some(3), None), (Some(3), Some(12))];
+
+        rolls
+            .sort_by_key(|(initiative, dexterity)| initiative_order(*initiative, *dexterity, true));
+
+        assert_eq!(
+            rolls,
+            [(Some(3), Some(12)), (Some(3), None), (None, Some(20))]
+        );
+    }
+}
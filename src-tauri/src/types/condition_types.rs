@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+use crate::types::error_types::AppError;
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[typeshare]
 pub enum ConditionType {
     Blinded,
@@ -21,6 +23,106 @@ pub enum ConditionType {
     Unconscious,
 }
 
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[typeshare]
+pub enum ConditionExpiry {
+    StartOfTurn,
+    #[default]
+    EndOfTurn,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct ActiveCondition {
+    pub condition: ConditionType,
+    // 1 to 6, only set for Exhaustion
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exhaustion_level: Option<u8>,
+    // None lasts until removed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rounds_remaining: Option<u32>,
+    // Combatant key of whoever applied the condition
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_key: Option<String>,
+    #[serde(default)]
+    pub expires: ConditionExpiry,
+}
+
+impl ActiveCondition {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.rounds_remaining == Some(0) {
+            return Err(AppError::validation(
+                "rounds_remaining",
+                "A condition's duration must be at least one round",
+            ));
+        }
+
+        match (self.condition, self.exhaustion_level) {
+            (ConditionType::Exhaustion, Some(level)) if (1..=6).contains(&level) => Ok(()),
+            (ConditionType::Exhaustion, Some(level)) => Err(AppError::validation(
+                "exhaustion_level",
+                format!("Exhaustion level must be between 1 and 6, got {}", level),
+            )),
+            (ConditionType::Exhaustion, None) => Err(AppError::validation(
+                "exhaustion_level",
+                "Exhaustion needs an exhaustion level",
+            )),
+            (condition, Some(_)) => Err(AppError::validation(
+                "exhaustion_level",
+                format!("{:?} does not take an exhaustion level", condition),
+            )),
+            (_, None) => Ok(()),
+        }
+    }
+
+    // Durations count down on the source's turn, "until the end of your next turn",
+    // falling back to the affected creature's own turn
+    fn counts_down_on<'a>(&'a self, owner_key: &'a str) -> &'a str {
+        self.source_key.as_deref().unwrap_or(owner_key)
+    }
+}
+
+// Adds a condition, refreshing one already present and stacking exhaustion up to level 6
+pub fn add_condition(conditions: &mut Vec<ActiveCondition>, condition: ActiveCondition) {
+    match conditions
+        .iter_mut()
+        .find(|existing| existing.condition == condition.condition)
+    {
+        Some(existing) if condition.condition == ConditionType::Exhaustion => {
+            existing.exhaustion_level = Some(
+                (existing.exhaustion_level.unwrap_or(0) + condition.exhaustion_level.unwrap_or(1))
+                    .min(6),
+            );
+        }
+        Some(existing) => *existing = condition,
+        None => conditions.push(condition),
+    }
+}
+
+// Counts down conditions tied to `turn_key` at the given point of its turn,
+// returning whether anything changed
+pub fn tick_conditions(
+    conditions: &mut Vec<ActiveCondition>,
+    owner_key: &str,
+    turn_key: &str,
+    timing: ConditionExpiry,
+) -> bool {
+    let mut changed = false;
+
+    for condition in conditions.iter_mut() {
+        if condition.expires != timing || condition.counts_down_on(owner_key) != turn_key {
+            continue;
+        }
+        if let Some(rounds) = condition.rounds_remaining.as_mut() {
+            *rounds = rounds.saturating_sub(1);
+            changed = true;
+        }
+    }
+
+    conditions.retain(|condition| condition.rounds_remaining != Some(0));
+    changed
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ConditionImmunityDB {
     pub statblock_id: i64,
@@ -31,3 +133,147 @@ pub struct ConditionImmunityDB {
 pub struct ConditionTypeFromJoin {
     pub condition_type: ConditionType,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn condition(condition: ConditionType, rounds_remaining: Option<u32>) -> ActiveCondition {
+        ActiveCondition {
+            condition,
+            exhaustion_level: None,
+            rounds_remaining,
+            source_key: None,
+            expires: ConditionExpiry::EndOfTurn,
+        }
+    }
+
+    fn exhaustion(level: u8) -> ActiveCondition {
+        ActiveCondition {
+            exhaustion_level: Some(level),
+            ..condition(ConditionType::Exhaustion, None)
+        }
+    }
+
+    fn names(conditions: &[ActiveCondition]) -> Vec<ConditionType> {
+        conditions
+            .iter()
+            .map(|condition| condition.condition)
+            .collect()
+    }
+
+    #[test]
+    fn exhaustion_stacks_up_to_level_six() {
+        let mut conditions = vec![];
+
+        add_condition(&mut conditions, exhaustion(2));
+        add_condition(&mut conditions, exhaustion(1));
+        assert_eq!(conditions.len(), 1);
+        assert_eq!(conditions[0].exhaustion_level, Some(3));
+
+        add_condition(&mut conditions, exhaustion(5));
+        assert_eq!(conditions[0].exhaustion_level, Some(6));
+    }
+
+    #[test]
+    fn adding_a_present_condition_refreshes_it() {
+        let mut conditions = vec![condition(ConditionType::Poisoned, Some(1))];
+
+        add_condition(
+            &mut conditions,
+            condition(ConditionType::Poisoned, Some(10)),
+        );
+        add_condition(&mut conditions, condition(ConditionType::Prone, None));
+
+        assert_eq!(
+            names(&conditions),
+            [ConditionType::Poisoned, ConditionType::Prone]
+        );
+        assert_eq!(conditions[0].rounds_remaining, Some(10));
+    }
+
+    #[test]
+    fn conditions_expire_at_their_point_of_the_turn() {
+        let start_of_turn = ActiveCondition {
+            expires: ConditionExpiry::StartOfTurn,
+            ..condition(ConditionType::Frightened, Some(1))
+        };
+        let mut conditions = vec![
+            condition(ConditionType::Poisoned, Some(1)),
+            condition(ConditionType::Blinded, Some(2)),
+            condition(ConditionType::Prone, None),
+            start_of_turn,
+        ];
+
+        assert!(tick_conditions(
+            &mut conditions,
+            "player:Aria",
+            "player:Aria",
+            ConditionExpiry::EndOfTurn,
+        ));
+        assert_eq!(
+            names(&conditions),
+            [
+                ConditionType::Blinded,
+                ConditionType::Prone,
+                ConditionType::Frightened
+            ]
+        );
+        assert_eq!(conditions[0].rounds_remaining, Some(1));
+
+        assert!(tick_conditions(
+            &mut conditions,
+            "player:Aria",
+            "player:Aria",
+            ConditionExpiry::StartOfTurn,
+        ));
+        assert_eq!(
+            names(&conditions),
+            [ConditionType::Blinded, ConditionType::Prone]
+        );
+    }
+
+    #[test]
+    fn conditions_count_down_on_their_source_turn() {
+        let mut conditions = vec![ActiveCondition {
+            source_key: Some("monster:4".to_string()),
+            ..condition(ConditionType::Frightened, Some(1))
+        }];
+
+        // The frightened creature's own turn leaves it alone
+        assert!(!tick_conditions(
+            &mut conditions,
+            "player:Aria",
+            "player:Aria",
+            ConditionExpiry::EndOfTurn,
+        ));
+        assert_eq!(conditions.len(), 1);
+
+        assert!(tick_conditions(
+            &mut conditions,
+            "player:Aria",
+            "monster:4",
+            ConditionExpiry::EndOfTurn,
+        ));
+        assert!(conditions.is_empty());
+    }
+
+    #[test]
+    fn invalid_conditions_are_refused() {
+        let invalid = [
+            condition(ConditionType::Poisoned, Some(0)),
+            exhaustion(0),
+            exhaustion(7),
+            condition(ConditionType::Exhaustion, None),
+            ActiveCondition {
+                exhaustion_level: Some(1),
+                ..condition(ConditionType::Poisoned, None)
+            },
+        ];
+
+        for condition in invalid {
+            assert!(condition.validate().is_err(), "{:?}", condition);
+        }
+        assert!(exhaustion(6).validate().is_ok());
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use typeshare::typeshare;
+
+use crate::types::{encounter_types::Encounter, error_types::AppError, statblock_types::StatBlock};
+
+// Always differ between two saves, so they are left out of the diff
+const IGNORED_FIELDS: [&str; 2] = ["id", "last_modified"];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct FieldDifference {
+    // Dotted path into the saved object, e.g. "stats.strength" or "actions[1].description"
+    pub field: String,
+    // Null when the field only exists on the other side
+    pub local: Value,
+    pub server: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct StatBlockConflict {
+    pub local: StatBlock,
+    pub server: StatBlock,
+    pub differences: Vec<FieldDifference>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct EncounterConflict {
+    pub local: Encounter,
+    pub server: Encounter,
+    pub differences: Vec<FieldDifference>,
+}
+
+impl StatBlockConflict {
+    pub fn new(local: StatBlock, server: StatBlock) -> Result<Self, AppError> {
+        let differences = diff_fields(&to_value(&local)?, &to_value(&server)?);
+
+        Ok(StatBlockConflict {
+            local,
+            server,
+            differences,
+        })
+    }
+}
+
+impl EncounterConflict {
+    pub fn new(local: Encounter, server: Encounter) -> Result<Self, AppError> {
+        let differences = diff_fields(&to_value(&local)?, &to_value(&server)?);
+
+        Ok(EncounterConflict {
+            local,
+            server,
+            differences,
+        })
+    }
+}
+
+pub fn diff_fields(local: &Value, server: &Value) -> Vec<FieldDifference> {
+    let mut differences = Vec::new();
+    collect_differences("", local, server, &mut differences);
+    differences
+}
+
+fn collect_differences(
+    path: &str,
+    local: &Value,
+    server: &Value,
+    differences: &mut Vec<FieldDifference>,
+) {
+    match (local, server) {
+        (Value::Object(local_fields), Value::Object(server_fields)) => {
+            let keys: BTreeSet<&String> = local_fields.keys().chain(server_fields.keys()).collect();
+
+            for key in keys {
+                if path.is_empty() && IGNORED_FIELDS.contains(&key.as_str()) {
+                    continue;
+                }
+
+                let field = if path.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                collect_differences(
+                    &field,
+                    local_fields.get(key).unwrap_or(&Value::Null),
+                    server_fields.get(key).unwrap_or(&Value::Null),
+                    differences,
+                );
+            }
+        }
+        (Value::Array(local_items), Value::Array(server_items)) => {
+            for index in 0..local_items.len().max(server_items.len()) {
+                collect_differences(
+                    &format!("{}[{}]", path, index),
+                    local_items.get(index).unwrap_or(&Value::Null),
+                    server_items.get(index).unwrap_or(&Value::Null),
+                    differences,
+                );
+            }
+        }
+        _ if local != server => differences.push(FieldDifference {
+            field: path.to_string(),
+            local: local.clone(),
+            server: server.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, AppError> {
+    serde_json::to_value(value)
+        .map_err(|e| AppError::parse(format!("Failed to compare versions: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn fields(differences: &[FieldDifference]) -> Vec<&str> {
+        differences
+            .iter()
+            .map(|difference| difference.field.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn equal_values_have_no_differences() {
+        let value = json!({ "name": "Goblin", "stats": { "strength": 8 }, "actions": [] });
+
+        assert!(diff_fields(&value, &value).is_empty());
+    }
+
+    #[test]
+    fn ignores_id_and_last_modified_at_the_top_level_only() {
+        let local = json!({ "id": 1, "last_modified": "a", "spells": { "id": 3 } });
+        let server = json!({ "id": 2, "last_modified": "b", "spells": { "id": 4 } });
+
+        assert_eq!(fields(&diff_fields(&local, &server)), ["spells.id"]);
+    }
+
+    #[test]
+    fn paths_point_into_nested_objects_and_arrays() {
+        let local = json!({
+            "stats": { "strength": 8, "dexterity": 14 },
+            "actions": [
+                { "name": "Scimitar", "description": "Slash" },
+                { "name": "Shortbow", "description": "Shoot" },
+            ],
+        });
+        let server = json!({
+            "stats": { "strength": 10, "dexterity": 14 },
+            "actions": [
+                { "name": "Scimitar", "description": "Slash" },
+                { "name": "Shortbow", "description": "Loose an arrow" },
+                { "name": "Nimble Escape", "description": "Disengage" },
+            ],
+        });
+
+        let differences = diff_fields(&local, &server);
+
+        assert_eq!(
+            fields(&differences),
+            ["actions[1].description", "actions[2]", "stats.strength"]
+        );
+        assert_eq!(differences[0].local, json!("Shoot"));
+        assert_eq!(differences[0].server, json!("Loose an arrow"));
+        assert_eq!(differences[1].local, Value::Null);
+        assert_eq!(differences[2].server, json!(10));
+    }
+
+    #[test]
+    fn fields_missing_on_one_side_compare_as_null() {
+        let local = json!({ "senses": "darkvision 60 ft." });
+        let server = json!({});
+
+        let differences = diff_fields(&local, &server);
+
+        assert_eq!(fields(&differences), ["senses"]);
+        assert_eq!(differences[0].server, Value::Null);
+    }
+
+    #[test]
+    fn encounter_conflict_lists_changed_fields() {
+        let encounter = |name: &str, last_modified: &str| -> Encounter {
+            serde_json::from_value(json!({
+                "id": 7,
+                "name": name,
+                "user_id": "user",
+                "last_modified": last_modified,
+                "hp_mode": "Average",
+            }))
+            .unwrap()
+        };
+
+        let conflict = EncounterConflict::new(
+            encounter("Goblin Ambush", "2026-10-18T12:00:00Z"),
+            encounter("Goblin Camp", "2026-10-18T12:05:00Z"),
+        )
+        .unwrap();
+
+        assert_eq!(fields(&conflict.differences), ["name"]);
+        assert_eq!(conflict.server.name, "Goblin Camp");
+    }
+}
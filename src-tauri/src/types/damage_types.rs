@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[typeshare]
 pub enum DamageType {
     Acid,
@@ -29,3 +29,34 @@ pub struct DamageTypeDB {
 pub struct DamageTypeFromJoin {
     pub damage_type: DamageType,
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct DamageResult {
+    pub key: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub damage_type: Option<DamageType>,
+    pub raw_amount: u16,
+    pub immune: bool,
+    pub resisted: bool,
+    pub vulnerable: bool,
+    // Damage after immunity, resistance and vulnerability
+    pub adjusted_amount: u16,
+    pub temporary_hp_absorbed: u16,
+    pub hp_lost: u16,
+    pub current_hp: u16,
+    pub temporary_hp: u16,
+    pub max_hp: u16,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct HealingResult {
+    pub key: String,
+    pub name: String,
+    pub amount: u16,
+    pub hp_restored: u16,
+    pub current_hp: u16,
+    pub max_hp: u16,
+}
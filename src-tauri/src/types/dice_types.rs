@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[typeshare]
+pub struct DieRoll {
+    pub sides: u32,
+    pub value: u32,
+    pub kept: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[typeshare]
+pub struct TermRoll {
+    pub term: String,
+    pub dice: Vec<DieRoll>,
+    pub subtotal: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[typeshare]
+pub struct DiceRoll {
+    pub expression: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u32>,
+    pub terms: Vec<TermRoll>,
+    pub total: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct DiceEvaluation {
+    pub expression: String,
+    pub average: f64,
+    pub min: i32,
+    pub max: i32,
+    pub roll: DiceRoll,
+}
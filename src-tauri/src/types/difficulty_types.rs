@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::types::challenge_rating_types::ChallengeRating;
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[typeshare]
+pub enum DifficultyModel {
+    // 2014 DMG: per-character thresholds, XP scaled by a monster count multiplier
+    Dmg2014,
+    // 2024 DMG: per-character XP budgets, no multiplier
+    Dmg2024,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[typeshare]
+pub enum DifficultyRating {
+    Trivial,
+    Easy,
+    Medium,
+    Hard,
+    Deadly,
+    Low,
+    Moderate,
+    High,
+    // Over the 2024 High budget
+    Extreme,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct DifficultyThreshold {
+    pub rating: DifficultyRating,
+    pub xp: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct MonsterXp {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub playable_statblock_id: Option<i64>,
+    pub statblock_id: i64,
+    pub name: String,
+    pub cr: ChallengeRating,
+    pub xp: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct EncounterDifficulty {
+    pub model: DifficultyModel,
+    pub monsters: Vec<MonsterXp>,
+    pub total_xp: u32,
+    pub multiplier: f32,
+    pub adjusted_xp: u32,
+    pub thresholds: Vec<DifficultyThreshold>,
+    pub rating: DifficultyRating,
+}
@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
+use crate::types::condition_types::ActiveCondition;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[typeshare]
 pub struct EncounterPlayer {
@@ -12,20 +14,35 @@ pub struct EncounterPlayer {
     pub temporary_hp: u16,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub initiative: Option<u16>,
+    #[serde(default)]
+    pub conditions: Vec<ActiveCondition>,
     pub encounter_id: i64,
 }
 
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[typeshare]
+pub enum HpMode {
+    #[default]
+    Average,
+    Rolled,
+    Max,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[typeshare]
 pub struct PlayableStatBlock {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_hp: Option<u16>,
     pub current_hp: u16,
     pub temporary_hp: u16,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub initiative: Option<u16>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    #[serde(default)]
+    pub conditions: Vec<ActiveCondition>,
     pub statblock_id: i64,
     pub encounter_id: i64,
 }
@@ -37,5 +54,48 @@ pub struct Encounter {
     pub id: Option<i64>,
     pub name: String,
     pub user_id: String,
+    // New Encounters are sent without one, the server stamps it on every save
+    #[serde(default)]
     pub last_modified: DateTime<Utc>,
+    // Left out of saves when unset so the stored mode is kept
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hp_mode: Option<HpMode>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unset_hp_mode_is_left_out_of_saves() {
+        let mut encounter: Encounter = serde_json::from_value(serde_json::json!({
+            "name": "Goblin Ambush",
+            "user_id": "user",
+            "last_modified": "2026-10-18T12:00:00Z",
+        }))
+        .unwrap();
+
+        assert_eq!(encounter.hp_mode, None);
+        assert!(serde_json::to_value(&encounter)
+            .unwrap()
+            .get("hp_mode")
+            .is_none());
+
+        encounter.hp_mode = Some(HpMode::Rolled);
+        assert_eq!(
+            serde_json::to_value(&encounter).unwrap()["hp_mode"],
+            "Rolled"
+        );
+    }
+
+    #[test]
+    fn new_encounters_can_leave_last_modified_to_the_server() {
+        let encounter: Encounter = serde_json::from_value(serde_json::json!({
+            "name": "Goblin Ambush",
+            "user_id": "user",
+        }))
+        .unwrap();
+
+        assert_eq!(encounter.last_modified, DateTime::<Utc>::default());
+    }
 }
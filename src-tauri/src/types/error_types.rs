@@ -0,0 +1,117 @@
+use std::fmt;
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+// Returned by every command, the frontend matches on code instead of the message text
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[typeshare]
+#[serde(tag = "code", content = "details")]
+pub enum AppError {
+    // Supabase or another service couldn't be reached
+    Network { message: String },
+    // The access token is missing, expired or lacks permission, the user has to log in again
+    Unauthorized { message: String },
+    NotFound { message: String },
+    // The row changed on the server since the client last saw it
+    Conflict { message: String },
+    // Input that can't be saved or used as given
+    Validation { field: String, reason: String },
+    RateLimited { message: String },
+    // A response, file or stored value couldn't be read
+    Parse { message: String },
+    // Supabase, the local store or the app data folder failed to read or write
+    Storage { message: String },
+}
+
+impl AppError {
+    pub fn network(message: impl Into<String>) -> Self {
+        AppError::Network {
+            message: message.into(),
+        }
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        AppError::Unauthorized {
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::NotFound {
+            message: message.into(),
+        }
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        AppError::Conflict {
+            message: message.into(),
+        }
+    }
+
+    pub fn validation(field: impl Into<String>, reason: impl Into<String>) -> Self {
+        AppError::Validation {
+            field: field.into(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn parse(message: impl Into<String>) -> Self {
+        AppError::Parse {
+            message: message.into(),
+        }
+    }
+
+    pub fn storage(message: impl Into<String>) -> Self {
+        AppError::Storage {
+            message: message.into(),
+        }
+    }
+
+    // Classifies a failed Supabase response by its status code
+    pub fn from_status(status: StatusCode, message: impl Into<String>) -> Self {
+        let message = message.into();
+
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => AppError::Unauthorized { message },
+            StatusCode::NOT_FOUND => AppError::NotFound { message },
+            StatusCode::CONFLICT => AppError::Conflict { message },
+            StatusCode::TOO_MANY_REQUESTS => AppError::RateLimited { message },
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => AppError::Validation {
+                field: "request".to_string(),
+                reason: message,
+            },
+            _ => AppError::Storage { message },
+        }
+    }
+
+    // Reads the body of a failed response into an error, prefixed with what was attempted
+    pub async fn from_response(context: &str, response: reqwest::Response) -> Self {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+
+        AppError::from_status(status, format!("{} ({}): {}", context, status, text))
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            AppError::Network { message }
+            | AppError::Unauthorized { message }
+            | AppError::NotFound { message }
+            | AppError::Conflict { message }
+            | AppError::RateLimited { message }
+            | AppError::Parse { message }
+            | AppError::Storage { message } => message.clone(),
+            AppError::Validation { field, reason } => format!("{}: {}", field, reason),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::types::{encounter_types::PlayableStatBlock, statblock_types::StatBlock};
+
+// A StatBlock of an Encounter with every PlayableStatBlock made from it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct EncounterCreature {
+    pub stat_block: StatBlock,
+    pub instances: Vec<PlayableStatBlock>,
+}
+
+// What an Encounter export to Foundry VTT wrote, also saved next to the actors as encounter.json
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct FoundryEncounterExport {
+    pub folder: String,
+    pub actors: Vec<FoundryActorExport>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct FoundryActorExport {
+    pub name: String,
+    // Actor JSON file, relative to the folder
+    pub file: String,
+    pub statblock_id: i64,
+    // How many tokens of this actor the Encounter places
+    pub tokens: u32,
+    pub token_names: Vec<String>,
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::types::{error_types::AppError, statblock_types::StatBlock};
+
+// A creature converted from another tool, fields that couldn't be mapped are listed instead of guessed
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct ImportedStatBlock {
+    pub stat_block: StatBlock,
+    pub warnings: Vec<ImportWarning>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[typeshare]
+pub struct ImportWarning {
+    // The field of the source format, e.g. "alignment" or "resist"
+    pub field: String,
+    pub message: String,
+}
+
+// A creature that couldn't be converted at all, the rest of the file still imports
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct ImportFailure {
+    // The creature's name, or its position in the file when it has none
+    pub name: String,
+    pub error: AppError,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[typeshare]
+pub struct ImportReport {
+    pub imported: Vec<ImportedStatBlock>,
+    pub failed: Vec<ImportFailure>,
+}
+
+// The outcome of importing a folder of files, each creature counts once
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[typeshare]
+pub struct BatchImportReport {
+    pub created: u32,
+    // Creatures with the name of a StatBlock that already exists
+    pub skipped: u32,
+    // Files that couldn't be read and creatures that couldn't be converted or saved
+    pub failed: u32,
+    pub skipped_names: Vec<String>,
+    pub failures: Vec<ImportFailure>,
+    // Only created creatures with at least one warning are listed
+    pub warnings: Vec<CreatureWarnings>,
+}
+
+// The fields of one imported creature that couldn't be mapped
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct CreatureWarnings {
+    pub name: String,
+    pub warnings: Vec<ImportWarning>,
+}
+
+impl ImportWarning {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        ImportWarning {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
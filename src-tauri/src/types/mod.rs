@@ -1,9 +1,18 @@
 pub mod action_types;
 pub mod auth_types;
+pub mod challenge_rating_types;
+pub mod combat_types;
 pub mod condition_types;
+pub mod conflict_types;
 pub mod damage_types;
+pub mod dice_types;
+pub mod difficulty_types;
 pub mod encounter_types;
+pub mod error_types;
+pub mod export_types;
+pub mod import_types;
 pub mod proficiency_types;
 pub mod spell_types;
 pub mod statblock_types;
+pub mod sync_types;
 pub mod trait_types;
@@ -3,7 +3,7 @@ use typeshare::typeshare;
 
 use crate::types::statblock_types::{Ability, Score};
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
 #[typeshare]
 pub enum ProficiencyLevel {
     #[serde(rename = "none")]
@@ -14,6 +14,17 @@ pub enum ProficiencyLevel {
     Expertise,
 }
 
+impl ProficiencyLevel {
+    // How many times the proficiency bonus applies
+    pub fn multiplier(&self) -> i8 {
+        match self {
+            ProficiencyLevel::None => 0,
+            ProficiencyLevel::Proficient => 1,
+            ProficiencyLevel::Expertise => 2,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[typeshare]
 pub struct SkillProficiency {
@@ -5,8 +5,10 @@ use typeshare::typeshare;
 
 use crate::types::{
     action_types::{Action, ActionDB},
+    challenge_rating_types::ChallengeRating,
     condition_types::{ConditionImmunityDB, ConditionType, ConditionTypeFromJoin},
     damage_types::{DamageType, DamageTypeDB, DamageTypeFromJoin},
+    error_types::AppError,
     proficiency_types::{
         ProficiencyLevel, SaveProficiency, SaveProficiencyDB, SkillProficiency, SkillProficiencyDB,
     },
@@ -41,7 +43,7 @@ pub enum Size {
     Gargantuan,
 }
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[typeshare]
 pub enum Score {
     Strength,
@@ -52,7 +54,7 @@ pub enum Score {
     Charisma,
 }
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[typeshare]
 pub enum Ability {
     Acrobatics,
@@ -75,18 +77,18 @@ pub enum Ability {
     Survival,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[typeshare]
 pub struct Stats {
-    strength: u8,
-    dexterity: u8,
-    constitution: u8,
-    intelligence: u8,
-    wisdom: u8,
-    charisma: u8,
+    pub strength: u8,
+    pub dexterity: u8,
+    pub constitution: u8,
+    pub intelligence: u8,
+    pub wisdom: u8,
+    pub charisma: u8,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[typeshare]
 pub struct StatBlock {
     pub id: Option<i64>,
@@ -112,7 +114,7 @@ pub struct StatBlock {
     pub damage_resistances: Vec<DamageType>,
     pub damage_immunities: Vec<DamageType>,
     pub condition_immunities: Vec<ConditionType>,
-    pub cr: String,
+    pub cr: ChallengeRating,
     pub traits: Vec<Trait>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub spells: Option<Spells>,
@@ -126,6 +128,42 @@ pub struct StatBlock {
     pub user_id: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct ScoreModifier {
+    pub score: Score,
+    pub value: u8,
+    pub modifier: i8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct SaveBonus {
+    pub score: Score,
+    pub level: ProficiencyLevel,
+    pub bonus: i8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct SkillBonus {
+    pub ability: Ability,
+    pub score: Score,
+    pub level: ProficiencyLevel,
+    pub bonus: i8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct DerivedStats {
+    pub modifiers: Vec<ScoreModifier>,
+    pub proficiency_bonus: u8,
+    pub saves: Vec<SaveBonus>,
+    pub skills: Vec<SkillBonus>,
+    pub passive_perception: i8,
+    pub initiative_bonus: i8,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct StatBlockToDB {
     name: String,
@@ -146,7 +184,7 @@ pub struct StatBlockToDB {
     intelligence: u8,
     wisdom: u8,
     charisma: u8,
-    cr: String,
+    cr: ChallengeRating,
     last_modified: String,
     legendary_description: Option<String>,
     user_id: String,
@@ -188,7 +226,7 @@ pub struct StatBlockFromDB {
     damage_immunities: Option<Vec<DamageTypeFromJoin>>,
     #[serde(rename = "ConditionImmunity")]
     condition_immunities: Option<Vec<ConditionTypeFromJoin>>,
-    cr: String,
+    cr: ChallengeRating,
     last_modified: String,
     #[serde(rename = "Trait")]
     traits: Option<Vec<Trait>>,
@@ -209,7 +247,170 @@ pub struct StatBlockFromDB {
     spells: Option<Vec<SpellsFromJoin>>,
 }
 
+impl Score {
+    pub const ALL: [Score; 6] = [
+        Score::Strength,
+        Score::Dexterity,
+        Score::Constitution,
+        Score::Intelligence,
+        Score::Wisdom,
+        Score::Charisma,
+    ];
+}
+
+impl Ability {
+    pub const ALL: [Ability; 18] = [
+        Ability::Acrobatics,
+        Ability::AnimalHandling,
+        Ability::Arcana,
+        Ability::Athletics,
+        Ability::Deception,
+        Ability::History,
+        Ability::Insight,
+        Ability::Intimidation,
+        Ability::Investigation,
+        Ability::Medicine,
+        Ability::Nature,
+        Ability::Perception,
+        Ability::Performance,
+        Ability::Persuasion,
+        Ability::Religion,
+        Ability::SleightOfHand,
+        Ability::Stealth,
+        Ability::Survival,
+    ];
+
+    pub fn score(&self) -> Score {
+        match self {
+            Ability::Athletics => Score::Strength,
+            Ability::Acrobatics | Ability::SleightOfHand | Ability::Stealth => Score::Dexterity,
+            Ability::Arcana
+            | Ability::History
+            | Ability::Investigation
+            | Ability::Nature
+            | Ability::Religion => Score::Intelligence,
+            Ability::AnimalHandling
+            | Ability::Insight
+            | Ability::Medicine
+            | Ability::Perception
+            | Ability::Survival => Score::Wisdom,
+            Ability::Deception
+            | Ability::Intimidation
+            | Ability::Performance
+            | Ability::Persuasion => Score::Charisma,
+        }
+    }
+}
+
+impl Stats {
+    pub fn score(&self, score: Score) -> u8 {
+        match score {
+            Score::Strength => self.strength,
+            Score::Dexterity => self.dexterity,
+            Score::Constitution => self.constitution,
+            Score::Intelligence => self.intelligence,
+            Score::Wisdom => self.wisdom,
+            Score::Charisma => self.charisma,
+        }
+    }
+
+    // Worked out in i16, scores above 127 would wrap as an i8
+    pub fn modifier(&self, score: Score) -> i8 {
+        (self.score(score) as i16 - 10).div_euclid(2) as i8
+    }
+}
+
 impl StatBlock {
+    pub fn proficiency_bonus(&self) -> u8 {
+        self.cr.proficiency_bonus()
+    }
+
+    pub fn save_level(&self, score: Score) -> ProficiencyLevel {
+        self.saves
+            .iter()
+            .find(|save| save.score == score)
+            .map(|save| save.level)
+            .unwrap_or(ProficiencyLevel::None)
+    }
+
+    pub fn skill_level(&self, ability: Ability) -> ProficiencyLevel {
+        self.skill_saves
+            .iter()
+            .find(|skill| skill.ability == ability)
+            .map(|skill| skill.level)
+            .unwrap_or(ProficiencyLevel::None)
+    }
+
+    pub fn save_bonus(&self, score: Score) -> i8 {
+        self.stats
+            .modifier(score)
+            .saturating_add(self.save_level(score).multiplier() * self.proficiency_bonus() as i8)
+    }
+
+    pub fn skill_bonus(&self, ability: Ability) -> i8 {
+        self.stats
+            .modifier(ability.score())
+            .saturating_add(self.skill_level(ability).multiplier() * self.proficiency_bonus() as i8)
+    }
+
+    pub fn passive_perception(&self) -> i8 {
+        self.skill_bonus(Ability::Perception).saturating_add(10)
+    }
+
+    pub fn initiative_bonus(&self) -> i8 {
+        self.stats
+            .modifier(Score::Dexterity)
+            .saturating_add(self.initiative.multiplier() * self.proficiency_bonus() as i8)
+    }
+
+    pub fn validate_attacks(&self) -> Result<(), AppError> {
+        for action in self
+            .actions
+            .iter()
+            .chain(&self.bonus_actions)
+            .chain(&self.reactions)
+            .chain(&self.legendary_actions)
+        {
+            if let Some(attack) = &action.attack {
+                attack.validate(&action.name)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn derived_stats(&self) -> DerivedStats {
+        DerivedStats {
+            modifiers: Score::ALL
+                .iter()
+                .map(|&score| ScoreModifier {
+                    score,
+                    value: self.stats.score(score),
+                    modifier: self.stats.modifier(score),
+                })
+                .collect(),
+            proficiency_bonus: self.proficiency_bonus(),
+            saves: Score::ALL
+                .iter()
+                .map(|&score| SaveBonus {
+                    score,
+                    level: self.save_level(score),
+                    bonus: self.save_bonus(score),
+                })
+                .collect(),
+            skills: Ability::ALL
+                .iter()
+                .map(|&ability| SkillBonus {
+                    ability,
+                    score: ability.score(),
+                    level: self.skill_level(ability),
+                    bonus: self.skill_bonus(ability),
+                })
+                .collect(),
+            passive_perception: self.passive_perception(),
+            initiative_bonus: self.initiative_bonus(),
+        }
+    }
+
     pub fn statblock_to_db(&self) -> StatBlockToDB {
         StatBlockToDB {
             name: self.name.clone(),
@@ -230,7 +431,7 @@ impl StatBlock {
             intelligence: self.stats.intelligence,
             wisdom: self.stats.wisdom,
             charisma: self.stats.charisma,
-            cr: self.cr.clone(),
+            cr: self.cr,
             last_modified: self.last_modified.clone(),
             legendary_description: self.legendary_description.clone(),
             user_id: self.user_id.clone(),
@@ -248,7 +449,7 @@ impl StatBlock {
         StatBlock {
             id: Some(db.id),
             name: db.name.clone(),
-            size: db.size.clone(),
+            size: db.size,
             type_: db.creature_type.clone(),
             subtype: db.subtype.clone(),
             alignment: db.alignment,
@@ -313,7 +514,7 @@ impl StatBlock {
             } else {
                 Vec::new()
             },
-            cr: db.cr.clone(),
+            cr: db.cr,
             traits: if let Some(traits) = &db.traits {
                 traits.to_vec()
             } else {
@@ -328,16 +529,8 @@ impl StatBlock {
 
                 Some(Spells {
                     ability,
-                    save_dc: if let Some(save_dc) = db.save_dc {
-                        save_dc
-                    } else {
-                        0
-                    },
-                    attack_bonus: if let Some(attack_bonus) = db.spell_attack_bonus {
-                        attack_bonus
-                    } else {
-                        0
-                    },
+                    save_dc: db.save_dc.unwrap_or_default(),
+                    attack_bonus: db.spell_attack_bonus.unwrap_or_default(),
                     spells: if let Some(spells) = &db.spells {
                         let mut map = HashMap::new();
                         for spell in spells {
@@ -377,7 +570,7 @@ impl StatBlock {
         }
     }
 
-    pub fn spells_to_db(&self) -> Result<Vec<SpellsDB>, String> {
+    pub fn spells_to_db(&self) -> Result<Vec<SpellsDB>, AppError> {
         if let Some(statblock_id) = self.id {
             if let Some(statblock_spells) = &self.spells {
                 return Ok(statblock_spells
@@ -391,54 +584,54 @@ impl StatBlock {
                     .collect());
             }
         }
-        Err("No StatBlock ID".to_string())
+        Err(AppError::validation("id", "No StatBlock ID"))
     }
 
-    pub fn condition_immunities_to_db(&self) -> Result<Vec<ConditionImmunityDB>, String> {
+    pub fn condition_immunities_to_db(&self) -> Result<Vec<ConditionImmunityDB>, AppError> {
         if let Some(statblock_id) = self.id {
             return Ok(self
                 .condition_immunities
                 .iter()
                 .map(|condition_type| ConditionImmunityDB {
                     statblock_id,
-                    condition_type: condition_type.clone(),
+                    condition_type: *condition_type,
                 })
                 .collect());
         }
-        Err("No StatBlock ID".to_string())
+        Err(AppError::validation("id", "No StatBlock ID"))
     }
 
-    pub fn save_proficiencies_to_db(&self) -> Result<Vec<SaveProficiencyDB>, String> {
+    pub fn save_proficiencies_to_db(&self) -> Result<Vec<SaveProficiencyDB>, AppError> {
         if let Some(statblock_id) = self.id {
             return Ok(self
                 .saves
                 .iter()
                 .map(|save| SaveProficiencyDB {
                     statblock_id,
-                    score: save.score.clone(),
-                    level: save.level.clone(),
+                    score: save.score,
+                    level: save.level,
                 })
                 .collect());
         }
-        Err("No StatBlock ID".to_string())
+        Err(AppError::validation("id", "No StatBlock ID"))
     }
 
-    pub fn skill_proficiencies_to_db(&self) -> Result<Vec<SkillProficiencyDB>, String> {
+    pub fn skill_proficiencies_to_db(&self) -> Result<Vec<SkillProficiencyDB>, AppError> {
         if let Some(statblock_id) = self.id {
             return Ok(self
                 .skill_saves
                 .iter()
                 .map(|skill| SkillProficiencyDB {
                     statblock_id,
-                    ability: skill.ability.clone(),
-                    level: skill.level.clone(),
+                    ability: skill.ability,
+                    level: skill.level,
                 })
                 .collect());
         }
-        Err("No StatBlock ID".to_string())
+        Err(AppError::validation("id", "No StatBlock ID"))
     }
 
-    pub fn damage_types_to_db(&self) -> Result<HashMap<String, Vec<DamageTypeDB>>, String> {
+    pub fn damage_types_to_db(&self) -> Result<HashMap<String, Vec<DamageTypeDB>>, AppError> {
         if let Some(statblock_id) = self.id {
             let mut map: HashMap<String, Vec<DamageTypeDB>> = HashMap::new();
 
@@ -448,7 +641,7 @@ impl StatBlock {
                     .iter()
                     .map(|resistance| DamageTypeDB {
                         statblock_id,
-                        damage_type: resistance.clone(),
+                        damage_type: *resistance,
                     })
                     .collect(),
             );
@@ -459,7 +652,7 @@ impl StatBlock {
                     .iter()
                     .map(|immunity| DamageTypeDB {
                         statblock_id,
-                        damage_type: immunity.clone(),
+                        damage_type: *immunity,
                     })
                     .collect(),
             );
@@ -470,17 +663,17 @@ impl StatBlock {
                     .iter()
                     .map(|vulnerability| DamageTypeDB {
                         statblock_id,
-                        damage_type: vulnerability.clone(),
+                        damage_type: *vulnerability,
                     })
                     .collect(),
             );
 
             return Ok(map);
         }
-        Err("No StatBlock ID".to_string())
+        Err(AppError::validation("id", "No StatBlock ID"))
     }
 
-    pub fn traits_to_db(&self) -> Result<Vec<TraitDB>, String> {
+    pub fn traits_to_db(&self) -> Result<Vec<TraitDB>, AppError> {
         if let Some(statblock_id) = self.id {
             return Ok(self
                 .traits
@@ -492,63 +685,129 @@ impl StatBlock {
                 })
                 .collect());
         }
-        Err("No StatBlock ID".to_string())
+        Err(AppError::validation("id", "No StatBlock ID"))
     }
 
-    pub fn actions_to_db(&self) -> Result<HashMap<String, Vec<ActionDB>>, String> {
+    pub fn actions_to_db(&self) -> Result<HashMap<String, Vec<ActionDB>>, AppError> {
         if let Some(statblock_id) = self.id {
             let mut map: HashMap<String, Vec<ActionDB>> = HashMap::new();
 
-            map.insert(
-                "Action".to_string(),
-                self.actions
-                    .iter()
-                    .map(|action| ActionDB {
-                        statblock_id,
-                        name: action.name.clone(),
-                        description: action.description.clone(),
-                    })
-                    .collect(),
-            );
+            let tables = [
+                ("Action", &self.actions),
+                ("BonusAction", &self.bonus_actions),
+                ("Reaction", &self.reactions),
+                ("LegendaryAction", &self.legendary_actions),
+            ];
 
-            map.insert(
-                "BonusAction".to_string(),
-                self.bonus_actions
-                    .iter()
-                    .map(|bonus_action| ActionDB {
-                        statblock_id,
-                        name: bonus_action.name.clone(),
-                        description: bonus_action.description.clone(),
-                    })
-                    .collect(),
-            );
+            for (table_name, actions) in tables {
+                map.insert(
+                    table_name.to_string(),
+                    actions
+                        .iter()
+                        .map(|action| ActionDB {
+                            statblock_id,
+                            name: action.name.clone(),
+                            description: action.description.clone(),
+                            attack: action.attack.clone(),
+                        })
+                        .collect(),
+                );
+            }
 
-            map.insert(
-                "Reaction".to_string(),
-                self.reactions
-                    .iter()
-                    .map(|reaction| ActionDB {
-                        statblock_id,
-                        name: reaction.name.clone(),
-                        description: reaction.description.clone(),
-                    })
-                    .collect(),
-            );
+            return Ok(map);
+        }
+        Err(AppError::validation("id", "No StatBlock ID"))
+    }
 
-            map.insert(
-                "LegendaryAction".to_string(),
-                self.legendary_actions
-                    .iter()
-                    .map(|legendary_actions| ActionDB {
-                        statblock_id,
-                        name: legendary_actions.name.clone(),
-                        description: legendary_actions.description.clone(),
-                    })
-                    .collect(),
-            );
+    // Rows of every child table keyed by table name, empty tables included so they get cleared
+    pub fn children_to_db(&self) -> Result<serde_json::Map<String, serde_json::Value>, AppError> {
+        fn rows<T: Serialize>(rows: &[T]) -> Result<serde_json::Value, AppError> {
+            serde_json::to_value(rows).map_err(|e| AppError::parse(e.to_string()))
+        }
 
-            return Ok(map);
+        let mut children = serde_json::Map::new();
+
+        for (table_name, actions) in self.actions_to_db()? {
+            children.insert(table_name, rows(&actions)?);
+        }
+        for (table_name, damage_types) in self.damage_types_to_db()? {
+            children.insert(table_name, rows(&damage_types)?);
+        }
+        children.insert("Trait".to_string(), rows(&self.traits_to_db()?)?);
+        children.insert(
+            "ConditionImmunity".to_string(),
+            rows(&self.condition_immunities_to_db()?)?,
+        );
+        children.insert(
+            "SaveProficiency".to_string(),
+            rows(&self.save_proficiencies_to_db()?)?,
+        );
+        children.insert(
+            "SkillProficiency".to_string(),
+            rows(&self.skill_proficiencies_to_db()?)?,
+        );
+        let spells = if self.spells.is_some() {
+            self.spells_to_db()?
+        } else {
+            Vec::new()
+        };
+        children.insert("Spells".to_string(), rows(&spells)?);
+
+        Ok(children)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::import_utils::empty_statblock;
+
+    fn stats_with_strength(strength: u8) -> Stats {
+        let mut stats = empty_statblock("Ogre").stats;
+        stats.strength = strength;
+        stats
+    }
+
+    #[test]
+    fn modifier_rounds_down_from_the_score() {
+        let cases = [
+            (1, -5),
+            (8, -1),
+            (9, -1),
+            (10, 0),
+            (11, 0),
+            (12, 1),
+            (30, 10),
+        ];
+
+        for (score, modifier) in cases {
+            assert_eq!(
+                stats_with_strength(score).modifier(Score::Strength),
+                modifier,
+                "score {}",
+                score
+            );
         }
-        Err("No StatBlock ID".to_string())
+    }
+
+    #[test]
+    fn modifier_of_a_score_above_127_does_not_wrap() {
+        assert_eq!(stats_with_strength(128).modifier(Score::Strength), 59);
+        assert_eq!(stats_with_strength(200).modifier(Score::Strength), 95);
+        assert_eq!(stats_with_strength(255).modifier(Score::Strength), 122);
+    }
+
+    #[test]
+    fn bonuses_saturate_instead_of_overflowing() {
+        let mut stat_block = empty_statblock("Titan");
+        stat_block.stats = stats_with_strength(255);
+        stat_block.cr = "30".parse().unwrap();
+        stat_block.saves = vec![SaveProficiency {
+            score: Score::Strength,
+            level: ProficiencyLevel::Expertise,
+        }];
+
+        assert_eq!(stat_block.save_bonus(Score::Strength), i8::MAX);
+        assert_eq!(stat_block.skill_bonus(Ability::Athletics), 122);
     }
 }
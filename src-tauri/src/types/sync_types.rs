@@ -0,0 +1,126 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+// Ids refer to rows in the local store, the current local copy is what gets replayed
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[typeshare]
+#[serde(tag = "type", content = "content")]
+pub enum SyncOperation {
+    SaveStatBlock {
+        statblock_id: i64,
+        // The server's last_modified when the offline edit started, None for new rows
+        base_last_modified: Option<String>,
+    },
+    DeleteStatBlock {
+        statblock_id: i64,
+    },
+    SaveEncounter {
+        encounter_id: i64,
+        base_last_modified: Option<String>,
+    },
+    DeleteEncounter {
+        encounter_id: i64,
+    },
+    SaveEncounterPlayers {
+        encounter_id: i64,
+    },
+    SavePlayableStatBlocks {
+        encounter_id: i64,
+    },
+    UpdateCombatant {
+        encounter_id: i64,
+        combatant_key: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct PendingSyncOperation {
+    pub id: i64,
+    pub operation: SyncOperation,
+    pub created_at: DateTime<Utc>,
+    pub attempts: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[typeshare]
+pub struct SyncStatus {
+    pub pending: u32,
+    pub syncing: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_success: Option<DateTime<Utc>>,
+    // Operations whose last replay failed for a reason other than being offline
+    pub errors: Vec<PendingSyncOperation>,
+}
+
+impl SyncOperation {
+    // The table and local id of the row this operation writes
+    pub fn row(&self) -> (&'static str, i64) {
+        match self {
+            SyncOperation::SaveStatBlock { statblock_id, .. }
+            | SyncOperation::DeleteStatBlock { statblock_id } => ("StatBlock", *statblock_id),
+            SyncOperation::SaveEncounter { encounter_id, .. }
+            | SyncOperation::DeleteEncounter { encounter_id }
+            | SyncOperation::SaveEncounterPlayers { encounter_id }
+            | SyncOperation::SavePlayableStatBlocks { encounter_id }
+            | SyncOperation::UpdateCombatant { encounter_id, .. } => ("Encounter", *encounter_id),
+        }
+    }
+
+    pub fn is_delete(&self) -> bool {
+        matches!(
+            self,
+            SyncOperation::DeleteStatBlock { .. } | SyncOperation::DeleteEncounter { .. }
+        )
+    }
+
+    // A second save of the same row replaces nothing, the queued one already replays the latest local copy
+    pub fn supersedes(&self, other: &SyncOperation) -> bool {
+        match (self, other) {
+            (
+                SyncOperation::SaveStatBlock {
+                    statblock_id: a, ..
+                },
+                SyncOperation::SaveStatBlock {
+                    statblock_id: b, ..
+                },
+            ) => a == b,
+            (
+                SyncOperation::SaveEncounter {
+                    encounter_id: a, ..
+                },
+                SyncOperation::SaveEncounter {
+                    encounter_id: b, ..
+                },
+            ) => a == b,
+            _ => self == other,
+        }
+    }
+
+    // Rewrites a temporary id once Supabase has assigned the real one
+    pub fn remap_id(&mut self, table_name: &str, temporary_id: i64, server_id: i64) {
+        let id = match (table_name, self) {
+            (
+                "StatBlock",
+                SyncOperation::SaveStatBlock { statblock_id, .. }
+                | SyncOperation::DeleteStatBlock { statblock_id },
+            ) => statblock_id,
+            (
+                "Encounter",
+                SyncOperation::SaveEncounter { encounter_id, .. }
+                | SyncOperation::DeleteEncounter { encounter_id }
+                | SyncOperation::SaveEncounterPlayers { encounter_id }
+                | SyncOperation::SavePlayableStatBlocks { encounter_id }
+                | SyncOperation::UpdateCombatant { encounter_id, .. },
+            ) => encounter_id,
+            _ => return,
+        };
+
+        if *id == temporary_id {
+            *id = server_id;
+        }
+    }
+}
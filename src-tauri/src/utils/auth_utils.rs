@@ -1,16 +1,47 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use rand::{rngs::OsRng, RngCore};
 use serde_json::json;
-use std::{collections::HashMap, fs};
-use tauri::Manager;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+use tauri::Emitter;
 
 use crate::{
-    types::auth_types::{AuthResponse, LoginRequest, RegisterRequest, SupabaseAuthResponse},
-    utils::supabase_util::init_supabase,
+    database::local_db::close_local_store,
+    types::{
+        auth_types::{
+            AuthResponse, LoginRequest, OAuthCallback, PendingOAuth, RegisterRequest,
+            SupabaseAuthResponse,
+        },
+        error_types::AppError,
+    },
+    utils::{
+        credential_utils::{
+            read_credential, remove_credential, store_credential, validate_frontend_key,
+        },
+        supabase_util::{init_supabase, SupabaseClient},
+    },
 };
 
+static AUTH_APP: OnceLock<tauri::AppHandle> = OnceLock::new();
+static REFRESH_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+// token_expires_at as last stored, None until it is first read from the credential store
+static TOKEN_EXPIRES_AT: Mutex<Option<Option<i64>>> = Mutex::new(None);
+
+// Refresh this long before the token runs out so a request never starts with a dying token
+const REFRESH_MARGIN_MS: i64 = 60_000;
+
+const OAUTH_REDIRECT_URI: &str = "encounterarchitect://oauth";
+// A login left open longer than this is abandoned, its deep link is refused
+const OAUTH_TIMEOUT_MS: i64 = 10 * 60_000;
+
 #[tauri::command]
 pub async fn register_with_email(
     register_request: RegisterRequest,
-) -> Result<AuthResponse, String> {
+) -> Result<AuthResponse, AppError> {
     let config = init_supabase().await?;
     let client = reqwest::Client::new();
 
@@ -23,8 +54,6 @@ pub async fn register_with_email(
         }
     });
 
-    println!("Registering with: {:?}", body);
-
     let response = client
         .post(&format!("{}/auth/v1/signup", config.url))
         .header("apikey", &config.anon_key)
@@ -32,29 +61,22 @@ pub async fn register_with_email(
         .json(&body)
         .send()
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+        .map_err(|e| AppError::network(format!("Request failed: {}", e)))?;
 
     if !response.status().is_success() {
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("Registration failed: {}", error_text));
+        return Err(AppError::from_response("Registration failed", response).await);
     }
 
     let supabase_response: SupabaseAuthResponse = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+        .map_err(|e| AppError::parse(format!("Failed to parse response: {}", e)))?;
 
-    let auth_response = convert_supabase_response(supabase_response);
-
-    println!("Received response: {:?}", auth_response);
-    Ok(auth_response)
+    Ok(convert_supabase_response(supabase_response))
 }
 
 #[tauri::command]
-pub async fn login_with_email(login_request: LoginRequest) -> Result<AuthResponse, String> {
+pub async fn login_with_email(login_request: LoginRequest) -> Result<AuthResponse, AppError> {
     let config = init_supabase().await?;
     let client = reqwest::Client::new();
 
@@ -70,20 +92,16 @@ pub async fn login_with_email(login_request: LoginRequest) -> Result<AuthRespons
         .json(&body)
         .send()
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+        .map_err(|e| AppError::network(format!("Request failed: {}", e)))?;
 
     if !response.status().is_success() {
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("Login failed: {}", error_text));
+        return Err(auth_error("Login failed", response).await);
     }
 
     let supabase_response: SupabaseAuthResponse = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+        .map_err(|e| AppError::parse(format!("Failed to parse response: {}", e)))?;
 
     let auth_response = convert_supabase_response(supabase_response);
 
@@ -91,15 +109,28 @@ pub async fn login_with_email(login_request: LoginRequest) -> Result<AuthRespons
 }
 
 #[tauri::command]
-pub async fn login_with_discord() -> Result<String, String> {
+pub async fn login_with_discord(app: tauri::AppHandle) -> Result<String, AppError> {
     let config = init_supabase().await?;
 
-    let redirect_uri = "encounterarchitect://oauth";
+    let pending = PendingOAuth {
+        state: random_token(16),
+        code_verifier: random_token(32),
+        created_at: Utc::now().timestamp_millis(),
+    };
+    let code_challenge = code_challenge(&pending.code_verifier);
+
+    // Supabase keeps the query of redirect_to and appends the code, so the state comes back with it
+    let redirect_uri = format!("{}?state={}", OAUTH_REDIRECT_URI, pending.state);
+
+    let pending_json =
+        serde_json::to_string(&pending).map_err(|e| AppError::parse(e.to_string()))?;
+    store_credential(&app, "oauth_pending", &pending_json)?;
 
     let discord_oauth_url = format!(
-        "{}/auth/v1/authorize?provider=discord&redirect_to={}",
+        "{}/auth/v1/authorize?provider=discord&redirect_to={}&code_challenge={}&code_challenge_method=s256",
         config.url,
-        urlencoding::encode(redirect_uri)
+        urlencoding::encode(&redirect_uri),
+        code_challenge
     );
 
     Ok(discord_oauth_url)
@@ -107,32 +138,43 @@ pub async fn login_with_discord() -> Result<String, String> {
 
 #[tauri::command]
 pub async fn handle_discord_oauth_callback(
+    app: tauri::AppHandle,
     code: String,
     state: String,
-) -> Result<AuthResponse, String> {
+) -> Result<AuthResponse, AppError> {
     let config = init_supabase().await?;
     let client = reqwest::Client::new();
 
+    let pending = read_credential(&app, "oauth_pending")?
+        .ok_or(AppError::unauthorized("No Discord login is in progress"))?;
+    let pending: PendingOAuth = serde_json::from_str(&pending)
+        .map_err(|e| AppError::parse(format!("Failed to parse pending login: {}", e)))?;
+
+    check_oauth_state(&pending, &state, Utc::now().timestamp_millis())?;
+    // Only a matching state ends the login, a forged link can't cancel the real one
+    remove_credential(&app, "oauth_pending")?;
+
     let mut body = HashMap::new();
     body.insert("auth_code", code);
-    body.insert("code_verifier", state);
+    body.insert("code_verifier", pending.code_verifier);
 
     let response = client
-        .post(&format!(
-            "{}/auth/v1/token?grant_type=authorization_code",
-            config.url
-        ))
+        .post(&format!("{}/auth/v1/token?grant_type=pkce", config.url))
         .header("apikey", &config.anon_key)
         .header("Content-Type", "application/json")
         .json(&body)
         .send()
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+        .map_err(|e| AppError::network(format!("Request failed: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(auth_error("Discord login failed", response).await);
+    }
 
     let supabase_response: SupabaseAuthResponse = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+        .map_err(|e| AppError::parse(format!("Failed to parse response: {}", e)))?;
 
     let auth_response = convert_supabase_response(supabase_response);
 
@@ -140,28 +182,29 @@ pub async fn handle_discord_oauth_callback(
 }
 
 #[tauri::command]
-pub async fn get_current_user(access_token: String) -> Result<serde_json::Value, String> {
-    let config = init_supabase().await?;
-    let client = reqwest::Client::new();
+pub async fn get_current_user(access_token: String) -> Result<serde_json::Value, AppError> {
+    let client = SupabaseClient::connect(&access_token).await?;
+    let user_url = format!("{}/auth/v1/user", client.config.url);
 
-    let response = client
-        .get(&format!("{}/auth/v1/user", config.url))
-        .header("apikey", &config.anon_key)
-        .header("Authorization", format!("Bearer {}", access_token))
-        .send()
-        .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+    let response = client.send(|client| client.get(&user_url)).await?;
+
+    if !response.status().is_success() {
+        return Err(AppError::from_response("User fetch failed", response).await);
+    }
 
     let user: serde_json::Value = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+        .map_err(|e| AppError::parse(format!("Failed to parse response: {}", e)))?;
 
     Ok(user)
 }
 
 #[tauri::command]
-pub async fn logout_user(access_token: String) -> Result<(), String> {
+pub async fn logout_user(access_token: String) -> Result<(), AppError> {
+    // Closed first so an offline logout still leaves the next user with an empty store
+    close_local_store()?;
+
     let config = init_supabase().await?;
     let client = reqwest::Client::new();
 
@@ -171,66 +214,54 @@ pub async fn logout_user(access_token: String) -> Result<(), String> {
         .header("Authorization", format!("Bearer {}", access_token))
         .send()
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+        .map_err(|e| AppError::network(format!("Request failed: {}", e)))?;
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn store_value(app: tauri::AppHandle, key: String, value: String) -> Result<(), String> {
-    let app_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
-
-    fs::create_dir_all(&app_dir).map_err(|e| format!("Failed to create app data dir: {}", e))?;
-
-    let file_path = app_dir.join(format!("{}.txt", key));
-
-    fs::write(file_path, value).map_err(|e| format!("Failed to write to storage: {}", e))?;
+pub async fn store_value(
+    app: tauri::AppHandle,
+    key: String,
+    value: String,
+) -> Result<(), AppError> {
+    validate_frontend_key(&key)?;
+    store_credential(&app, &key, &value)?;
+    if key == "token_expires_at" {
+        cache_token_expiry(parse_token_expiry(&value));
+    }
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_stored_value(app: tauri::AppHandle, key: String) -> Result<String, String> {
-    let app_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
-
-    let file_path = app_dir.join(format!("{}.txt", key));
-
-    if !file_path.exists() {
-        return Err(format!("Key not found: {}", key));
-    }
-
-    let value =
-        fs::read_to_string(file_path).map_err(|e| format!("Failed to read from storage: {}", e))?;
-
-    Ok(value)
+pub async fn get_stored_value(app: tauri::AppHandle, key: String) -> Result<String, AppError> {
+    validate_frontend_key(&key)?;
+    read_credential(&app, &key)?.ok_or(AppError::not_found(format!("Key not found: {}", key)))
 }
 
 #[tauri::command]
-pub async fn remove_stored_value(app: tauri::AppHandle, key: String) -> Result<(), String> {
-    let app_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
-
-    let file_path = app_dir.join(format!("{}.txt", key));
-
-    if !file_path.exists() {
-        return Err(format!("Key not found: {}", key));
+pub async fn remove_stored_value(app: tauri::AppHandle, key: String) -> Result<(), AppError> {
+    validate_frontend_key(&key)?;
+    let removed = remove_credential(&app, &key)?;
+    if key == "token_expires_at" {
+        cache_token_expiry(None);
     }
 
-    fs::remove_file(file_path).map_err(|e| format!("Failed to remove from storage: {}", e))?;
+    if !removed {
+        return Err(AppError::not_found(format!("Key not found: {}", key)));
+    }
 
     Ok(())
 }
 
+// Refreshes the stored session for the frontend's timer, sharing the lock with requests that hit an expired token
 #[tauri::command]
-pub async fn refresh_access_token(refresh_token: String) -> Result<AuthResponse, String> {
+pub async fn refresh_session_tokens(access_token: String) -> Result<String, AppError> {
+    refresh_session(&access_token).await
+}
+
+async fn refresh_access_token(refresh_token: String) -> Result<AuthResponse, AppError> {
     let config = init_supabase().await?;
     let client = reqwest::Client::new();
 
@@ -248,26 +279,249 @@ pub async fn refresh_access_token(refresh_token: String) -> Result<AuthResponse,
         .json(&body)
         .send()
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+        .map_err(|e| AppError::network(format!("Request failed: {}", e)))?;
 
     if !response.status().is_success() {
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Token refresh failed".to_string());
-        return Err(format!("Token refresh failed: {}", error_text));
+        return Err(auth_error("Token refresh failed", response).await);
     }
 
     let supabase_response: SupabaseAuthResponse = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+        .map_err(|e| AppError::parse(format!("Failed to parse response: {}", e)))?;
 
     let auth_response = convert_supabase_response(supabase_response);
 
     Ok(auth_response)
 }
 
+//? Session
+
+pub fn init_session(app: tauri::AppHandle) {
+    let _ = AUTH_APP.set(app);
+}
+
+// Checked before every Supabase request, so the expiry is kept in memory instead of decrypting the store each time
+pub async fn session_expiring() -> bool {
+    let Some(app) = AUTH_APP.get() else {
+        return false;
+    };
+
+    let cached = TOKEN_EXPIRES_AT.lock().ok().and_then(|cached| *cached);
+    let expires_at = match cached {
+        Some(expires_at) => expires_at,
+        None => {
+            let expires_at = get_stored_value(app.clone(), "token_expires_at".to_string())
+                .await
+                .ok()
+                .and_then(|expires_at| parse_token_expiry(&expires_at));
+            cache_token_expiry(expires_at);
+            expires_at
+        }
+    };
+
+    token_expiring(expires_at, Utc::now().timestamp_millis())
+}
+
+// Supabase checks the signature on every request, this only reads whose session the token belongs to
+pub fn token_user_id(access_token: &str) -> Result<String, AppError> {
+    access_token
+        .split('.')
+        .nth(1)
+        .and_then(|payload| URL_SAFE_NO_PAD.decode(payload).ok())
+        .and_then(|payload| serde_json::from_slice::<serde_json::Value>(&payload).ok())
+        .and_then(|claims| claims["sub"].as_str().map(str::to_string))
+        .ok_or(AppError::unauthorized("Access token has no user"))
+}
+
+// token_expires_at is stored in milliseconds, the same way the frontend stores it
+pub fn parse_token_expiry(value: &str) -> Option<i64> {
+    value.trim().parse().ok()
+}
+
+pub fn token_expiring(expires_at: Option<i64>, now: i64) -> bool {
+    expires_at.is_some_and(|expires_at| now >= expires_at - REFRESH_MARGIN_MS)
+}
+
+fn cache_token_expiry(expires_at: Option<i64>) {
+    if let Ok(mut cached) = TOKEN_EXPIRES_AT.lock() {
+        *cached = Some(expires_at);
+    }
+}
+
+// Returns an access token to replace expired_token, refreshing the stored session if nobody else has
+pub async fn refresh_session(expired_token: &str) -> Result<String, AppError> {
+    let Some(app) = AUTH_APP.get() else {
+        return Err(AppError::unauthorized("Session expired"));
+    };
+
+    // Requests that fail together wait here so the refresh token is only spent once
+    let _refreshing = REFRESH_LOCK.lock().await;
+
+    if let Ok(stored_token) = get_stored_value(app.clone(), "access_token".to_string()).await {
+        if stored_token != expired_token && !session_expiring().await {
+            return Ok(stored_token);
+        }
+    }
+
+    match refresh_stored_session(app).await {
+        Ok(access_token) => Ok(access_token),
+        // Offline isn't a reason to log out, the offline fallbacks take over
+        Err(e @ AppError::Network { .. }) => Err(e),
+        Err(e) => {
+            if let Err(emit_error) = app.emit("auth-refresh-failed", &e) {
+                eprintln!("Failed to emit auth-refresh-failed event: {}", emit_error);
+            }
+            Err(e)
+        }
+    }
+}
+
+async fn refresh_stored_session(app: &tauri::AppHandle) -> Result<String, AppError> {
+    let refresh_token = get_stored_value(app.clone(), "refresh_token".to_string())
+        .await
+        .map_err(|_| AppError::unauthorized("Session expired: no refresh token stored"))?;
+
+    let auth_response = refresh_access_token(refresh_token).await?;
+    store_session(app, auth_response).await
+}
+
+// Keeps the tokens of a login or refresh and returns the new access token
+async fn store_session(
+    app: &tauri::AppHandle,
+    auth_response: AuthResponse,
+) -> Result<String, AppError> {
+    let access_token = auth_response
+        .access_token
+        .clone()
+        .ok_or(AppError::unauthorized("Supabase returned no access token"))?;
+
+    store_value(
+        app.clone(),
+        "access_token".to_string(),
+        access_token.clone(),
+    )
+    .await?;
+    if let Some(refresh_token) = auth_response.refresh_token {
+        store_value(app.clone(), "refresh_token".to_string(), refresh_token).await?;
+    }
+
+    let expires_at = match (auth_response.expires_at, auth_response.expires_in) {
+        (Some(expires_at), _) => expires_at as i64 * 1000,
+        (None, expires_in) => {
+            Utc::now().timestamp_millis() + expires_in.unwrap_or(3600) as i64 * 1000
+        }
+    };
+    store_value(
+        app.clone(),
+        "token_expires_at".to_string(),
+        expires_at.to_string(),
+    )
+    .await?;
+
+    Ok(access_token)
+}
+
+//? Discord OAuth
+
+// Finishes the login a deep link belongs to, storing the session the same way the frontend reads it
+pub async fn complete_discord_login(
+    app: &tauri::AppHandle,
+    callback: OAuthCallback,
+) -> Result<(), AppError> {
+    let auth_response =
+        handle_discord_oauth_callback(app.clone(), callback.code, callback.state).await?;
+    let access_token = store_session(app, auth_response).await?;
+
+    let user = get_current_user(access_token).await?;
+    store_value(app.clone(), "user".to_string(), user.to_string()).await
+}
+
+// None for links that aren't an OAuth redirect, tokens handed over in the fragment are refused
+pub fn parse_oauth_callback(url: &str) -> Result<Option<OAuthCallback>, AppError> {
+    let parsed_url = url::Url::parse(url)
+        .map_err(|e| AppError::validation("url", format!("Invalid deep link: {}", e)))?;
+
+    if parsed_url.scheme() != "encounterarchitect" || parsed_url.host_str() != Some("oauth") {
+        return Ok(None);
+    }
+
+    // Anything can open the deep link, a session that didn't come from our own code exchange is not trusted
+    if parsed_url
+        .fragment()
+        .is_some_and(|fragment| fragment.contains("access_token"))
+    {
+        return Err(AppError::unauthorized(
+            "OAuth redirect carried tokens instead of a code",
+        ));
+    }
+
+    let query_pairs: HashMap<_, _> = parsed_url.query_pairs().collect();
+
+    if let Some(error) = query_pairs.get("error") {
+        let description = query_pairs.get("error_description").unwrap_or(error);
+        return Err(AppError::unauthorized(format!(
+            "Discord login failed: {}",
+            description
+        )));
+    }
+
+    let code = query_pairs
+        .get("code")
+        .ok_or(AppError::validation("code", "OAuth redirect has no code"))?;
+    let state = query_pairs
+        .get("state")
+        .ok_or(AppError::validation("state", "OAuth redirect has no state"))?;
+
+    Ok(Some(OAuthCallback {
+        code: code.to_string(),
+        state: state.to_string(),
+    }))
+}
+
+pub fn check_oauth_state(pending: &PendingOAuth, state: &str, now: i64) -> Result<(), AppError> {
+    if now - pending.created_at > OAUTH_TIMEOUT_MS {
+        return Err(AppError::unauthorized(
+            "Discord login took too long, start it again",
+        ));
+    }
+
+    // Compared in full so the time taken doesn't reveal how much of the state matched
+    let matches = pending.state.len() == state.len()
+        && pending
+            .state
+            .bytes()
+            .zip(state.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0;
+
+    if !matches {
+        return Err(AppError::unauthorized(
+            "OAuth state does not match the login in progress",
+        ));
+    }
+
+    Ok(())
+}
+
+fn random_token(byte_count: usize) -> String {
+    let mut bytes = vec![0u8; byte_count];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+// Supabase answers a wrong password or a revoked refresh token with 400, both mean the user has to log in again
+async fn auth_error(context: &str, response: reqwest::Response) -> AppError {
+    match AppError::from_response(context, response).await {
+        AppError::Validation { reason, .. } => AppError::unauthorized(reason),
+        error => error,
+    }
+}
+
 fn convert_supabase_response(supabase_response: SupabaseAuthResponse) -> AuthResponse {
     let user = if let Some(user_data) = supabase_response.user {
         Some(crate::types::auth_types::User {
@@ -310,3 +564,148 @@ fn convert_supabase_response(supabase_response: SupabaseAuthResponse) -> AuthRes
             .or(supabase_response.error_description),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_expiry_is_read_in_milliseconds() {
+        assert_eq!(
+            parse_token_expiry(" 1760788800000\n"),
+            Some(1_760_788_800_000)
+        );
+        assert_eq!(parse_token_expiry("soon"), None);
+    }
+
+    #[test]
+    fn session_is_expiring_within_the_refresh_margin() {
+        let expires_at = 1_760_788_800_000;
+
+        assert!(!token_expiring(
+            Some(expires_at),
+            expires_at - REFRESH_MARGIN_MS - 1
+        ));
+        assert!(token_expiring(
+            Some(expires_at),
+            expires_at - REFRESH_MARGIN_MS
+        ));
+        assert!(token_expiring(Some(expires_at), expires_at + 1));
+        // Without a stored expiry only a 401 triggers a refresh
+        assert!(!token_expiring(None, expires_at));
+    }
+
+    #[test]
+    fn user_id_is_read_from_the_token_subject() {
+        let claims = URL_SAFE_NO_PAD
+            .encode(r#"{"sub":"6f1c2a9e-0b7d-4e8a-9c3f-2d5e8b1a4c7f","role":"authenticated"}"#);
+        let access_token = format!("eyJhbGciOiJIUzI1NiJ9.{}.signature", claims);
+
+        assert_eq!(
+            token_user_id(&access_token).unwrap(),
+            "6f1c2a9e-0b7d-4e8a-9c3f-2d5e8b1a4c7f"
+        );
+        assert!(token_user_id("access-token").is_err());
+        assert!(token_user_id(&format!(
+            "header.{}.signature",
+            URL_SAFE_NO_PAD.encode("{}")
+        ))
+        .is_err());
+    }
+
+    fn pending(state: &str) -> PendingOAuth {
+        PendingOAuth {
+            state: state.to_string(),
+            code_verifier: "verifier".to_string(),
+            created_at: 1_760_788_800_000,
+        }
+    }
+
+    fn is_unauthorized(error: AppError) -> bool {
+        matches!(error, AppError::Unauthorized { .. })
+    }
+
+    #[test]
+    fn callback_with_code_and_state_is_accepted() {
+        let callback = parse_oauth_callback("encounterarchitect://oauth?state=abc&code=xyz")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(callback.code, "xyz");
+        assert_eq!(callback.state, "abc");
+    }
+
+    #[test]
+    fn other_deep_links_are_ignored() {
+        assert!(parse_oauth_callback("encounterarchitect://encounter/7")
+            .unwrap()
+            .is_none());
+        assert!(parse_oauth_callback("https://oauth/?code=xyz&state=abc")
+            .unwrap()
+            .is_none());
+        assert!(parse_oauth_callback("not a url").is_err());
+    }
+
+    #[test]
+    fn tokens_in_the_fragment_are_refused() {
+        let error = parse_oauth_callback(
+            "encounterarchitect://oauth#access_token=forged&refresh_token=forged&expires_in=3600",
+        )
+        .unwrap_err();
+
+        assert!(is_unauthorized(error));
+    }
+
+    #[test]
+    fn callback_without_code_or_state_is_refused() {
+        for url in [
+            "encounterarchitect://oauth?state=abc",
+            "encounterarchitect://oauth?code=xyz",
+            "encounterarchitect://oauth",
+        ] {
+            let error = parse_oauth_callback(url).unwrap_err();
+            assert!(matches!(error, AppError::Validation { .. }), "{}", url);
+        }
+    }
+
+    #[test]
+    fn error_callbacks_report_the_description() {
+        let error = parse_oauth_callback(
+            "encounterarchitect://oauth?state=abc&error=access_denied&error_description=User+cancelled",
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "Discord login failed: User cancelled");
+
+        let error =
+            parse_oauth_callback("encounterarchitect://oauth?error=access_denied").unwrap_err();
+        assert_eq!(error.to_string(), "Discord login failed: access_denied");
+    }
+
+    #[test]
+    fn matching_state_within_the_timeout_is_accepted() {
+        let pending = pending("abc");
+
+        assert!(check_oauth_state(&pending, "abc", pending.created_at).is_ok());
+        assert!(check_oauth_state(&pending, "abc", pending.created_at + OAUTH_TIMEOUT_MS).is_ok());
+    }
+
+    #[test]
+    fn mismatched_state_is_refused() {
+        let pending = pending("abc");
+
+        for state in ["abd", "ab", "abcd", ""] {
+            let error = check_oauth_state(&pending, state, pending.created_at).unwrap_err();
+            assert!(is_unauthorized(error), "{}", state);
+        }
+    }
+
+    #[test]
+    fn expired_pending_login_is_refused() {
+        let pending = pending("abc");
+
+        let error = check_oauth_state(&pending, "abc", pending.created_at + OAUTH_TIMEOUT_MS + 1)
+            .unwrap_err();
+
+        assert!(is_unauthorized(error));
+    }
+}
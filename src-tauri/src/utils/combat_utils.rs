@@ -0,0 +1,612 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use tauri::Manager;
+
+use crate::{
+    database::{
+        encounter_db::{
+            fetch_encounter_players_from, fetch_playable_statblocks_from,
+            save_encounter_players_to, save_playable_statblocks_to, update_combatant_conditions,
+        },
+        repository::{EncounterRepository, StatBlockRepository, SupabaseRepository},
+        statblock_db::fetch_statblocks_by_ids,
+    },
+    types::{
+        combat_types::{
+            initiative_order, CombatSession, Combatant, CombatantKind, CombatantRef,
+            InitiativeOptions, InitiativeRoll,
+        },
+        condition_types::{tick_conditions, ActiveCondition, ConditionExpiry},
+        encounter_types::PlayableStatBlock,
+        error_types::AppError,
+        statblock_types::{Score, StatBlock},
+    },
+    utils::dice_utils::{dice_rng, DiceExpression},
+};
+
+pub struct CombatantState {
+    pub name: String,
+    pub current_hp: u16,
+    pub temporary_hp: u16,
+    pub max_hp: u16,
+    pub conditions: Vec<ActiveCondition>,
+    pub statblock: Option<StatBlock>,
+}
+
+//? Commands
+
+#[tauri::command]
+pub async fn start_combat(
+    app: tauri::AppHandle,
+    encounter_id: i64,
+    tie_break_by_dexterity: bool,
+    access_token: String,
+) -> Result<CombatSession, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
+
+    let encounter_players = fetch_encounter_players_from(&repository, encounter_id)
+        .await?
+        .encounter_players;
+
+    let playable_stat_blocks = fetch_playable_statblocks_from(&repository, encounter_id)
+        .await?
+        .playable_stat_blocks;
+
+    let statblocks = fetch_encounter_statblocks(&repository, &playable_stat_blocks).await?;
+
+    let mut combatants: Vec<Combatant> = encounter_players
+        .iter()
+        .map(Combatant::from_player)
+        .collect();
+
+    for playable in &playable_stat_blocks {
+        let dexterity = statblocks
+            .get(&playable.statblock_id)
+            .map(|statblock| statblock.stats.score(Score::Dexterity));
+        combatants.push(Combatant::from_playable_statblock(playable, dexterity)?);
+    }
+
+    let session = CombatSession::new(encounter_id, combatants, tie_break_by_dexterity)?;
+    write_combat_session(&app, &session)?;
+
+    Ok(session)
+}
+
+// Rolls for every combatant without an initiative and saves the results to the encounter
+#[tauri::command]
+pub async fn roll_initiative(
+    encounter_id: i64,
+    options: InitiativeOptions,
+    access_token: String,
+) -> Result<Vec<InitiativeRoll>, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
+
+    let mut encounter_players = fetch_encounter_players_from(&repository, encounter_id)
+        .await?
+        .encounter_players;
+
+    let mut playable_stat_blocks = fetch_playable_statblocks_from(&repository, encounter_id)
+        .await?
+        .playable_stat_blocks;
+
+    let statblocks = fetch_encounter_statblocks(&repository, &playable_stat_blocks).await?;
+
+    let d20: DiceExpression = "1d20".parse().map_err(AppError::parse)?;
+    let mut rng = dice_rng(options.seed);
+    let mut rolls = Vec::new();
+
+    // Groups that already have a member with initiative join that value instead of rolling
+    let mut group_initiatives: HashMap<i64, u16> = HashMap::new();
+    if options.group_by_statblock {
+        for playable in &playable_stat_blocks {
+            if let Some(initiative) = playable.initiative {
+                group_initiatives
+                    .entry(playable.statblock_id)
+                    .or_insert(initiative);
+            }
+        }
+    }
+
+    for playable in playable_stat_blocks
+        .iter_mut()
+        .filter(|playable| playable.initiative.is_none())
+    {
+        let statblock = statblocks
+            .get(&playable.statblock_id)
+            .ok_or(AppError::not_found(format!(
+                "Initiative roll failed: StatBlock {} not found",
+                playable.statblock_id
+            )))?;
+        let bonus = statblock.initiative_bonus();
+
+        let (roll, initiative) = match group_initiatives.get(&playable.statblock_id) {
+            Some(&initiative) if options.group_by_statblock => (None, initiative),
+            _ => {
+                let roll = d20.roll(&mut rng);
+                let initiative = (roll.total + bonus as i32).max(0) as u16;
+                if options.group_by_statblock {
+                    group_initiatives.insert(playable.statblock_id, initiative);
+                }
+                (Some(roll), initiative)
+            }
+        };
+
+        playable.initiative = Some(initiative);
+
+        let combatant = Combatant::from_playable_statblock(
+            playable,
+            Some(statblock.stats.score(Score::Dexterity)),
+        )?;
+        rolls.push(InitiativeRoll {
+            key: combatant.key,
+            kind: CombatantKind::Monster,
+            name: combatant.name,
+            bonus,
+            dexterity: combatant.dexterity,
+            roll,
+            initiative,
+        });
+    }
+
+    let monsters_rolled = !rolls.is_empty();
+    let mut players_rolled = false;
+
+    if options.include_players {
+        for player in encounter_players
+            .iter_mut()
+            .filter(|player| player.initiative.is_none())
+        {
+            // Player modifiers aren't tracked, so this is a flat d20
+            let roll = d20.roll(&mut rng);
+            let initiative = roll.total as u16;
+            player.initiative = Some(initiative);
+            players_rolled = true;
+
+            let combatant = Combatant::from_player(player);
+            rolls.push(InitiativeRoll {
+                key: combatant.key,
+                kind: CombatantKind::Player,
+                name: combatant.name,
+                bonus: 0,
+                dexterity: None,
+                roll: Some(roll),
+                initiative,
+            });
+        }
+    }
+
+    if monsters_rolled {
+        save_playable_statblocks_to(&repository, playable_stat_blocks).await?;
+    }
+    if players_rolled {
+        save_encounter_players_to(&repository, encounter_players).await?;
+    }
+
+    rolls.sort_by_key(|roll| {
+        initiative_order(
+            Some(roll.initiative),
+            roll.dexterity,
+            options.tie_break_by_dexterity,
+        )
+    });
+
+    Ok(rolls)
+}
+
+#[tauri::command]
+pub async fn get_combat_session(
+    app: tauri::AppHandle,
+    encounter_id: i64,
+) -> Result<Option<CombatSession>, AppError> {
+    read_combat_session(&app, encounter_id)
+}
+
+// Also counts down conditions that expire at the end of the outgoing turn or start of the next
+#[tauri::command]
+pub async fn next_turn(
+    app: tauri::AppHandle,
+    encounter_id: i64,
+    access_token: String,
+) -> Result<CombatSession, AppError> {
+    let mut session = require_combat_session(&app, encounter_id)?;
+    let repository = SupabaseRepository::connect(&access_token).await?;
+
+    // Written only once the expiries went through, so a failed call can be retried without skipping a turn
+    advance_turn(&repository, &mut session).await?;
+    write_combat_session(&app, &session)?;
+
+    Ok(session)
+}
+
+#[tauri::command]
+pub async fn previous_turn(
+    app: tauri::AppHandle,
+    encounter_id: i64,
+) -> Result<CombatSession, AppError> {
+    update_combat_session(&app, encounter_id, |session| session.previous_turn())
+}
+
+#[tauri::command]
+pub async fn delay_turn(
+    app: tauri::AppHandle,
+    encounter_id: i64,
+    combatant_key: String,
+) -> Result<CombatSession, AppError> {
+    update_combat_session(&app, encounter_id, |session| session.delay(&combatant_key))
+}
+
+#[tauri::command]
+pub async fn resume_delayed_turn(
+    app: tauri::AppHandle,
+    encounter_id: i64,
+    combatant_key: String,
+) -> Result<CombatSession, AppError> {
+    update_combat_session(&app, encounter_id, |session| session.resume(&combatant_key))
+}
+
+#[tauri::command]
+pub async fn ready_action(
+    app: tauri::AppHandle,
+    encounter_id: i64,
+    combatant_key: String,
+    trigger: String,
+) -> Result<CombatSession, AppError> {
+    update_combat_session(&app, encounter_id, |session| {
+        session.ready(&combatant_key, trigger)
+    })
+}
+
+#[tauri::command]
+pub async fn trigger_readied_action(
+    app: tauri::AppHandle,
+    encounter_id: i64,
+    combatant_key: String,
+) -> Result<CombatSession, AppError> {
+    update_combat_session(&app, encounter_id, |session| {
+        session.trigger_readied(&combatant_key)
+    })
+}
+
+#[tauri::command]
+pub async fn end_combat(app: tauri::AppHandle, encounter_id: i64) -> Result<(), AppError> {
+    let file_path = combat_session_path(&app, encounter_id)?;
+
+    if file_path.exists() {
+        fs::remove_file(file_path)
+            .map_err(|e| AppError::storage(format!("Failed to remove combat session: {}", e)))?;
+    }
+
+    Ok(())
+}
+
+//? Helper Util
+
+pub async fn fetch_combatant(
+    repository: &(impl EncounterRepository + StatBlockRepository),
+    combatant: &CombatantRef,
+    encounter_id: i64,
+) -> Result<CombatantState, AppError> {
+    match combatant {
+        CombatantRef::Player(name) => {
+            let player = fetch_encounter_players_from(repository, encounter_id)
+                .await?
+                .encounter_players
+                .into_iter()
+                .find(|player| &player.name == name)
+                .ok_or(AppError::not_found(format!(
+                    "EncounterPlayer not found: {}",
+                    name
+                )))?;
+
+            Ok(CombatantState {
+                name: player.name,
+                current_hp: player.current_hp,
+                temporary_hp: player.temporary_hp,
+                max_hp: player.hp,
+                conditions: player.conditions,
+                statblock: None,
+            })
+        }
+        CombatantRef::Monster(id) => {
+            let playable = fetch_playable_statblocks_from(repository, encounter_id)
+                .await?
+                .playable_stat_blocks
+                .into_iter()
+                .find(|playable| playable.id == Some(*id))
+                .ok_or(AppError::not_found(format!(
+                    "PlayableStatBlock not found: {}",
+                    id
+                )))?;
+
+            let statblock = fetch_statblocks_by_ids(repository, &[playable.statblock_id])
+                .await?
+                .into_iter()
+                .next()
+                .ok_or(AppError::not_found(format!(
+                    "StatBlock not found: {}",
+                    playable.statblock_id
+                )))?;
+
+            Ok(CombatantState {
+                name: playable.name.unwrap_or_else(|| statblock.name.clone()),
+                current_hp: playable.current_hp,
+                temporary_hp: playable.temporary_hp,
+                // Legacy instances without a stored maximum use the StatBlock's average
+                max_hp: playable.max_hp.unwrap_or(statblock.hp),
+                conditions: playable.conditions,
+                statblock: Some(statblock),
+            })
+        }
+    }
+}
+
+pub async fn fetch_encounter_statblocks(
+    repository: &impl StatBlockRepository,
+    playable_stat_blocks: &[PlayableStatBlock],
+) -> Result<HashMap<i64, StatBlock>, AppError> {
+    let mut statblock_ids: Vec<i64> = playable_stat_blocks
+        .iter()
+        .map(|playable| playable.statblock_id)
+        .collect();
+    statblock_ids.sort_unstable();
+    statblock_ids.dedup();
+
+    Ok(fetch_statblocks_by_ids(repository, &statblock_ids)
+        .await?
+        .into_iter()
+        .filter_map(|statblock| statblock.id.map(|id| (id, statblock)))
+        .collect())
+}
+
+pub async fn advance_turn(
+    repository: &impl EncounterRepository,
+    session: &mut CombatSession,
+) -> Result<(), AppError> {
+    let ended_key = session.current().map(|combatant| combatant.key.clone());
+    session.next_turn()?;
+    let started_key = session.current().map(|combatant| combatant.key.clone());
+
+    expire_conditions(
+        repository,
+        session.encounter_id,
+        ended_key.as_deref(),
+        started_key.as_deref(),
+    )
+    .await
+}
+
+async fn expire_conditions(
+    repository: &impl EncounterRepository,
+    encounter_id: i64,
+    ended_key: Option<&str>,
+    started_key: Option<&str>,
+) -> Result<(), AppError> {
+    let tick = |conditions: &mut Vec<ActiveCondition>, owner_key: &str| {
+        let ended = ended_key.is_some_and(|key| {
+            tick_conditions(conditions, owner_key, key, ConditionExpiry::EndOfTurn)
+        });
+        let started = started_key.is_some_and(|key| {
+            tick_conditions(conditions, owner_key, key, ConditionExpiry::StartOfTurn)
+        });
+        ended || started
+    };
+
+    let encounter_players = fetch_encounter_players_from(repository, encounter_id)
+        .await?
+        .encounter_players;
+
+    for mut player in encounter_players {
+        let key = Combatant::from_player(&player).key;
+        if tick(&mut player.conditions, &key) {
+            update_combatant_conditions(
+                repository,
+                &CombatantRef::Player(player.name),
+                encounter_id,
+                &player.conditions,
+            )
+            .await?;
+        }
+    }
+
+    let playable_stat_blocks = fetch_playable_statblocks_from(repository, encounter_id)
+        .await?
+        .playable_stat_blocks;
+
+    for mut playable in playable_stat_blocks {
+        let Some(id) = playable.id else {
+            continue;
+        };
+        if tick(&mut playable.conditions, &format!("monster:{}", id)) {
+            update_combatant_conditions(
+                repository,
+                &CombatantRef::Monster(id),
+                encounter_id,
+                &playable.conditions,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn combat_session_path(app: &tauri::AppHandle, encounter_id: i64) -> Result<PathBuf, AppError> {
+    let app_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::storage(format!("Failed to get app data dir: {}", e)))?;
+
+    Ok(app_dir
+        .join("combat_sessions")
+        .join(format!("{}.json", encounter_id)))
+}
+
+pub fn read_combat_session(
+    app: &tauri::AppHandle,
+    encounter_id: i64,
+) -> Result<Option<CombatSession>, AppError> {
+    let file_path = combat_session_path(app, encounter_id)?;
+
+    if !file_path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(file_path)
+        .map_err(|e| AppError::storage(format!("Failed to read combat session: {}", e)))?;
+
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| AppError::parse(format!("Failed to parse combat session: {}", e)))
+}
+
+pub fn write_combat_session(
+    app: &tauri::AppHandle,
+    session: &CombatSession,
+) -> Result<(), AppError> {
+    let file_path = combat_session_path(app, session.encounter_id)?;
+
+    if let Some(parent) = file_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| {
+            AppError::storage(format!("Failed to create combat session dir: {}", e))
+        })?;
+    }
+
+    let content = serde_json::to_string(session).map_err(|e| AppError::parse(e.to_string()))?;
+
+    fs::write(file_path, content)
+        .map_err(|e| AppError::storage(format!("Failed to write combat session: {}", e)))
+}
+
+fn require_combat_session(
+    app: &tauri::AppHandle,
+    encounter_id: i64,
+) -> Result<CombatSession, AppError> {
+    read_combat_session(app, encounter_id)?.ok_or(AppError::not_found(format!(
+        "No combat in progress for encounter {}",
+        encounter_id
+    )))
+}
+
+fn update_combat_session(
+    app: &tauri::AppHandle,
+    encounter_id: i64,
+    update: impl FnOnce(&mut CombatSession) -> Result<(), AppError>,
+) -> Result<CombatSession, AppError> {
+    let mut session = require_combat_session(app, encounter_id)?;
+
+    update(&mut session)?;
+    write_combat_session(app, &session)?;
+
+    Ok(session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::memory_repository::InMemoryRepository,
+        types::{
+            condition_types::{ConditionExpiry, ConditionType},
+            encounter_types::{Encounter, EncounterPlayer},
+        },
+    };
+
+    fn condition(condition: ConditionType, expires: ConditionExpiry) -> ActiveCondition {
+        ActiveCondition {
+            condition,
+            exhaustion_level: None,
+            rounds_remaining: Some(1),
+            source_key: None,
+            expires,
+        }
+    }
+
+    fn player(name: &str, initiative: u16, conditions: Vec<ActiveCondition>) -> EncounterPlayer {
+        EncounterPlayer {
+            name: name.to_string(),
+            level: 3,
+            hp: 20,
+            current_hp: 20,
+            temporary_hp: 0,
+            initiative: Some(initiative),
+            conditions,
+            encounter_id: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn advancing_the_turn_expires_the_conditions_of_both_turns() {
+        let repository = InMemoryRepository::new();
+        let encounter_id = repository
+            .save_encounter(
+                Encounter {
+                    id: None,
+                    name: "Goblin Ambush".to_string(),
+                    user_id: "user".to_string(),
+                    last_modified: chrono::Utc::now(),
+                    hp_mode: None,
+                },
+                None,
+            )
+            .await
+            .unwrap()
+            .id;
+        let players: Vec<EncounterPlayer> = [
+            player(
+                "Aria",
+                15,
+                vec![condition(
+                    ConditionType::Poisoned,
+                    ConditionExpiry::EndOfTurn,
+                )],
+            ),
+            player(
+                "Bram",
+                10,
+                vec![
+                    condition(ConditionType::Prone, ConditionExpiry::StartOfTurn),
+                    condition(ConditionType::Blinded, ConditionExpiry::EndOfTurn),
+                ],
+            ),
+        ]
+        .into_iter()
+        .map(|mut player| {
+            player.encounter_id = encounter_id;
+            player
+        })
+        .collect();
+        let mut session = CombatSession::new(
+            encounter_id,
+            players.iter().map(Combatant::from_player).collect(),
+            false,
+        )
+        .unwrap();
+        repository.save_encounter_players(players).await.unwrap();
+
+        advance_turn(&repository, &mut session).await.unwrap();
+
+        assert_eq!(session.current().unwrap().name, "Bram");
+        let conditions: Vec<(String, Vec<ConditionType>)> = repository
+            .fetch_encounter_players(encounter_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|player| {
+                let conditions = player
+                    .conditions
+                    .iter()
+                    .map(|condition| condition.condition)
+                    .collect();
+                (player.name, conditions)
+            })
+            .collect();
+        // Bram's own end of turn condition is left for the end of his turn
+        assert_eq!(
+            conditions,
+            [
+                ("Aria".to_string(), vec![]),
+                ("Bram".to_string(), vec![ConditionType::Blinded])
+            ]
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{
+    database::{
+        encounter_db::update_combatant_conditions,
+        repository::{EncounterRepository, StatBlockRepository, SupabaseRepository},
+    },
+    types::{
+        combat_types::CombatantRef,
+        condition_types::{add_condition, ActiveCondition, ConditionType},
+        error_types::AppError,
+    },
+    utils::combat_utils::fetch_combatant,
+};
+
+//? Commands
+
+#[tauri::command]
+pub async fn apply_condition(
+    encounter_id: i64,
+    combatant_key: String,
+    condition: ActiveCondition,
+    access_token: String,
+) -> Result<Vec<ActiveCondition>, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
+
+    apply_condition_to(&repository, encounter_id, &combatant_key, condition).await
+}
+
+pub async fn apply_condition_to(
+    repository: &(impl EncounterRepository + StatBlockRepository),
+    encounter_id: i64,
+    combatant_key: &str,
+    condition: ActiveCondition,
+) -> Result<Vec<ActiveCondition>, AppError> {
+    condition.validate()?;
+
+    let combatant = CombatantRef::parse(combatant_key)?;
+    let mut state = fetch_combatant(repository, &combatant, encounter_id).await?;
+
+    if let Some(statblock) = &state.statblock {
+        if statblock
+            .condition_immunities
+            .contains(&condition.condition)
+        {
+            return Err(AppError::validation(
+                "condition",
+                format!(
+                    "{} is immune to the {:?} condition",
+                    state.name, condition.condition
+                ),
+            ));
+        }
+    }
+
+    add_condition(&mut state.conditions, condition);
+    update_combatant_conditions(repository, &combatant, encounter_id, &state.conditions).await?;
+
+    Ok(state.conditions)
+}
+
+#[tauri::command]
+pub async fn remove_condition(
+    encounter_id: i64,
+    combatant_key: String,
+    condition: ConditionType,
+    access_token: String,
+) -> Result<Vec<ActiveCondition>, AppError> {
+    let combatant = CombatantRef::parse(&combatant_key)?;
+    let repository = SupabaseRepository::connect(&access_token).await?;
+
+    let mut state = fetch_combatant(&repository, &combatant, encounter_id).await?;
+
+    let count = state.conditions.len();
+    state
+        .conditions
+        .retain(|active| active.condition != condition);
+
+    if state.conditions.len() == count {
+        return Err(AppError::validation(
+            "condition",
+            format!("{} is not {:?}", state.name, condition),
+        ));
+    }
+
+    update_combatant_conditions(&repository, &combatant, encounter_id, &state.conditions).await?;
+
+    Ok(state.conditions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::memory_repository::InMemoryRepository,
+        types::{
+            condition_types::ConditionExpiry,
+            encounter_types::{Encounter, PlayableStatBlock},
+        },
+        utils::import_utils::empty_statblock,
+    };
+
+    fn condition(condition: ConditionType) -> ActiveCondition {
+        ActiveCondition {
+            condition,
+            exhaustion_level: None,
+            rounds_remaining: Some(1),
+            source_key: None,
+            expires: ConditionExpiry::EndOfTurn,
+        }
+    }
+
+    // An encounter with one instance of a StatBlock immune to poison, returning its combatant key
+    async fn immune_monster(repository: &InMemoryRepository) -> (i64, String) {
+        let mut statblock = empty_statblock("Zombie");
+        statblock.condition_immunities = vec![ConditionType::Poisoned];
+        let statblock_id = repository
+            .save_statblock(&mut statblock, None)
+            .await
+            .unwrap()
+            .id;
+        let encounter_id = repository
+            .save_encounter(
+                Encounter {
+                    id: None,
+                    name: "Crypt".to_string(),
+                    user_id: "user".to_string(),
+                    last_modified: chrono::Utc::now(),
+                    hp_mode: None,
+                },
+                None,
+            )
+            .await
+            .unwrap()
+            .id;
+        repository
+            .save_playable_statblocks(vec![PlayableStatBlock {
+                id: None,
+                max_hp: Some(22),
+                current_hp: 22,
+                temporary_hp: 0,
+                initiative: None,
+                name: None,
+                conditions: vec![],
+                statblock_id,
+                encounter_id,
+            }])
+            .await
+            .unwrap();
+        let playable_id = repository
+            .fetch_playable_statblocks(encounter_id)
+            .await
+            .unwrap()[0]
+            .id
+            .unwrap();
+
+        (encounter_id, CombatantRef::Monster(playable_id).key())
+    }
+
+    #[tokio::test]
+    async fn immune_creatures_refuse_the_condition() {
+        let repository = InMemoryRepository::new();
+        let (encounter_id, key) = immune_monster(&repository).await;
+
+        let error = apply_condition_to(
+            &repository,
+            encounter_id,
+            &key,
+            condition(ConditionType::Poisoned),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(matches!(error, AppError::Validation { .. }), "{:?}", error);
+        let playable = repository
+            .fetch_playable_statblocks(encounter_id)
+            .await
+            .unwrap();
+        assert!(playable[0].conditions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn other_conditions_are_applied() {
+        let repository = InMemoryRepository::new();
+        let (encounter_id, key) = immune_monster(&repository).await;
+
+        let conditions = apply_condition_to(
+            &repository,
+            encounter_id,
+            &key,
+            condition(ConditionType::Prone),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(conditions.len(), 1);
+        let playable = repository
+            .fetch_playable_statblocks(encounter_id)
+            .await
+            .unwrap();
+        assert_eq!(playable[0].conditions[0].condition, ConditionType::Prone);
+    }
+}
@@ -0,0 +1,245 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use sha2::{Digest, Sha256};
+use tauri::Manager;
+
+use crate::types::error_types::AppError;
+
+// Everything the frontend and the session refresh keep between runs, other keys are refused
+pub const CREDENTIAL_KEYS: [&str; 4] =
+    ["access_token", "refresh_token", "user", "token_expires_at"];
+
+// Only read and written by the backend, the store commands refuse them so a page can't plant its own login
+pub const BACKEND_CREDENTIAL_KEYS: [&str; 1] = ["oauth_pending"];
+
+const CREDENTIAL_FILE: &str = "credentials.enc";
+const NONCE_LEN: usize = 12;
+
+// Every change rewrites the whole file, so reads and writes take turns
+static CREDENTIAL_LOCK: Mutex<()> = Mutex::new(());
+
+//? Store
+
+pub fn store_credential(app: &tauri::AppHandle, key: &str, value: &str) -> Result<(), AppError> {
+    validate_key(key)?;
+    let path = credential_path(app)?;
+
+    let _guard = lock()?;
+    let mut credentials = read_credentials(&path)?;
+    credentials.insert(key.to_string(), value.to_string());
+    write_credentials(&path, &credentials)
+}
+
+pub fn read_credential(app: &tauri::AppHandle, key: &str) -> Result<Option<String>, AppError> {
+    validate_key(key)?;
+    let path = credential_path(app)?;
+
+    let _guard = lock()?;
+    Ok(read_credentials(&path)?.remove(key))
+}
+
+// Returns whether the key was stored
+pub fn remove_credential(app: &tauri::AppHandle, key: &str) -> Result<bool, AppError> {
+    validate_key(key)?;
+    let path = credential_path(app)?;
+
+    let _guard = lock()?;
+    let mut credentials = read_credentials(&path)?;
+    if credentials.remove(key).is_none() {
+        return Ok(false);
+    }
+
+    write_credentials(&path, &credentials)?;
+    Ok(true)
+}
+
+// Earlier versions wrote each key to a plaintext <key>.txt, those are moved into the store and deleted
+pub fn migrate_plaintext_credentials(app: &tauri::AppHandle) -> Result<(), AppError> {
+    let path = credential_path(app)?;
+    let Some(app_dir) = path.parent() else {
+        return Ok(());
+    };
+
+    let _guard = lock()?;
+    let mut credentials = read_credentials(&path)?;
+    let mut migrated = Vec::new();
+
+    for key in CREDENTIAL_KEYS {
+        let plaintext_path = app_dir.join(format!("{}.txt", key));
+        if !plaintext_path.exists() {
+            continue;
+        }
+
+        let value = fs::read_to_string(&plaintext_path).map_err(|e| {
+            AppError::storage(format!(
+                "Failed to read {}: {}",
+                plaintext_path.display(),
+                e
+            ))
+        })?;
+        // A value already in the store is newer than the leftover file
+        credentials.entry(key.to_string()).or_insert(value);
+        migrated.push(plaintext_path);
+    }
+
+    if migrated.is_empty() {
+        return Ok(());
+    }
+
+    write_credentials(&path, &credentials)?;
+
+    for plaintext_path in migrated {
+        fs::remove_file(&plaintext_path).map_err(|e| {
+            AppError::storage(format!(
+                "Failed to delete {}: {}",
+                plaintext_path.display(),
+                e
+            ))
+        })?;
+    }
+
+    Ok(())
+}
+
+//? Helper Util
+
+// The keys the store_value, get_stored_value and remove_stored_value commands accept
+pub fn validate_frontend_key(key: &str) -> Result<(), AppError> {
+    if CREDENTIAL_KEYS.contains(&key) {
+        return Ok(());
+    }
+
+    Err(AppError::validation(
+        "key",
+        format!("{} is not a stored credential", key),
+    ))
+}
+
+fn validate_key(key: &str) -> Result<(), AppError> {
+    if CREDENTIAL_KEYS.contains(&key) || BACKEND_CREDENTIAL_KEYS.contains(&key) {
+        return Ok(());
+    }
+
+    Err(AppError::validation(
+        "key",
+        format!("{} is not a stored credential", key),
+    ))
+}
+
+fn lock() -> Result<std::sync::MutexGuard<'static, ()>, AppError> {
+    CREDENTIAL_LOCK
+        .lock()
+        .map_err(|_| AppError::storage("Credential store is unavailable"))
+}
+
+fn credential_path(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    let app_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::storage(format!("Failed to get app data dir: {}", e)))?;
+
+    Ok(app_dir.join(CREDENTIAL_FILE))
+}
+
+// The key never touches the disk, it is derived from this machine's id so the file is useless elsewhere
+fn cipher() -> Result<ChaCha20Poly1305, AppError> {
+    let machine_id = machine_uid::get()
+        .map_err(|e| AppError::storage(format!("Failed to read machine id: {}", e)))?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"encounter-builder credential store v1");
+    hasher.update(machine_id.trim().as_bytes());
+
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&hasher.finalize())))
+}
+
+fn read_credentials(path: &Path) -> Result<BTreeMap<String, String>, AppError> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let content = fs::read(path)
+        .map_err(|e| AppError::storage(format!("Failed to read credential store: {}", e)))?;
+
+    if content.len() < NONCE_LEN {
+        eprintln!("Credential store is truncated, starting over");
+        return Ok(BTreeMap::new());
+    }
+
+    let (nonce, ciphertext) = content.split_at(NONCE_LEN);
+    // Written on another machine or tampered with, the user has to log in again either way
+    let Ok(plaintext) = cipher()?.decrypt(Nonce::from_slice(nonce), ciphertext) else {
+        eprintln!("Credential store could not be decrypted, starting over");
+        return Ok(BTreeMap::new());
+    };
+
+    serde_json::from_slice(&plaintext)
+        .map_err(|e| AppError::parse(format!("Failed to parse credential store: {}", e)))
+}
+
+fn write_credentials(path: &Path, credentials: &BTreeMap<String, String>) -> Result<(), AppError> {
+    let plaintext = serde_json::to_vec(credentials).map_err(|e| AppError::parse(e.to_string()))?;
+
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher()?
+        .encrypt(&nonce, plaintext.as_slice())
+        .map_err(|_| AppError::storage("Failed to encrypt credentials"))?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::storage(format!("Failed to create app data dir: {}", e)))?;
+    }
+
+    // Written next to the store and renamed over it, so a crash never leaves half a file
+    let temporary_path = path.with_extension("enc.tmp");
+    let mut file = open_private(&temporary_path)
+        .map_err(|e| AppError::storage(format!("Failed to write credential store: {}", e)))?;
+    file.write_all(&nonce)
+        .and_then(|_| file.write_all(&ciphertext))
+        .and_then(|_| file.sync_all())
+        .map_err(|e| AppError::storage(format!("Failed to write credential store: {}", e)))?;
+
+    fs::rename(&temporary_path, path)
+        .map_err(|e| AppError::storage(format!("Failed to write credential store: {}", e)))
+}
+
+// Only the current user can read or write the file, Windows already scopes the app data dir per user
+fn open_private(path: &Path) -> std::io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options.open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_commands_refuse_backend_keys() {
+        for key in CREDENTIAL_KEYS {
+            assert!(validate_frontend_key(key).is_ok());
+        }
+
+        let error = validate_frontend_key("oauth_pending").unwrap_err();
+        assert!(matches!(error, AppError::Validation { .. }), "{:?}", error);
+        assert!(validate_key("oauth_pending").is_ok());
+        assert!(validate_key("session_cookie").is_err());
+    }
+}
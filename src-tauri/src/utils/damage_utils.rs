@@ -0,0 +1,263 @@
+use crate::{
+    database::{encounter_db::update_combatant_hp, repository::SupabaseRepository},
+    types::{
+        combat_types::CombatantRef,
+        damage_types::{DamageResult, DamageType, HealingResult},
+        error_types::AppError,
+        statblock_types::StatBlock,
+    },
+    utils::combat_utils::{fetch_combatant, CombatantState},
+};
+
+//? Commands
+
+#[tauri::command]
+pub async fn apply_damage(
+    encounter_id: i64,
+    combatant_key: String,
+    amount: u16,
+    damage_type: Option<DamageType>,
+    access_token: String,
+) -> Result<DamageResult, AppError> {
+    let combatant = CombatantRef::parse(&combatant_key)?;
+    let repository = SupabaseRepository::connect(&access_token).await?;
+
+    let hp = fetch_combatant(&repository, &combatant, encounter_id).await?;
+    let result = resolve_damage(&combatant_key, &hp, amount, damage_type);
+
+    update_combatant_hp(
+        &repository,
+        &combatant,
+        encounter_id,
+        result.current_hp,
+        result.temporary_hp,
+    )
+    .await?;
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn apply_healing(
+    encounter_id: i64,
+    combatant_key: String,
+    amount: u16,
+    access_token: String,
+) -> Result<HealingResult, AppError> {
+    let combatant = CombatantRef::parse(&combatant_key)?;
+    let repository = SupabaseRepository::connect(&access_token).await?;
+
+    let hp = fetch_combatant(&repository, &combatant, encounter_id).await?;
+    let result = resolve_healing(&combatant_key, &hp, amount);
+
+    update_combatant_hp(
+        &repository,
+        &combatant,
+        encounter_id,
+        result.current_hp,
+        hp.temporary_hp,
+    )
+    .await?;
+
+    Ok(result)
+}
+
+//? Helper Util
+
+fn resolve_damage(
+    key: &str,
+    hp: &CombatantState,
+    amount: u16,
+    damage_type: Option<DamageType>,
+) -> DamageResult {
+    let has = |list: fn(&StatBlock) -> &Vec<DamageType>| match (&hp.statblock, damage_type) {
+        (Some(statblock), Some(damage_type)) => list(statblock).contains(&damage_type),
+        _ => false,
+    };
+
+    let immune = has(|statblock| &statblock.damage_immunities);
+    let resisted = !immune && has(|statblock| &statblock.damage_resistances);
+    let vulnerable = !immune && has(|statblock| &statblock.damage_vulnerabilities);
+
+    // Resistance is applied before vulnerability, rounding down
+    let mut adjusted_amount = if immune { 0 } else { amount };
+    if resisted {
+        adjusted_amount /= 2;
+    }
+    if vulnerable {
+        adjusted_amount = adjusted_amount.saturating_mul(2);
+    }
+
+    let temporary_hp_absorbed = adjusted_amount.min(hp.temporary_hp);
+    let hp_lost = (adjusted_amount - temporary_hp_absorbed).min(hp.current_hp);
+
+    DamageResult {
+        key: key.to_string(),
+        name: hp.name.clone(),
+        damage_type,
+        raw_amount: amount,
+        immune,
+        resisted,
+        vulnerable,
+        adjusted_amount,
+        temporary_hp_absorbed,
+        hp_lost,
+        current_hp: (hp.current_hp - hp_lost).min(hp.max_hp),
+        temporary_hp: hp.temporary_hp - temporary_hp_absorbed,
+        max_hp: hp.max_hp,
+    }
+}
+
+// Healing never raises hit points past the maximum and leaves temporary hit points alone
+fn resolve_healing(key: &str, hp: &CombatantState, amount: u16) -> HealingResult {
+    let current_hp = hp.current_hp.saturating_add(amount).min(hp.max_hp);
+
+    HealingResult {
+        key: key.to_string(),
+        name: hp.name.clone(),
+        amount,
+        hp_restored: current_hp.saturating_sub(hp.current_hp),
+        current_hp,
+        max_hp: hp.max_hp,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::import_utils::empty_statblock;
+
+    fn ogre(current_hp: u16, temporary_hp: u16) -> CombatantState {
+        let mut statblock = empty_statblock("Ogre");
+        statblock.damage_immunities = vec![DamageType::Poison];
+        statblock.damage_resistances = vec![DamageType::Cold, DamageType::Fire];
+        statblock.damage_vulnerabilities = vec![DamageType::Fire, DamageType::Radiant];
+
+        CombatantState {
+            name: "Ogre".to_string(),
+            current_hp,
+            temporary_hp,
+            max_hp: 59,
+            conditions: vec![],
+            statblock: Some(statblock),
+        }
+    }
+
+    fn damage(hp: &CombatantState, amount: u16, damage_type: Option<DamageType>) -> DamageResult {
+        resolve_damage("monster:1", hp, amount, damage_type)
+    }
+
+    #[test]
+    fn adjusts_damage_for_immunity_resistance_and_vulnerability() {
+        let cases = [
+            // (damage type, immune, resisted, vulnerable, adjusted)
+            (None, false, false, false, 15),
+            (Some(DamageType::Slashing), false, false, false, 15),
+            (Some(DamageType::Poison), true, false, false, 0),
+            (Some(DamageType::Cold), false, true, false, 7),
+            (Some(DamageType::Radiant), false, false, true, 30),
+            // Halved and rounded down first, then doubled
+            (Some(DamageType::Fire), false, true, true, 14),
+        ];
+
+        for (damage_type, immune, resisted, vulnerable, adjusted_amount) in cases {
+            let result = damage(&ogre(59, 0), 15, damage_type);
+
+            assert_eq!(
+                (
+                    result.immune,
+                    result.resisted,
+                    result.vulnerable,
+                    result.adjusted_amount
+                ),
+                (immune, resisted, vulnerable, adjusted_amount),
+                "{:?}",
+                damage_type
+            );
+            assert_eq!(result.raw_amount, 15);
+        }
+    }
+
+    #[test]
+    fn immunity_wins_over_resistance_and_vulnerability() {
+        let mut hp = ogre(59, 0);
+        if let Some(statblock) = hp.statblock.as_mut() {
+            statblock.damage_immunities.push(DamageType::Fire);
+        }
+
+        let result = damage(&hp, 15, Some(DamageType::Fire));
+
+        assert!(result.immune);
+        assert!(!result.resisted && !result.vulnerable);
+        assert_eq!((result.adjusted_amount, result.current_hp), (0, 59));
+    }
+
+    #[test]
+    fn temporary_hit_points_absorb_damage_first() {
+        let result = damage(&ogre(59, 5), 12, Some(DamageType::Slashing));
+        assert_eq!(
+            (
+                result.temporary_hp_absorbed,
+                result.hp_lost,
+                result.temporary_hp,
+                result.current_hp
+            ),
+            (5, 7, 0, 52)
+        );
+
+        // Resistance applies before the temporary hit points soak it up
+        let result = damage(&ogre(59, 5), 8, Some(DamageType::Cold));
+        assert_eq!(
+            (
+                result.temporary_hp_absorbed,
+                result.hp_lost,
+                result.temporary_hp,
+                result.current_hp
+            ),
+            (4, 0, 1, 59)
+        );
+    }
+
+    #[test]
+    fn hit_points_stop_at_zero() {
+        let result = damage(&ogre(6, 2), 30, Some(DamageType::Radiant));
+
+        assert_eq!(result.adjusted_amount, 60);
+        assert_eq!((result.hp_lost, result.current_hp), (6, 0));
+        assert_eq!(result.temporary_hp, 0);
+    }
+
+    #[test]
+    fn players_take_damage_without_adjustments() {
+        let mut hp = ogre(20, 0);
+        hp.statblock = None;
+
+        let result = damage(&hp, 9, Some(DamageType::Poison));
+
+        assert!(!result.immune);
+        assert_eq!((result.adjusted_amount, result.current_hp), (9, 11));
+    }
+
+    #[test]
+    fn healing_is_capped_at_the_maximum() {
+        let cases = [
+            // (current, amount, restored, after)
+            (40, 10, 10, 50),
+            (55, 10, 4, 59),
+            (59, 10, 0, 59),
+            (0, u16::MAX, 59, 59),
+        ];
+
+        for (current_hp, amount, hp_restored, after) in cases {
+            let result = resolve_healing("monster:1", &ogre(current_hp, 3), amount);
+
+            assert_eq!(
+                (result.hp_restored, result.current_hp, result.max_hp),
+                (hp_restored, after, 59),
+                "{} healed by {}",
+                current_hp,
+                amount
+            );
+        }
+    }
+}
@@ -0,0 +1,631 @@
+use std::{fmt, str::FromStr};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::types::{
+    dice_types::{DiceEvaluation, DiceRoll, DieRoll, TermRoll},
+    encounter_types::HpMode,
+    error_types::AppError,
+    statblock_types::StatBlock,
+};
+
+const MAX_DICE: u32 = 100;
+const MAX_SIDES: u32 = 1000;
+const MAX_MODIFIER: u32 = 10_000;
+// Keeps every total of a parsed expression well inside i32
+const MAX_TERMS: usize = 20;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Keep {
+    Highest(u32),
+    Lowest(u32),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DiceTerm {
+    Dice {
+        negative: bool,
+        count: u32,
+        sides: u32,
+        keep: Option<Keep>,
+    },
+    Modifier(i32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiceExpression {
+    pub terms: Vec<DiceTerm>,
+}
+
+//? Commands
+
+#[tauri::command]
+pub fn roll_dice(expression: String, seed: Option<u32>) -> Result<DiceEvaluation, AppError> {
+    let dice: DiceExpression = expression
+        .parse()
+        .map_err(|e: String| AppError::validation("expression", e))?;
+    let mut rng = dice_rng(seed);
+
+    let mut roll = dice.roll(&mut rng);
+    roll.expression = expression.clone();
+    roll.seed = seed;
+
+    Ok(DiceEvaluation {
+        expression,
+        average: dice.average(),
+        min: dice.min(),
+        max: dice.max(),
+        roll,
+    })
+}
+
+//? Helper Util
+
+// ChaCha8 output is stable across rand versions, so a stored seed always replays the same rolls
+pub fn dice_rng(seed: Option<u32>) -> ChaCha8Rng {
+    match seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed as u64),
+        None => ChaCha8Rng::from_entropy(),
+    }
+}
+
+pub fn roll_hit_points<R: Rng>(
+    statblock: &StatBlock,
+    mode: HpMode,
+    rng: &mut R,
+) -> Result<u16, AppError> {
+    if mode == HpMode::Average {
+        return Ok(statblock.hp);
+    }
+
+    let hit_dice: DiceExpression = statblock.hit_dice.parse().map_err(|e| {
+        AppError::validation(
+            "hit_dice",
+            format!("{} has unusable hit dice: {}", statblock.name, e),
+        )
+    })?;
+
+    let hp = match mode {
+        HpMode::Rolled => hit_dice.roll(rng).total,
+        _ => hit_dice.max(),
+    };
+
+    // A creature always has at least 1 hit point, even with a large negative modifier
+    Ok(hp.clamp(1, u16::MAX as i32) as u16)
+}
+
+impl DiceTerm {
+    fn kept_count(count: u32, keep: Option<Keep>) -> u32 {
+        match keep {
+            Some(Keep::Highest(kept)) | Some(Keep::Lowest(kept)) => kept.min(count),
+            None => count,
+        }
+    }
+
+    fn sign(negative: bool) -> i32 {
+        if negative {
+            -1
+        } else {
+            1
+        }
+    }
+
+    pub fn average(&self) -> f64 {
+        match *self {
+            DiceTerm::Dice {
+                negative,
+                count,
+                sides,
+                keep,
+            } => {
+                let average = match keep {
+                    Some(Keep::Highest(kept)) if kept < count => (count - kept + 1..=count)
+                        .map(|rank| order_statistic_mean(count, sides, rank))
+                        .sum(),
+                    Some(Keep::Lowest(kept)) if kept < count => (1..=kept)
+                        .map(|rank| order_statistic_mean(count, sides, rank))
+                        .sum(),
+                    _ => count as f64 * (sides as f64 + 1.0) / 2.0,
+                };
+                Self::sign(negative) as f64 * average
+            }
+            DiceTerm::Modifier(value) => value as f64,
+        }
+    }
+
+    pub fn min(&self) -> i32 {
+        match *self {
+            DiceTerm::Dice {
+                negative,
+                count,
+                sides,
+                keep,
+            } => {
+                let kept = Self::kept_count(count, keep) as i32;
+                if negative {
+                    -kept * sides as i32
+                } else {
+                    kept
+                }
+            }
+            DiceTerm::Modifier(value) => value,
+        }
+    }
+
+    pub fn max(&self) -> i32 {
+        match *self {
+            DiceTerm::Dice {
+                negative,
+                count,
+                sides,
+                keep,
+            } => {
+                let kept = Self::kept_count(count, keep) as i32;
+                if negative {
+                    -kept
+                } else {
+                    kept * sides as i32
+                }
+            }
+            DiceTerm::Modifier(value) => value,
+        }
+    }
+
+    pub fn roll<R: Rng>(&self, rng: &mut R) -> TermRoll {
+        match *self {
+            DiceTerm::Dice {
+                negative,
+                count,
+                sides,
+                keep,
+            } => {
+                let mut dice: Vec<DieRoll> = (0..count)
+                    .map(|_| DieRoll {
+                        sides,
+                        value: rng.gen_range(1..=sides),
+                        kept: true,
+                    })
+                    .collect();
+
+                if let Some(rule) = keep {
+                    // Rank dice by value, ties broken by roll order, and drop everything outside the kept range
+                    let mut ranked: Vec<usize> = (0..dice.len()).collect();
+                    ranked.sort_by_key(|&index| dice[index].value);
+
+                    let kept = Self::kept_count(count, keep) as usize;
+                    let dropped = match rule {
+                        Keep::Highest(_) => &ranked[..ranked.len() - kept],
+                        Keep::Lowest(_) => &ranked[kept..],
+                    };
+
+                    for &index in dropped {
+                        dice[index].kept = false;
+                    }
+                }
+
+                let subtotal: i32 = dice
+                    .iter()
+                    .filter(|die| die.kept)
+                    .map(|die| die.value as i32)
+                    .sum();
+
+                TermRoll {
+                    term: self.to_string(),
+                    dice,
+                    subtotal: Self::sign(negative) * subtotal,
+                }
+            }
+            DiceTerm::Modifier(value) => TermRoll {
+                term: self.to_string(),
+                dice: Vec::new(),
+                subtotal: value,
+            },
+        }
+    }
+}
+
+impl DiceExpression {
+    pub fn average(&self) -> f64 {
+        self.terms.iter().map(DiceTerm::average).sum()
+    }
+
+    pub fn min(&self) -> i32 {
+        self.terms.iter().map(DiceTerm::min).sum()
+    }
+
+    pub fn max(&self) -> i32 {
+        self.terms.iter().map(DiceTerm::max).sum()
+    }
+
+    pub fn roll<R: Rng>(&self, rng: &mut R) -> DiceRoll {
+        let terms: Vec<TermRoll> = self.terms.iter().map(|term| term.roll(rng)).collect();
+
+        DiceRoll {
+            expression: self.to_string(),
+            seed: None,
+            total: terms.iter().map(|term| term.subtotal).sum(),
+            terms,
+        }
+    }
+}
+
+// Expected value of the rank-th lowest of `count` dice with `sides` faces
+fn order_statistic_mean(count: u32, sides: u32, rank: u32) -> f64 {
+    let needed = count - rank + 1;
+
+    (1..=sides)
+        .map(|value| {
+            // Probability that at least `needed` dice show `value` or higher
+            let p = (sides - value + 1) as f64 / sides as f64;
+            (needed..=count)
+                .map(|hits| {
+                    binomial(count, hits)
+                        * p.powi(hits as i32)
+                        * (1.0 - p).powi((count - hits) as i32)
+                })
+                .sum::<f64>()
+        })
+        .sum()
+}
+
+fn binomial(n: u32, k: u32) -> f64 {
+    let k = k.min(n - k);
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+//? Parsing
+
+struct DiceParser<'a> {
+    source: &'a str,
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl<'a> DiceParser<'a> {
+    fn new(source: &'a str) -> Self {
+        DiceParser {
+            source,
+            chars: source.to_lowercase().chars().collect(),
+            position: 0,
+        }
+    }
+
+    fn error(&self, reason: &str) -> String {
+        format!("Invalid dice expression \"{}\": {}", self.source, reason)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn number(&mut self) -> Option<Result<u32, String>> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+
+        if start == self.position {
+            return None;
+        }
+
+        let digits: String = self.chars[start..self.position].iter().collect();
+        Some(
+            digits
+                .parse::<u32>()
+                .map_err(|_| self.error(&format!("number {} is too large", digits))),
+        )
+    }
+
+    fn word(&mut self) -> String {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn parse(mut self) -> Result<DiceExpression, String> {
+        let mut terms = Vec::new();
+        let mut negative = false;
+
+        self.skip_whitespace();
+        if let Some(sign @ ('+' | '-')) = self.peek() {
+            negative = sign == '-';
+            self.position += 1;
+        }
+
+        loop {
+            if terms.len() == MAX_TERMS {
+                return Err(self.error(&format!("at most {} terms are allowed", MAX_TERMS)));
+            }
+
+            self.skip_whitespace();
+            terms.push(self.term(negative)?);
+            self.skip_whitespace();
+
+            match self.peek() {
+                None => break,
+                Some('+') => negative = false,
+                Some('-') => negative = true,
+                Some(other) => return Err(self.error(&format!("unexpected '{}'", other))),
+            }
+            self.position += 1;
+        }
+
+        Ok(DiceExpression { terms })
+    }
+
+    fn term(&mut self, negative: bool) -> Result<DiceTerm, String> {
+        let count = self.number().transpose()?;
+
+        if self.peek() != Some('d') {
+            return match count {
+                Some(value) if value > MAX_MODIFIER => {
+                    Err(self.error(&format!("modifier must be at most {}", MAX_MODIFIER)))
+                }
+                Some(value) => {
+                    let value = value as i32;
+                    Ok(DiceTerm::Modifier(if negative { -value } else { value }))
+                }
+                None => Err(self.error("expected a number or dice")),
+            };
+        }
+        self.position += 1;
+
+        let count = count.unwrap_or(1);
+        let sides = self
+            .number()
+            .transpose()?
+            .ok_or_else(|| self.error("expected die size after 'd'"))?;
+
+        if count == 0 || count > MAX_DICE {
+            return Err(self.error(&format!("dice count must be between 1 and {}", MAX_DICE)));
+        }
+        if sides == 0 || sides > MAX_SIDES {
+            return Err(self.error(&format!("die size must be between 1 and {}", MAX_SIDES)));
+        }
+
+        let mut term = DiceTerm::Dice {
+            negative,
+            count,
+            sides,
+            keep: None,
+        };
+
+        let checkpoint = self.position;
+        self.skip_whitespace();
+
+        match self.word().as_str() {
+            "" => self.position = checkpoint,
+            rule @ ("k" | "kh" | "kl") => {
+                let kept = self
+                    .number()
+                    .transpose()?
+                    .ok_or_else(|| self.error(&format!("expected a count after '{}'", rule)))?;
+
+                if kept == 0 || kept > count {
+                    return Err(self.error(&format!("cannot keep {} of {} dice", kept, count)));
+                }
+
+                term = DiceTerm::Dice {
+                    negative,
+                    count,
+                    sides,
+                    keep: Some(if rule == "kl" {
+                        Keep::Lowest(kept)
+                    } else {
+                        Keep::Highest(kept)
+                    }),
+                };
+            }
+            mode @ ("adv" | "advantage" | "dis" | "disadvantage") => {
+                if count != 1 {
+                    return Err(self.error("advantage and disadvantage apply to a single die"));
+                }
+
+                term = DiceTerm::Dice {
+                    negative,
+                    count: 2,
+                    sides,
+                    keep: Some(if mode.starts_with("adv") {
+                        Keep::Highest(1)
+                    } else {
+                        Keep::Lowest(1)
+                    }),
+                };
+            }
+            other => return Err(self.error(&format!("unknown modifier '{}'", other))),
+        }
+
+        Ok(term)
+    }
+}
+
+impl FromStr for DiceExpression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DiceParser::new(s).parse()
+    }
+}
+
+impl fmt::Display for DiceTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            DiceTerm::Dice {
+                count, sides, keep, ..
+            } => {
+                write!(f, "{}d{}", count, sides)?;
+                match keep {
+                    Some(Keep::Highest(kept)) => write!(f, "kh{}", kept),
+                    Some(Keep::Lowest(kept)) => write!(f, "kl{}", kept),
+                    None => Ok(()),
+                }
+            }
+            DiceTerm::Modifier(value) => write!(f, "{}", value.abs()),
+        }
+    }
+}
+
+impl fmt::Display for DiceExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, term) in self.terms.iter().enumerate() {
+            let negative = match *term {
+                DiceTerm::Dice { negative, .. } => negative,
+                DiceTerm::Modifier(value) => value < 0,
+            };
+
+            match (index, negative) {
+                (0, true) => write!(f, "-")?,
+                (0, false) => {}
+                (_, true) => write!(f, " - ")?,
+                (_, false) => write!(f, " + ")?,
+            }
+            write!(f, "{}", term)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(expression: &str) -> DiceExpression {
+        expression.parse().unwrap()
+    }
+
+    fn dice(count: u32, sides: u32, keep: Option<Keep>) -> DiceTerm {
+        DiceTerm::Dice {
+            negative: false,
+            count,
+            sides,
+            keep,
+        }
+    }
+
+    #[test]
+    fn parses_dice_with_modifier() {
+        let expression = parse("2d6+4");
+
+        assert_eq!(
+            expression.terms,
+            vec![dice(2, 6, None), DiceTerm::Modifier(4)]
+        );
+        assert_eq!(expression.to_string(), "2d6 + 4");
+    }
+
+    #[test]
+    fn parses_whitespace_between_terms() {
+        let expression = parse("18d10 + 36");
+
+        assert_eq!(
+            expression.terms,
+            vec![dice(18, 10, None), DiceTerm::Modifier(36)]
+        );
+    }
+
+    #[test]
+    fn parses_keep_highest() {
+        let expression = parse("4d6kh3");
+
+        assert_eq!(expression.terms, vec![dice(4, 6, Some(Keep::Highest(3)))]);
+        assert_eq!(expression.to_string(), "4d6kh3");
+    }
+
+    #[test]
+    fn parses_advantage_as_keep_highest_of_two() {
+        let expression = parse("1d20 adv");
+
+        assert_eq!(expression.terms, vec![dice(2, 20, Some(Keep::Highest(1)))]);
+        assert_eq!(
+            parse("d20 dis").terms,
+            vec![dice(2, 20, Some(Keep::Lowest(1)))]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for expression in ["", "2d", "d0", "101d6", "3d6kh4", "2d20 adv", "1d6 x"] {
+            assert!(
+                expression.parse::<DiceExpression>().is_err(),
+                "{}",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_totals_that_could_overflow() {
+        assert!("2147483647 + 1".parse::<DiceExpression>().is_err());
+        assert!("10001".parse::<DiceExpression>().is_err());
+        assert!(["1"; MAX_TERMS + 1]
+            .join(" + ")
+            .parse::<DiceExpression>()
+            .is_err());
+
+        let largest = vec![format!("{}d{}", MAX_DICE, MAX_SIDES); MAX_TERMS].join(" + ");
+        assert_eq!(parse(&largest).max(), 2_000_000);
+        assert!(matches!(
+            roll_dice("2147483647 + 1".to_string(), None),
+            Err(AppError::Validation { .. })
+        ));
+    }
+
+    #[test]
+    fn average_min_and_max() {
+        let expression = parse("2d6+4");
+        assert_eq!(expression.average(), 11.0);
+        assert_eq!(expression.min(), 6);
+        assert_eq!(expression.max(), 16);
+
+        let expression = parse("18d10 + 36");
+        assert_eq!(expression.average(), 135.0);
+        assert_eq!(expression.min(), 54);
+        assert_eq!(expression.max(), 216);
+
+        let expression = parse("1d8 - 2");
+        assert_eq!(expression.average(), 2.5);
+        assert_eq!(expression.min(), -1);
+        assert_eq!(expression.max(), 6);
+    }
+
+    #[test]
+    fn average_of_kept_dice() {
+        let expression = parse("4d6kh3");
+        assert!((expression.average() - 12.2446).abs() < 0.0001);
+        assert_eq!(expression.min(), 3);
+        assert_eq!(expression.max(), 18);
+
+        assert!((parse("1d20 adv").average() - 13.825).abs() < 0.0001);
+        assert!((parse("1d20 dis").average() - 7.175).abs() < 0.0001);
+    }
+
+    #[test]
+    fn same_seed_replays_the_same_roll() {
+        let first = roll_dice("4d6kh3 + 2".to_string(), Some(42)).unwrap();
+        let second = roll_dice("4d6kh3 + 2".to_string(), Some(42)).unwrap();
+
+        assert_eq!(first.roll, second.roll);
+        assert_eq!(first.roll.seed, Some(42));
+        assert!((first.min..=first.max).contains(&first.roll.total));
+
+        let dice = &first.roll.terms[0].dice;
+        assert_eq!(dice.len(), 4);
+        assert_eq!(dice.iter().filter(|die| !die.kept).count(), 1);
+        assert_eq!(
+            first.roll.terms[0].subtotal,
+            dice.iter()
+                .filter(|die| die.kept)
+                .map(|die| die.value as i32)
+                .sum::<i32>()
+        );
+    }
+}
@@ -0,0 +1,514 @@
+use std::collections::HashMap;
+
+use crate::{
+    database::{
+        encounter_db::{fetch_encounter_players_from, fetch_playable_statblocks_from},
+        repository::{StatBlockRepository, SupabaseRepository},
+        statblock_db::fetch_statblocks_by_ids,
+    },
+    types::{
+        difficulty_types::{
+            DifficultyModel, DifficultyRating, DifficultyThreshold, EncounterDifficulty, MonsterXp,
+        },
+        encounter_types::{EncounterPlayer, PlayableStatBlock},
+        error_types::AppError,
+        statblock_types::StatBlock,
+    },
+};
+
+// Easy, Medium, Hard, Deadly per character, indexed by level - 1
+const DMG_2014_THRESHOLDS: [[u32; 4]; 20] = [
+    [25, 50, 75, 100],
+    [50, 100, 150, 200],
+    [75, 150, 225, 400],
+    [125, 250, 375, 500],
+    [250, 500, 750, 1100],
+    [300, 600, 900, 1400],
+    [350, 750, 1100, 1700],
+    [450, 900, 1400, 2100],
+    [550, 1100, 1600, 2400],
+    [600, 1200, 1900, 2800],
+    [800, 1600, 2400, 3600],
+    [1000, 2000, 3000, 4500],
+    [1100, 2200, 3400, 5100],
+    [1250, 2500, 3800, 5700],
+    [1400, 2800, 4300, 6400],
+    [1600, 3200, 4800, 7200],
+    [2000, 3900, 5900, 8800],
+    [2100, 4200, 6300, 9500],
+    [2400, 4900, 7300, 10900],
+    [2800, 5700, 8500, 12700],
+];
+
+// Low, Moderate, High per character, indexed by level - 1
+const DMG_2024_BUDGETS: [[u32; 3]; 20] = [
+    [50, 75, 100],
+    [100, 150, 200],
+    [150, 225, 400],
+    [250, 375, 500],
+    [500, 750, 1100],
+    [600, 1000, 1400],
+    [750, 1300, 1700],
+    [1000, 1700, 2100],
+    [1300, 2000, 2600],
+    [1600, 2300, 3100],
+    [1900, 2900, 4100],
+    [2200, 3700, 4700],
+    [2600, 4200, 5400],
+    [2900, 4900, 6200],
+    [3300, 5400, 7800],
+    [3800, 6100, 9800],
+    [4500, 7200, 11700],
+    [5000, 8700, 14200],
+    [5500, 10700, 17200],
+    [6400, 13200, 22000],
+];
+
+const DMG_2014_MULTIPLIERS: [f32; 8] = [0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 4.0, 5.0];
+
+//? Commands
+
+#[tauri::command]
+pub async fn calculate_encounter_difficulty(
+    encounter_id: i64,
+    model: DifficultyModel,
+    access_token: String,
+) -> Result<EncounterDifficulty, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
+
+    let encounter_players = fetch_encounter_players_from(&repository, encounter_id)
+        .await?
+        .encounter_players;
+
+    let playable_stat_blocks = fetch_playable_statblocks_from(&repository, encounter_id)
+        .await?
+        .playable_stat_blocks;
+
+    difficulty_from(&repository, playable_stat_blocks, encounter_players, model).await
+}
+
+#[tauri::command]
+pub async fn calculate_difficulty(
+    playable_stat_blocks: Vec<PlayableStatBlock>,
+    encounter_players: Vec<EncounterPlayer>,
+    model: DifficultyModel,
+    access_token: String,
+) -> Result<EncounterDifficulty, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
+
+    difficulty_from(&repository, playable_stat_blocks, encounter_players, model).await
+}
+
+pub async fn difficulty_from(
+    repository: &impl StatBlockRepository,
+    playable_stat_blocks: Vec<PlayableStatBlock>,
+    encounter_players: Vec<EncounterPlayer>,
+    model: DifficultyModel,
+) -> Result<EncounterDifficulty, AppError> {
+    let mut statblock_ids: Vec<i64> = playable_stat_blocks
+        .iter()
+        .map(|playable| playable.statblock_id)
+        .collect();
+    statblock_ids.sort_unstable();
+    statblock_ids.dedup();
+
+    let statblocks = fetch_statblocks_by_ids(repository, &statblock_ids).await?;
+
+    difficulty_for(
+        &playable_stat_blocks,
+        &statblocks,
+        &encounter_players,
+        model,
+    )
+}
+
+//? Helper Util
+
+pub fn difficulty_for(
+    playable_stat_blocks: &[PlayableStatBlock],
+    statblocks: &[StatBlock],
+    encounter_players: &[EncounterPlayer],
+    model: DifficultyModel,
+) -> Result<EncounterDifficulty, AppError> {
+    if encounter_players.is_empty() {
+        return Err(AppError::validation(
+            "encounter_players",
+            "Difficulty calculation failed: Encounter has no players",
+        ));
+    }
+
+    let statblocks_by_id: HashMap<i64, &StatBlock> = statblocks
+        .iter()
+        .filter_map(|statblock| statblock.id.map(|id| (id, statblock)))
+        .collect();
+
+    let mut monsters = Vec::new();
+
+    for playable in playable_stat_blocks {
+        let statblock = statblocks_by_id
+            .get(&playable.statblock_id)
+            .ok_or(AppError::not_found(format!(
+                "Difficulty calculation failed: StatBlock {} not found",
+                playable.statblock_id
+            )))?;
+
+        monsters.push(MonsterXp {
+            playable_statblock_id: playable.id,
+            statblock_id: playable.statblock_id,
+            name: playable
+                .name
+                .clone()
+                .unwrap_or_else(|| statblock.name.clone()),
+            cr: statblock.cr,
+            xp: statblock.cr.xp(),
+        });
+    }
+
+    let mut party_thresholds = [0u32; 4];
+
+    for player in encounter_players {
+        if player.level == 0 || player.level > 20 {
+            return Err(AppError::validation(
+                "level",
+                format!(
+                    "Difficulty calculation failed: {} has invalid level {}",
+                    player.name, player.level
+                ),
+            ));
+        }
+
+        let level_index = (player.level - 1) as usize;
+
+        match model {
+            DifficultyModel::Dmg2014 => {
+                for (total, xp) in party_thresholds
+                    .iter_mut()
+                    .zip(DMG_2014_THRESHOLDS[level_index])
+                {
+                    *total += xp;
+                }
+            }
+            DifficultyModel::Dmg2024 => {
+                for (total, xp) in party_thresholds
+                    .iter_mut()
+                    .zip(DMG_2024_BUDGETS[level_index])
+                {
+                    *total += xp;
+                }
+            }
+        }
+    }
+
+    let total_xp: u32 = monsters.iter().map(|monster| monster.xp).sum();
+
+    let (multiplier, thresholds) = match model {
+        DifficultyModel::Dmg2014 => (
+            encounter_multiplier(monsters.len(), encounter_players.len()),
+            vec![
+                DifficultyThreshold {
+                    rating: DifficultyRating::Easy,
+                    xp: party_thresholds[0],
+                },
+                DifficultyThreshold {
+                    rating: DifficultyRating::Medium,
+                    xp: party_thresholds[1],
+                },
+                DifficultyThreshold {
+                    rating: DifficultyRating::Hard,
+                    xp: party_thresholds[2],
+                },
+                DifficultyThreshold {
+                    rating: DifficultyRating::Deadly,
+                    xp: party_thresholds[3],
+                },
+            ],
+        ),
+        DifficultyModel::Dmg2024 => (
+            1.0,
+            vec![
+                DifficultyThreshold {
+                    rating: DifficultyRating::Low,
+                    xp: party_thresholds[0],
+                },
+                DifficultyThreshold {
+                    rating: DifficultyRating::Moderate,
+                    xp: party_thresholds[1],
+                },
+                DifficultyThreshold {
+                    rating: DifficultyRating::High,
+                    xp: party_thresholds[2],
+                },
+            ],
+        ),
+    };
+
+    let adjusted_xp = (total_xp as f32 * multiplier).round() as u32;
+
+    let rating = match model {
+        // Highest threshold the adjusted XP reaches
+        DifficultyModel::Dmg2014 => thresholds
+            .iter()
+            .rev()
+            .find(|threshold| adjusted_xp >= threshold.xp)
+            .map(|threshold| threshold.rating)
+            .unwrap_or(DifficultyRating::Trivial),
+        // Lowest budget the XP fits within
+        DifficultyModel::Dmg2024 => thresholds
+            .iter()
+            .find(|threshold| adjusted_xp <= threshold.xp)
+            .map(|threshold| threshold.rating)
+            .unwrap_or(DifficultyRating::Extreme),
+    };
+
+    Ok(EncounterDifficulty {
+        model,
+        monsters,
+        total_xp,
+        multiplier,
+        adjusted_xp,
+        thresholds,
+        rating,
+    })
+}
+
+fn encounter_multiplier(monster_count: usize, party_size: usize) -> f32 {
+    if monster_count == 0 {
+        return 1.0;
+    }
+
+    let index: usize = match monster_count {
+        1 => 1,
+        2 => 2,
+        3..=6 => 3,
+        7..=10 => 4,
+        11..=14 => 5,
+        _ => 6,
+    };
+
+    // Small parties step up the multiplier table, large parties step down
+    let index = match party_size {
+        0..=2 => index + 1,
+        3..=5 => index,
+        _ => index - 1,
+    };
+
+    DMG_2014_MULTIPLIERS[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::import_utils::empty_statblock;
+
+    fn player(level: u8) -> EncounterPlayer {
+        EncounterPlayer {
+            name: format!("Level {}", level),
+            level,
+            hp: 10,
+            current_hp: 10,
+            temporary_hp: 0,
+            initiative: None,
+            conditions: vec![],
+            encounter_id: 1,
+        }
+    }
+
+    // One StatBlock and one instance of it per challenge rating
+    fn difficulty(
+        model: DifficultyModel,
+        levels: &[u8],
+        challenge_ratings: &[&str],
+    ) -> Result<EncounterDifficulty, AppError> {
+        let statblocks: Vec<StatBlock> = challenge_ratings
+            .iter()
+            .zip(1..)
+            .map(|(cr, id)| {
+                let mut statblock = empty_statblock(&format!("CR {}", cr));
+                statblock.id = Some(id);
+                statblock.cr = cr.parse().unwrap();
+                statblock
+            })
+            .collect();
+        let playable_stat_blocks: Vec<PlayableStatBlock> = statblocks
+            .iter()
+            .map(|statblock| PlayableStatBlock {
+                id: None,
+                max_hp: None,
+                current_hp: 1,
+                temporary_hp: 0,
+                initiative: None,
+                name: None,
+                conditions: vec![],
+                statblock_id: statblock.id.unwrap(),
+                encounter_id: 1,
+            })
+            .collect();
+        let encounter_players: Vec<EncounterPlayer> =
+            levels.iter().map(|level| player(*level)).collect();
+
+        difficulty_for(
+            &playable_stat_blocks,
+            &statblocks,
+            &encounter_players,
+            model,
+        )
+    }
+
+    fn threshold_xp(difficulty: &EncounterDifficulty) -> Vec<u32> {
+        difficulty
+            .thresholds
+            .iter()
+            .map(|threshold| threshold.xp)
+            .collect()
+    }
+
+    #[test]
+    fn thresholds_add_up_over_the_party() {
+        let dmg_2014 = difficulty(DifficultyModel::Dmg2014, &[1, 3, 20], &["1"]).unwrap();
+        let dmg_2024 = difficulty(DifficultyModel::Dmg2024, &[1, 3, 20], &["1"]).unwrap();
+
+        assert_eq!(threshold_xp(&dmg_2014), [2900, 5900, 8800, 13200]);
+        assert_eq!(threshold_xp(&dmg_2024), [6600, 13500, 22500]);
+    }
+
+    #[test]
+    fn encounter_multiplier_follows_monster_count_and_party_size() {
+        let cases = [
+            // (monsters, party size, multiplier)
+            (0, 4, 1.0),
+            (1, 4, 1.0),
+            (2, 4, 1.5),
+            (3, 4, 2.0),
+            (6, 4, 2.0),
+            (7, 4, 2.5),
+            (10, 4, 2.5),
+            (11, 4, 3.0),
+            (14, 4, 3.0),
+            (15, 4, 4.0),
+            (1, 2, 1.5),
+            (15, 1, 5.0),
+            (1, 6, 0.5),
+            (2, 6, 1.0),
+            (15, 6, 3.0),
+        ];
+
+        for (monster_count, party_size, multiplier) in cases {
+            assert_eq!(
+                encounter_multiplier(monster_count, party_size),
+                multiplier,
+                "{} monsters against {} players",
+                monster_count,
+                party_size
+            );
+        }
+    }
+
+    #[test]
+    fn dmg_2014_rates_the_adjusted_xp_against_the_highest_threshold_reached() {
+        // Four level 1 characters: Easy 100, Medium 200, Hard 300, Deadly 400
+        let cases: [(&[&str], u32, u32, DifficultyRating); 7] = [
+            (&["0"], 10, 10, DifficultyRating::Trivial),
+            (&["1/4"], 50, 50, DifficultyRating::Trivial),
+            (&["1/2"], 100, 100, DifficultyRating::Easy),
+            (&["1/4", "1/4"], 100, 150, DifficultyRating::Easy),
+            (&["1"], 200, 200, DifficultyRating::Medium),
+            (&["1/2", "1/2"], 200, 300, DifficultyRating::Hard),
+            (&["2"], 450, 450, DifficultyRating::Deadly),
+        ];
+
+        for (challenge_ratings, total_xp, adjusted_xp, rating) in cases {
+            let difficulty =
+                difficulty(DifficultyModel::Dmg2014, &[1, 1, 1, 1], challenge_ratings).unwrap();
+
+            assert_eq!(
+                (
+                    difficulty.total_xp,
+                    difficulty.adjusted_xp,
+                    difficulty.rating
+                ),
+                (total_xp, adjusted_xp, rating),
+                "{:?}",
+                challenge_ratings
+            );
+        }
+    }
+
+    #[test]
+    fn dmg_2024_rates_the_xp_against_the_lowest_budget_it_fits() {
+        // Four level 1 characters: Low 200, Moderate 300, High 400
+        let cases: [(&[&str], u32, DifficultyRating); 6] = [
+            (&["1/2"], 100, DifficultyRating::Low),
+            (&["1"], 200, DifficultyRating::Low),
+            (&["1", "1/8"], 225, DifficultyRating::Moderate),
+            (&["1", "1/2"], 300, DifficultyRating::Moderate),
+            (&["1", "1/2", "1/2"], 400, DifficultyRating::High),
+            (&["2"], 450, DifficultyRating::Extreme),
+        ];
+
+        for (challenge_ratings, xp, rating) in cases {
+            let difficulty =
+                difficulty(DifficultyModel::Dmg2024, &[1, 1, 1, 1], challenge_ratings).unwrap();
+
+            // No multiplier, so several monsters cost exactly their XP
+            assert_eq!(difficulty.multiplier, 1.0);
+            assert_eq!(
+                (
+                    difficulty.total_xp,
+                    difficulty.adjusted_xp,
+                    difficulty.rating
+                ),
+                (xp, xp, rating),
+                "{:?}",
+                challenge_ratings
+            );
+        }
+    }
+
+    #[test]
+    fn monsters_list_the_xp_of_their_challenge_rating() {
+        let difficulty = difficulty(DifficultyModel::Dmg2014, &[5], &["1/8", "5", "30"]).unwrap();
+
+        let monsters: Vec<(String, u32)> = difficulty
+            .monsters
+            .iter()
+            .map(|monster| (monster.name.clone(), monster.xp))
+            .collect();
+        assert_eq!(
+            monsters,
+            [
+                ("CR 1/8".to_string(), 25),
+                ("CR 5".to_string(), 1800),
+                ("CR 30".to_string(), 155000)
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_parties_are_refused() {
+        for levels in [&[][..], &[0][..], &[4, 21][..]] {
+            let error = difficulty(DifficultyModel::Dmg2014, levels, &["1"]).unwrap_err();
+
+            assert!(matches!(error, AppError::Validation { .. }), "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn instance_of_a_missing_statblock_is_not_found() {
+        let playable = PlayableStatBlock {
+            id: Some(4),
+            max_hp: None,
+            current_hp: 1,
+            temporary_hp: 0,
+            initiative: None,
+            name: None,
+            conditions: vec![],
+            statblock_id: 9,
+            encounter_id: 1,
+        };
+
+        let error =
+            difficulty_for(&[playable], &[], &[player(1)], DifficultyModel::Dmg2024).unwrap_err();
+
+        assert!(matches!(error, AppError::NotFound { .. }), "{:?}", error);
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{
+    database::{
+        encounter_db::{fetch_encounter_by_id, fetch_playable_statblocks_from},
+        repository::{EncounterRepository, StatBlockRepository},
+    },
+    types::{
+        condition_types::ConditionType,
+        damage_types::DamageType,
+        encounter_types::Encounter,
+        error_types::AppError,
+        export_types::EncounterCreature,
+        proficiency_types::ProficiencyLevel,
+        spell_types::{SpellcastingAbility, Spells},
+        statblock_types::{Ability, Alignment, Score, StatBlock},
+    },
+    utils::{
+        combat_utils::fetch_encounter_statblocks,
+        import_utils::{leading_number, number_before},
+    },
+};
+
+//? Encounter
+
+// Each distinct StatBlock of the Encounter once, in the order they were first added
+pub async fn fetch_encounter_creatures(
+    repository: &(impl EncounterRepository + StatBlockRepository),
+    encounter_id: i64,
+) -> Result<(Encounter, Vec<EncounterCreature>), AppError> {
+    let encounter = fetch_encounter_by_id(repository, encounter_id)
+        .await?
+        .ok_or(AppError::not_found(format!(
+            "Export failed: Encounter {} not found",
+            encounter_id
+        )))?;
+
+    let playable_stat_blocks = fetch_playable_statblocks_from(repository, encounter_id)
+        .await?
+        .playable_stat_blocks;
+    let mut statblocks = fetch_encounter_statblocks(repository, &playable_stat_blocks).await?;
+
+    let mut creatures: Vec<EncounterCreature> = Vec::new();
+
+    for playable in playable_stat_blocks {
+        if let Some(creature) = creatures
+            .iter_mut()
+            .find(|creature| creature.stat_block.id == Some(playable.statblock_id))
+        {
+            creature.instances.push(playable);
+            continue;
+        }
+
+        let stat_block = statblocks
+            .remove(&playable.statblock_id)
+            .ok_or(AppError::not_found(format!(
+                "Export failed: StatBlock {} not found",
+                playable.statblock_id
+            )))?;
+
+        creatures.push(EncounterCreature {
+            stat_block,
+            instances: vec![playable],
+        });
+    }
+
+    Ok((encounter, creatures))
+}
+
+//? Text
+
+pub fn signed(value: impl Into<i16>) -> String {
+    let value = value.into();
+    if value < 0 {
+        format!("-{}", value.unsigned_abs())
+    } else {
+        format!("+{}", value)
+    }
+}
+
+pub fn size_name(stat_block: &StatBlock) -> String {
+    format!("{:?}", stat_block.size)
+}
+
+pub fn alignment_text(alignment: Alignment) -> &'static str {
+    match alignment {
+        Alignment::Unaligned => "unaligned",
+        Alignment::LawfulGood => "lawful good",
+        Alignment::NeutralGood => "neutral good",
+        Alignment::ChaoticGood => "chaotic good",
+        Alignment::LawfulNeutral => "lawful neutral",
+        Alignment::TrueNeutral => "neutral",
+        Alignment::ChaoticNeutral => "chaotic neutral",
+        Alignment::LawfulEvil => "lawful evil",
+        Alignment::NeutralEvil => "neutral evil",
+        Alignment::ChaoticEvil => "chaotic evil",
+    }
+}
+
+// e.g. "Small humanoid (goblinoid), neutral evil"
+pub fn type_line(stat_block: &StatBlock) -> String {
+    let creature_type = match &stat_block.subtype {
+        Some(subtype) if !subtype.is_empty() => format!("{} ({})", stat_block.type_, subtype),
+        _ => stat_block.type_.clone(),
+    };
+
+    format!(
+        "{} {}, {}",
+        size_name(stat_block),
+        creature_type,
+        alignment_text(stat_block.alignment)
+    )
+}
+
+pub fn score_abbreviation(score: Score) -> &'static str {
+    match score {
+        Score::Strength => "Str",
+        Score::Dexterity => "Dex",
+        Score::Constitution => "Con",
+        Score::Intelligence => "Int",
+        Score::Wisdom => "Wis",
+        Score::Charisma => "Cha",
+    }
+}
+
+pub fn ability_name(ability: Ability) -> &'static str {
+    match ability {
+        Ability::Acrobatics => "Acrobatics",
+        Ability::AnimalHandling => "Animal Handling",
+        Ability::Arcana => "Arcana",
+        Ability::Athletics => "Athletics",
+        Ability::Deception => "Deception",
+        Ability::History => "History",
+        Ability::Insight => "Insight",
+        Ability::Intimidation => "Intimidation",
+        Ability::Investigation => "Investigation",
+        Ability::Medicine => "Medicine",
+        Ability::Nature => "Nature",
+        Ability::Perception => "Perception",
+        Ability::Performance => "Performance",
+        Ability::Persuasion => "Persuasion",
+        Ability::Religion => "Religion",
+        Ability::SleightOfHand => "Sleight of Hand",
+        Ability::Stealth => "Stealth",
+        Ability::Survival => "Survival",
+    }
+}
+
+pub fn spellcasting_ability_name(ability: SpellcastingAbility) -> &'static str {
+    match ability {
+        SpellcastingAbility::Intelligence => "Intelligence",
+        SpellcastingAbility::Wisdom => "Wisdom",
+        SpellcastingAbility::Charisma => "Charisma",
+    }
+}
+
+pub fn hit_points_text(stat_block: &StatBlock) -> String {
+    if stat_block.hit_dice.is_empty() {
+        stat_block.hp.to_string()
+    } else {
+        format!("{} ({})", stat_block.hp, stat_block.hit_dice)
+    }
+}
+
+// Proficient saves only, e.g. "Dex +4, Wis +2"
+pub fn saves_text(stat_block: &StatBlock) -> Option<String> {
+    let saves: Vec<String> = stat_block
+        .derived_stats()
+        .saves
+        .iter()
+        .filter(|save| save.level != ProficiencyLevel::None)
+        .map(|save| format!("{} {}", score_abbreviation(save.score), signed(save.bonus)))
+        .collect();
+
+    (!saves.is_empty()).then(|| saves.join(", "))
+}
+
+pub fn skills_text(stat_block: &StatBlock) -> Option<String> {
+    let skills: Vec<String> = stat_block
+        .derived_stats()
+        .skills
+        .iter()
+        .filter(|skill| skill.level != ProficiencyLevel::None)
+        .map(|skill| format!("{} {}", ability_name(skill.ability), signed(skill.bonus)))
+        .collect();
+
+    (!skills.is_empty()).then(|| skills.join(", "))
+}
+
+pub fn damage_text(damage_types: &[DamageType]) -> Option<String> {
+    let names: Vec<String> = damage_types
+        .iter()
+        .map(|damage_type| format!("{:?}", damage_type).to_lowercase())
+        .collect();
+
+    (!names.is_empty()).then(|| names.join(", "))
+}
+
+pub fn condition_text(condition_types: &[ConditionType]) -> Option<String> {
+    let names: Vec<String> = condition_types
+        .iter()
+        .map(|condition_type| format!("{:?}", condition_type).to_lowercase())
+        .collect();
+
+    (!names.is_empty()).then(|| names.join(", "))
+}
+
+// Senses with the passive Perception the React view appends
+pub fn senses_text(stat_block: &StatBlock) -> String {
+    let passive = format!("passive Perception {}", stat_block.passive_perception());
+
+    match &stat_block.senses {
+        Some(senses) if senses.to_lowercase().contains("passive perception") => senses.clone(),
+        Some(senses) if !senses.is_empty() => format!("{}, {}", senses, passive),
+        _ => passive,
+    }
+}
+
+pub fn languages_text(stat_block: &StatBlock) -> String {
+    match &stat_block.languages {
+        Some(languages) if !languages.is_empty() => languages.clone(),
+        _ => "—".to_string(),
+    }
+}
+
+// e.g. "1/4 (50 XP)"
+pub fn challenge_text(stat_block: &StatBlock) -> String {
+    format!(
+        "{} ({} XP)",
+        stat_block.cr,
+        group_thousands(stat_block.cr.xp())
+    )
+}
+
+pub fn spellcasting_header(stat_block: &StatBlock, spells: &Spells) -> String {
+    format!(
+        "The {} casts one of the following spells, using {} as the spellcasting ability (spell save DC {}, {} to hit with spell attacks):",
+        stat_block.name,
+        spellcasting_ability_name(spells.ability),
+        spells.save_dc,
+        signed(spells.attack_bonus)
+    )
+}
+
+// At will and cantrips first, then uses per day from most to fewest, then spell levels
+pub fn sorted_spell_lists(spells: &Spells) -> Vec<(&String, &String)> {
+    let mut lists: Vec<(&String, &String)> = spells.spells.iter().collect();
+    lists.sort_by(|(a, _), (b, _)| {
+        spell_list_rank(a)
+            .cmp(&spell_list_rank(b))
+            .then_with(|| a.cmp(b))
+    });
+    lists
+}
+
+fn spell_list_rank(label: &str) -> (u8, i32) {
+    let label = label.to_lowercase();
+
+    if label.starts_with("at will") || label.starts_with("cantrip") {
+        (0, 0)
+    } else if let Some(uses) = number_before(&label, "/day") {
+        (1, -uses)
+    } else if label.contains("level") {
+        (2, leading_number(&label).unwrap_or(0))
+    } else {
+        (3, 0)
+    }
+}
+
+fn group_thousands(value: u32) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::new();
+
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    grouped
+}
@@ -0,0 +1,882 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{
+    types::{
+        action_types::Action,
+        challenge_rating_types::ChallengeRating,
+        condition_types::ConditionType,
+        damage_types::DamageType,
+        error_types::AppError,
+        import_types::{ImportFailure, ImportReport, ImportWarning, ImportedStatBlock},
+        proficiency_types::{SaveProficiency, SkillProficiency},
+        spell_types::Spells,
+        statblock_types::StatBlock,
+        trait_types::Trait,
+    },
+    utils::import_utils::{
+        empty_statblock, leading_number, parse_ability, parse_action, parse_alignment,
+        parse_condition_type, parse_damage_type, parse_score, parse_size,
+        parse_spellcasting_ability, proficiency_from_bonus, spellcasting_ability_in,
+    },
+};
+
+// Converts a 5e.tools bestiary file, the SRD dumps in the same shape, a list of monsters or a single monster.
+// Nothing is saved, the frontend reviews the StatBlocks and saves them like any other
+#[tauri::command]
+pub fn import_fivetools_statblocks(content: String) -> Result<ImportReport, AppError> {
+    let json: Value = serde_json::from_str(&content)
+        .map_err(|e| AppError::parse(format!("Failed to parse 5e.tools JSON: {}", e)))?;
+
+    import_fivetools_json(&json)
+}
+
+// Only a file without any monsters fails, each creature that can't be converted is reported on its own
+pub fn import_fivetools_json(json: &Value) -> Result<ImportReport, AppError> {
+    let monsters: Vec<&Value> = match json {
+        Value::Array(monsters) => monsters.iter().collect(),
+        Value::Object(file) if file.contains_key("monster") => file["monster"]
+            .as_array()
+            .ok_or(AppError::validation(
+                "monster",
+                "Expected a list of monsters",
+            ))?
+            .iter()
+            .collect(),
+        Value::Object(_) => vec![json],
+        _ => {
+            return Err(AppError::validation(
+                "monster",
+                "Expected a monster, a list of monsters or a bestiary file",
+            ))
+        }
+    };
+
+    let mut report = ImportReport::default();
+
+    for (index, monster) in monsters.into_iter().enumerate() {
+        match convert_monster(monster) {
+            Ok(imported) => report.imported.push(imported),
+            Err(error) => report.failed.push(ImportFailure {
+                name: monster
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or(format!("Monster {}", index + 1)),
+                error,
+            }),
+        }
+    }
+
+    Ok(report)
+}
+
+pub fn convert_monster(monster: &Value) -> Result<ImportedStatBlock, AppError> {
+    let Some(fields) = monster.as_object() else {
+        return Err(AppError::validation("monster", "Expected a monster object"));
+    };
+
+    let name = fields
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or(AppError::validation("name", "Monster has no name"))?;
+
+    // Copies only list their differences from another monster, which isn't in the same file as a rule
+    if let Some(copy) = fields.get("_copy") {
+        return Err(AppError::validation(
+            "_copy",
+            format!(
+                "{} is a copy of {}, import that monster instead",
+                name,
+                copy.get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("another monster")
+            ),
+        ));
+    }
+
+    let mut stat_block = empty_statblock(name);
+    let mut warnings = Vec::new();
+
+    read_size(fields.get("size"), &mut stat_block, &mut warnings);
+    read_type(fields.get("type"), &mut stat_block, &mut warnings);
+    read_alignment(fields.get("alignment"), &mut stat_block, &mut warnings);
+    read_ac(fields.get("ac"), &mut stat_block, &mut warnings);
+    read_hp(fields.get("hp"), &mut stat_block, &mut warnings);
+    read_speed(fields.get("speed"), &mut stat_block, &mut warnings);
+    read_stats(monster, &mut stat_block, &mut warnings);
+    // Saves and skills are stored as proficiency levels, which depend on the CR
+    read_cr(fields.get("cr"), &mut stat_block, &mut warnings);
+    read_saves(fields.get("save"), &mut stat_block, &mut warnings);
+    read_skills(fields.get("skill"), &mut stat_block, &mut warnings);
+
+    stat_block.senses = read_senses(fields.get("senses"), fields.get("passive"));
+    stat_block.languages = fields
+        .get("languages")
+        .and_then(Value::as_array)
+        .map(|languages| join_text(languages, ", "))
+        .filter(|languages| !languages.is_empty());
+
+    stat_block.damage_resistances =
+        read_damage_types(fields.get("resist"), "resist", &mut warnings);
+    stat_block.damage_immunities = read_damage_types(fields.get("immune"), "immune", &mut warnings);
+    stat_block.damage_vulnerabilities =
+        read_damage_types(fields.get("vulnerable"), "vulnerable", &mut warnings);
+    stat_block.condition_immunities =
+        read_condition_types(fields.get("conditionImmune"), &mut warnings);
+
+    stat_block.traits = read_entries(fields.get("trait"))
+        .into_iter()
+        .map(|(name, description)| Trait { name, description })
+        .collect();
+    stat_block.actions = read_actions(fields.get("action"));
+    stat_block.bonus_actions = read_actions(fields.get("bonus"));
+    stat_block.reactions = read_actions(fields.get("reaction"));
+    stat_block.legendary_actions = read_actions(fields.get("legendary"));
+    stat_block.legendary_description = fields
+        .get("legendaryHeader")
+        .and_then(Value::as_array)
+        .map(|header| join_text(header, "\n"));
+
+    read_spellcasting(fields.get("spellcasting"), &mut stat_block, &mut warnings);
+
+    if fields.contains_key("mythic") {
+        warnings.push(ImportWarning::new(
+            "mythic",
+            "Mythic actions have no place in a StatBlock and were left out",
+        ));
+    }
+
+    Ok(ImportedStatBlock {
+        stat_block,
+        warnings,
+    })
+}
+
+//? Fields
+
+fn read_size(size: Option<&Value>, stat_block: &mut StatBlock, warnings: &mut Vec<ImportWarning>) {
+    let sizes: Vec<&str> = match size {
+        Some(Value::Array(sizes)) => sizes.iter().filter_map(Value::as_str).collect(),
+        Some(Value::String(size)) => vec![size.as_str()],
+        _ => vec![],
+    };
+
+    match sizes.first().and_then(|size| parse_size(size)) {
+        Some(size) => stat_block.size = size,
+        None => warnings.push(ImportWarning::new(
+            "size",
+            "Size is missing or unknown, set to Medium",
+        )),
+    }
+
+    if sizes.len() > 1 {
+        warnings.push(ImportWarning::new(
+            "size",
+            format!("Only the first of the sizes {} was kept", sizes.join(", ")),
+        ));
+    }
+}
+
+// "humanoid", or {"type": "humanoid", "tags": ["goblinoid"]} with the tags as the subtype
+fn read_type(type_: Option<&Value>, stat_block: &mut StatBlock, warnings: &mut Vec<ImportWarning>) {
+    match type_ {
+        Some(Value::String(type_)) => stat_block.type_ = type_.clone(),
+        Some(Value::Object(type_)) => {
+            stat_block.type_ = match type_.get("type") {
+                Some(Value::String(type_)) => type_.clone(),
+                Some(Value::Object(choice)) => choice
+                    .get("choose")
+                    .and_then(Value::as_array)
+                    .map(|choices| join_text(choices, " or "))
+                    .unwrap_or_default(),
+                _ => String::new(),
+            };
+
+            let tags: Vec<String> = type_
+                .get("tags")
+                .and_then(Value::as_array)
+                .map(|tags| {
+                    tags.iter()
+                        .filter_map(|tag| match tag {
+                            Value::String(tag) => Some(tag.clone()),
+                            Value::Object(tag) => {
+                                tag.get("tag").and_then(Value::as_str).map(|name| {
+                                    match tag.get("prefix").and_then(Value::as_str) {
+                                        Some(prefix) => format!("{} {}", prefix, name),
+                                        None => name.to_string(),
+                                    }
+                                })
+                            }
+                            _ => None,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            if !tags.is_empty() {
+                stat_block.subtype = Some(tags.join(", "));
+            }
+
+            if let Some(swarm_size) = type_
+                .get("swarmSize")
+                .and_then(Value::as_str)
+                .and_then(parse_size)
+            {
+                stat_block.type_ = format!("swarm of {:?} {}s", swarm_size, stat_block.type_);
+            }
+        }
+        _ => {}
+    }
+
+    if stat_block.type_.is_empty() {
+        warnings.push(ImportWarning::new(
+            "type",
+            "Creature type is missing or unknown",
+        ));
+    }
+}
+
+// Letters for each axis, e.g. ["C", "E"] or ["U"], alignments with a chance or a choice have no equivalent
+fn read_alignment(
+    alignment: Option<&Value>,
+    stat_block: &mut StatBlock,
+    warnings: &mut Vec<ImportWarning>,
+) {
+    let codes: Vec<&str> = alignment
+        .and_then(Value::as_array)
+        .map(|codes| codes.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let text = codes
+        .iter()
+        .map(|code| match *code {
+            "L" => "lawful",
+            "N" => "neutral",
+            "C" => "chaotic",
+            "G" => "good",
+            "E" => "evil",
+            "U" => "unaligned",
+            "A" => "any",
+            _ => "?",
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    match parse_alignment(&text) {
+        Some(alignment) => stat_block.alignment = alignment,
+        None => warnings.push(ImportWarning::new(
+            "alignment",
+            format!(
+                "Alignment {} has no equivalent, set to Unaligned",
+                alignment
+                    .map(Value::to_string)
+                    .unwrap_or("(missing)".to_string())
+            ),
+        )),
+    }
+}
+
+// [15] or [{"ac": 15, "from": ["natural armor"]}], only the first value is kept
+fn read_ac(ac: Option<&Value>, stat_block: &mut StatBlock, warnings: &mut Vec<ImportWarning>) {
+    let values: Vec<&Value> = ac
+        .and_then(Value::as_array)
+        .map(|values| values.iter().collect())
+        .unwrap_or_default();
+
+    let first = values.first().and_then(|value| match value {
+        Value::Object(ac) => ac.get("ac").and_then(Value::as_u64),
+        value => value.as_u64(),
+    });
+
+    match first.and_then(|ac| u8::try_from(ac).ok()) {
+        Some(ac) => stat_block.ac = ac,
+        None => warnings.push(ImportWarning::new(
+            "ac",
+            "Armor class is missing, set to 10",
+        )),
+    }
+
+    if values.len() > 1 {
+        warnings.push(ImportWarning::new(
+            "ac",
+            "Only the first of several armor classes was kept",
+        ));
+    }
+}
+
+fn read_hp(hp: Option<&Value>, stat_block: &mut StatBlock, warnings: &mut Vec<ImportWarning>) {
+    let average = hp
+        .and_then(|hp| hp.get("average"))
+        .and_then(Value::as_u64)
+        .and_then(|average| u16::try_from(average).ok());
+
+    match average {
+        Some(average) => stat_block.hp = average,
+        None => warnings.push(ImportWarning::new(
+            "hp",
+            format!(
+                "Hit points {} aren't a number, set to 1",
+                hp.and_then(|hp| hp.get("special"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("(missing)")
+            ),
+        )),
+    }
+
+    if let Some(formula) = hp.and_then(|hp| hp.get("formula")).and_then(Value::as_str) {
+        stat_block.hit_dice = formula.to_string();
+    }
+}
+
+// {"walk": 30, "fly": {"number": 60, "condition": "(hover)"}} as "30 ft., fly 60 ft. (hover)"
+fn read_speed(
+    speed: Option<&Value>,
+    stat_block: &mut StatBlock,
+    warnings: &mut Vec<ImportWarning>,
+) {
+    let Some(speed) = speed.and_then(Value::as_object) else {
+        warnings.push(ImportWarning::new("speed", "Speed is missing"));
+        return;
+    };
+
+    let can_hover = speed.get("canHover").and_then(Value::as_bool) == Some(true);
+    let mut parts = Vec::new();
+
+    for mode in ["walk", "burrow", "climb", "fly", "swim"] {
+        let Some(value) = speed.get(mode) else {
+            continue;
+        };
+
+        let (feet, condition) = match value {
+            Value::Object(value) => (
+                value.get("number").and_then(Value::as_u64),
+                value
+                    .get("condition")
+                    .and_then(Value::as_str)
+                    .map(strip_tags),
+            ),
+            value => (value.as_u64(), None),
+        };
+        let Some(feet) = feet else {
+            continue;
+        };
+
+        let mut part = match mode {
+            "walk" => format!("{} ft.", feet),
+            mode => format!("{} {} ft.", mode, feet),
+        };
+        if let Some(condition) = condition {
+            part = format!("{} {}", part, condition);
+        } else if mode == "fly" && can_hover {
+            part = format!("{} (hover)", part);
+        }
+        parts.push(part);
+    }
+
+    stat_block.speed = parts.join(", ");
+}
+
+fn read_stats(monster: &Value, stat_block: &mut StatBlock, warnings: &mut Vec<ImportWarning>) {
+    let stats = &mut stat_block.stats;
+
+    for (key, value) in [
+        ("str", &mut stats.strength),
+        ("dex", &mut stats.dexterity),
+        ("con", &mut stats.constitution),
+        ("int", &mut stats.intelligence),
+        ("wis", &mut stats.wisdom),
+        ("cha", &mut stats.charisma),
+    ] {
+        match monster
+            .get(key)
+            .and_then(Value::as_u64)
+            .and_then(|score| u8::try_from(score).ok())
+        {
+            Some(score) => *value = score,
+            None => warnings.push(ImportWarning::new(
+                key,
+                "Ability score is missing, set to 10",
+            )),
+        }
+    }
+}
+
+// "1/4", or {"cr": "10", "lair": "11"} where only the base CR is kept
+fn read_cr(cr: Option<&Value>, stat_block: &mut StatBlock, warnings: &mut Vec<ImportWarning>) {
+    let text = match cr {
+        Some(Value::String(cr)) => Some(cr.as_str()),
+        Some(Value::Object(cr)) => cr.get("cr").and_then(Value::as_str),
+        _ => None,
+    };
+
+    match text.map(str::parse::<ChallengeRating>) {
+        Some(Ok(cr)) => stat_block.cr = cr,
+        Some(Err(e)) => warnings.push(ImportWarning::new("cr", format!("{}, set to 0", e))),
+        None => warnings.push(ImportWarning::new(
+            "cr",
+            "Challenge rating is missing, set to 0",
+        )),
+    }
+}
+
+// {"dex": "+4"}, the level follows from how far the bonus is over the modifier
+fn read_saves(
+    saves: Option<&Value>,
+    stat_block: &mut StatBlock,
+    warnings: &mut Vec<ImportWarning>,
+) {
+    let Some(saves) = saves.and_then(Value::as_object) else {
+        return;
+    };
+
+    for (key, value) in saves {
+        let score = parse_score(key);
+        let bonus = value
+            .as_str()
+            .and_then(leading_number)
+            .and_then(|b| i8::try_from(b).ok());
+
+        match (score, bonus) {
+            (Some(score), Some(bonus)) => stat_block.saves.push(SaveProficiency {
+                score,
+                level: proficiency_from_bonus(
+                    bonus,
+                    stat_block.stats.modifier(score),
+                    stat_block.proficiency_bonus(),
+                ),
+            }),
+            _ => warnings.push(ImportWarning::new(
+                "save",
+                format!("Saving throw {} {} couldn't be read", key, value),
+            )),
+        }
+    }
+}
+
+fn read_skills(
+    skills: Option<&Value>,
+    stat_block: &mut StatBlock,
+    warnings: &mut Vec<ImportWarning>,
+) {
+    let Some(skills) = skills.and_then(Value::as_object) else {
+        return;
+    };
+
+    for (key, bonus) in skills {
+        // Lists of "one of the following" skills
+        if key == "other" {
+            warnings.push(ImportWarning::new(
+                "skill",
+                "Optional skill choices were left out",
+            ));
+            continue;
+        }
+
+        let ability = parse_ability(key);
+        let bonus = bonus
+            .as_str()
+            .and_then(leading_number)
+            .and_then(|b| i8::try_from(b).ok());
+
+        match (ability, bonus) {
+            (Some(ability), Some(bonus)) => stat_block.skill_saves.push(SkillProficiency {
+                ability,
+                level: proficiency_from_bonus(
+                    bonus,
+                    stat_block.stats.modifier(ability.score()),
+                    stat_block.proficiency_bonus(),
+                ),
+            }),
+            _ => warnings.push(ImportWarning::new(
+                "skill",
+                format!("Skill {} couldn't be read", key),
+            )),
+        }
+    }
+}
+
+fn read_senses(senses: Option<&Value>, passive: Option<&Value>) -> Option<String> {
+    let mut parts: Vec<String> = senses
+        .and_then(Value::as_array)
+        .map(|senses| {
+            senses
+                .iter()
+                .filter_map(Value::as_str)
+                .map(strip_tags)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if let Some(passive) = passive.and_then(|passive| match passive {
+        Value::String(passive) => Some(passive.clone()),
+        passive => passive.as_u64().map(|passive| passive.to_string()),
+    }) {
+        parts.push(format!("passive Perception {}", passive));
+    }
+
+    Some(parts.join(", ")).filter(|senses| !senses.is_empty())
+}
+
+// "fire", or {"resist": ["bludgeoning", ...], "note": "from nonmagical attacks"} whose condition is dropped
+fn read_damage_types(
+    entries: Option<&Value>,
+    field: &str,
+    warnings: &mut Vec<ImportWarning>,
+) -> Vec<DamageType> {
+    let mut damage_types = Vec::new();
+
+    for entry in entries.and_then(Value::as_array).into_iter().flatten() {
+        match entry {
+            Value::String(name) => match parse_damage_type(name) {
+                Some(damage_type) => push_unique(&mut damage_types, damage_type),
+                None => warnings.push(ImportWarning::new(
+                    field,
+                    format!("Unknown damage type {}", name),
+                )),
+            },
+            Value::Object(group) => {
+                if let Some(special) = group.get("special").and_then(Value::as_str) {
+                    warnings.push(ImportWarning::new(
+                        field,
+                        format!("\"{}\" was left out", strip_tags(special)),
+                    ));
+                    continue;
+                }
+
+                let nested = read_damage_types(group.get(field), field, warnings);
+                let note = [group.get("preNote"), group.get("note")]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if !note.is_empty() {
+                    warnings.push(ImportWarning::new(
+                        field,
+                        format!(
+                            "{} are listed without their condition \"{}\"",
+                            nested
+                                .iter()
+                                .map(|damage_type| format!("{:?}", damage_type))
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                            strip_tags(&note)
+                        ),
+                    ));
+                }
+
+                for damage_type in nested {
+                    push_unique(&mut damage_types, damage_type);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    damage_types
+}
+
+fn read_condition_types(
+    entries: Option<&Value>,
+    warnings: &mut Vec<ImportWarning>,
+) -> Vec<ConditionType> {
+    let mut condition_types = Vec::new();
+
+    for entry in entries.and_then(Value::as_array).into_iter().flatten() {
+        match entry {
+            Value::String(name) => match parse_condition_type(name) {
+                Some(condition_type) => push_unique(&mut condition_types, condition_type),
+                None => warnings.push(ImportWarning::new(
+                    "conditionImmune",
+                    format!("Unknown condition {}", name),
+                )),
+            },
+            Value::Object(group) => {
+                if group.contains_key("note") || group.contains_key("preNote") {
+                    warnings.push(ImportWarning::new(
+                        "conditionImmune",
+                        "Condition immunities are listed without their condition",
+                    ));
+                }
+                for condition_type in read_condition_types(group.get("conditionImmune"), warnings) {
+                    push_unique(&mut condition_types, condition_type);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    condition_types
+}
+
+fn read_actions(entries: Option<&Value>) -> Vec<Action> {
+    read_entries(entries)
+        .into_iter()
+        .map(|(name, description)| parse_action(&name, &description))
+        .collect()
+}
+
+// [{"name": "...", "entries": [...]}] as names and plain text descriptions
+fn read_entries(entries: Option<&Value>) -> Vec<(String, String)> {
+    entries
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|entry| {
+            (
+                entry
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map(strip_tags)
+                    .unwrap_or_default(),
+                entry.get("entries").map(render_entries).unwrap_or_default(),
+            )
+        })
+        .collect()
+}
+
+// The first spellcasting entry becomes the StatBlock's Spells, any others are kept as traits
+fn read_spellcasting(
+    spellcasting: Option<&Value>,
+    stat_block: &mut StatBlock,
+    warnings: &mut Vec<ImportWarning>,
+) {
+    let entries: Vec<&Value> = spellcasting
+        .and_then(Value::as_array)
+        .map(|entries| entries.iter().collect())
+        .unwrap_or_default();
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        let header = entry
+            .get("headerEntries")
+            .map(render_tagged_text)
+            .unwrap_or_default();
+        let name = entry
+            .get("name")
+            .and_then(Value::as_str)
+            .map(strip_tags)
+            .unwrap_or("Spellcasting".to_string());
+
+        let ability = entry
+            .get("ability")
+            .and_then(Value::as_str)
+            .and_then(parse_spellcasting_ability)
+            .or_else(|| spellcasting_ability_in(&strip_tags(&header)));
+
+        match ability {
+            Some(ability) if index == 0 => {
+                stat_block.spells = Some(Spells {
+                    ability,
+                    save_dc: tag_number(&header, "dc")
+                        .and_then(|dc| u8::try_from(dc).ok())
+                        .unwrap_or_default(),
+                    attack_bonus: tag_number(&header, "hit")
+                        .and_then(|hit| u8::try_from(hit).ok())
+                        .unwrap_or_default(),
+                    spells: spell_lists(entry),
+                });
+            }
+            _ => {
+                let description = std::iter::once(strip_tags(&header))
+                    .chain(
+                        spell_lists(entry)
+                            .into_iter()
+                            .map(|(label, spells)| format!("{}: {}", label, spells)),
+                    )
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                stat_block.traits.push(Trait {
+                    name: name.clone(),
+                    description,
+                });
+                warnings.push(ImportWarning::new(
+                    "spellcasting",
+                    format!("{} was kept as a trait", name),
+                ));
+            }
+        }
+    }
+}
+
+// Labels as shown in the statblock, e.g. "At will", "3/day each" or "1st level (4 slots)"
+fn spell_lists(entry: &Value) -> HashMap<String, String> {
+    let mut lists = HashMap::new();
+
+    if let Some(will) = entry.get("will").and_then(Value::as_array) {
+        lists.insert("At will".to_string(), join_text(will, ", "));
+    }
+
+    for (period, label) in [("daily", "day"), ("rest", "rest"), ("weekly", "week")] {
+        let Some(uses) = entry.get(period).and_then(Value::as_object) else {
+            continue;
+        };
+        for (count, spells) in uses {
+            let Some(spells) = spells.as_array() else {
+                continue;
+            };
+            let label = match count.strip_suffix('e') {
+                Some(count) => format!("{}/{} each", count, label),
+                None => format!("{}/{}", count, label),
+            };
+            lists.insert(label, join_text(spells, ", "));
+        }
+    }
+
+    if let Some(levels) = entry.get("spells").and_then(Value::as_object) {
+        for (level, list) in levels {
+            let Some(spells) = list.get("spells").and_then(Value::as_array) else {
+                continue;
+            };
+            let label = match (level.as_str(), list.get("slots").and_then(Value::as_u64)) {
+                ("0", _) => "Cantrips (at will)".to_string(),
+                (level, Some(slots)) => format!("{} level ({} slots)", ordinal(level), slots),
+                (level, None) => format!("{} level", ordinal(level)),
+            };
+            lists.insert(label, join_text(spells, ", "));
+        }
+    }
+
+    lists
+}
+
+fn ordinal(level: &str) -> String {
+    match level {
+        "1" => "1st".to_string(),
+        "2" => "2nd".to_string(),
+        "3" => "3rd".to_string(),
+        level => format!("{}th", level),
+    }
+}
+
+//? Text
+
+// Entries are strings, lists or nested named entries, flattened into paragraphs
+fn render_entries(entries: &Value) -> String {
+    strip_tags(&render_tagged_text(entries))
+}
+
+fn render_tagged_text(entries: &Value) -> String {
+    match entries {
+        Value::String(text) => text.clone(),
+        Value::Array(entries) => entries
+            .iter()
+            .map(render_tagged_text)
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Object(entry) => {
+            let body = ["entries", "entry", "items"]
+                .into_iter()
+                .filter_map(|key| entry.get(key))
+                .map(render_tagged_text)
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            match entry.get("name").and_then(Value::as_str) {
+                Some(name) => format!("{}. {}", name, body),
+                None => body,
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+fn join_text(values: &[Value], separator: &str) -> String {
+    values
+        .iter()
+        .filter_map(Value::as_str)
+        .map(strip_tags)
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+// {@tag text|source|display}, innermost first so nested tags resolve too
+fn strip_tags(text: &str) -> String {
+    let mut text = text.to_string();
+
+    while let Some(open) = text.rfind("{@") {
+        let Some(close) = text[open..].find('}').map(|close| open + close) else {
+            break;
+        };
+
+        let tag = &text[open + 2..close];
+        let (name, content) = tag.split_once(' ').unwrap_or((tag, ""));
+        let rendered = render_tag(name, content.trim());
+
+        text.replace_range(open..=close, &rendered);
+    }
+
+    text
+}
+
+fn render_tag(name: &str, content: &str) -> String {
+    match name {
+        "atk" => format!("{} Attack:", attack_label(content, "Weapon")),
+        "atkr" => format!("{} Attack Roll:", attack_label(content, "")),
+        "hit" => match content.starts_with('-') {
+            true => content.to_string(),
+            false => format!("+{}", content),
+        },
+        "h" => "Hit: ".to_string(),
+        "hom" => "Hit or Miss: ".to_string(),
+        "dc" => format!("DC {}", content),
+        "actSave" => format!(
+            "{} Saving Throw:",
+            parse_score(content)
+                .map(|score| format!("{:?}", score))
+                .unwrap_or(content.to_string())
+        ),
+        "actSaveFail" => "Failure:".to_string(),
+        "actSaveSuccess" => "Success:".to_string(),
+        "actSaveSuccessOrFail" => "Failure or Success:".to_string(),
+        "recharge" if content.is_empty() => "(Recharge 6)".to_string(),
+        "recharge" => format!("(Recharge {}-6)", content),
+        "d20" => match content.starts_with('-') {
+            true => content.to_string(),
+            false => format!("+{}", content),
+        },
+        _ => {
+            let parts: Vec<&str> = content.split('|').collect();
+            parts
+                .get(2)
+                .filter(|display| !display.is_empty())
+                .unwrap_or(&parts[0])
+                .to_string()
+        }
+    }
+}
+
+// "mw,rw" as "Melee or Ranged Weapon", 2024 attack rolls leave out the weapon or spell part
+fn attack_label(codes: &str, weapon: &str) -> String {
+    let melee = codes.contains('m');
+    let ranged = codes.contains('r');
+    let spell = codes.contains('s');
+
+    let reach = match (melee, ranged) {
+        (true, true) => "Melee or Ranged",
+        (false, true) => "Ranged",
+        _ => "Melee",
+    };
+
+    match (spell, weapon.is_empty()) {
+        (true, _) => format!("{} Spell", reach),
+        (false, true) => reach.to_string(),
+        (false, false) => format!("{} {}", reach, weapon),
+    }
+}
+
+// The number in the first {@dc 13} or {@hit 5} of tagged text
+fn tag_number(text: &str, tag: &str) -> Option<i32> {
+    let marker = format!("{{@{} ", tag);
+    let index = text.find(&marker)?;
+    leading_number(&text[index + marker.len()..])
+}
+
+//? Helper Util
+
+fn push_unique<T: PartialEq>(values: &mut Vec<T>, value: T) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
@@ -0,0 +1,778 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    database::repository::SupabaseRepository,
+    types::{
+        action_types::{Action, AttackKind},
+        error_types::AppError,
+        export_types::{EncounterCreature, FoundryActorExport, FoundryEncounterExport},
+        proficiency_types::ProficiencyLevel,
+        spell_types::SpellcastingAbility,
+        statblock_types::{Ability, Score, Size, StatBlock},
+    },
+    utils::{
+        export_utils::{
+            alignment_text, fetch_encounter_creatures, sorted_spell_lists, spellcasting_header,
+        },
+        html_utils::paragraphs,
+        import_utils::{leading_number, number_after},
+    },
+};
+
+const SENSES: [&str; 4] = ["darkvision", "blindsight", "tremorsense", "truesight"];
+const MOVEMENT: [&str; 4] = ["burrow", "climb", "fly", "swim"];
+
+//? Commands
+
+// Actor JSON for the dnd5e system, ready for Foundry's "Import Data"
+#[tauri::command]
+pub fn export_statblock_foundry(stat_block: StatBlock) -> Result<Value, AppError> {
+    Ok(statblock_to_foundry(&stat_block))
+}
+
+// One actor file per distinct StatBlock in a folder named after the Encounter,
+// encounter.json lists how many tokens of each to place
+#[tauri::command]
+pub async fn export_encounter_foundry(
+    encounter_id: i64,
+    access_token: String,
+    directory: String,
+) -> Result<FoundryEncounterExport, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
+    let (encounter, creatures) = fetch_encounter_creatures(&repository, encounter_id).await?;
+
+    let folder = Path::new(&directory).join(file_stem(&encounter.name));
+    fs::create_dir_all(&folder)
+        .map_err(|e| AppError::storage(format!("Failed to create {}: {}", folder.display(), e)))?;
+
+    let export = encounter_manifest(&encounter.name, &creatures);
+
+    for (creature, actor) in creatures.iter().zip(&export.actors) {
+        write_json(
+            &folder.join(&actor.file),
+            &statblock_to_foundry(&creature.stat_block),
+        )?;
+    }
+
+    write_json(&folder.join("encounter.json"), &export)?;
+
+    Ok(export)
+}
+
+// One actor per creature, in the same order
+pub fn encounter_manifest(
+    encounter_name: &str,
+    creatures: &[EncounterCreature],
+) -> FoundryEncounterExport {
+    let mut export = FoundryEncounterExport {
+        folder: encounter_name.to_string(),
+        actors: Vec::new(),
+    };
+
+    for creature in creatures {
+        let stat_block = &creature.stat_block;
+        let statblock_id = stat_block.id.unwrap_or_default();
+
+        // Different StatBlocks can share a name
+        let mut file = format!("{}.json", file_stem(&stat_block.name));
+        if export.actors.iter().any(|actor| actor.file == file) {
+            file = format!("{}-{}.json", file_stem(&stat_block.name), statblock_id);
+        }
+
+        export.actors.push(FoundryActorExport {
+            name: stat_block.name.clone(),
+            file,
+            statblock_id,
+            tokens: creature.instances.len() as u32,
+            token_names: creature
+                .instances
+                .iter()
+                .map(|playable| {
+                    playable
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| stat_block.name.clone())
+                })
+                .collect(),
+        });
+    }
+
+    export
+}
+
+//? Actor
+
+// Targets the dnd5e 3.x data model, newer versions of the system migrate it on import
+pub fn statblock_to_foundry(stat_block: &StatBlock) -> Value {
+    let size = size_key(stat_block.size);
+    let token_size = match stat_block.size {
+        Size::Tiny => 0.5,
+        Size::Small | Size::Medium => 1.0,
+        Size::Large => 2.0,
+        Size::Huge => 3.0,
+        Size::Gargantuan => 4.0,
+    };
+
+    let initiative_bonus =
+        stat_block.initiative.multiplier() * stat_block.proficiency_bonus() as i8;
+    let legendary_actions = legendary_actions(stat_block);
+    let legendary_resistances = legendary_resistances(stat_block);
+
+    json!({
+        "name": stat_block.name,
+        "type": "npc",
+        "img": "icons/svg/mystery-man.svg",
+        "system": {
+            "abilities": abilities(stat_block),
+            "attributes": {
+                "ac": { "flat": stat_block.ac, "calc": "natural" },
+                "hp": {
+                    "value": stat_block.hp,
+                    "max": stat_block.hp,
+                    "temp": 0,
+                    "tempmax": 0,
+                    "formula": stat_block.hit_dice,
+                },
+                "init": {
+                    "ability": "",
+                    "bonus": if initiative_bonus == 0 { String::new() } else { initiative_bonus.to_string() },
+                },
+                "movement": movement(&stat_block.speed),
+                "senses": senses(stat_block.senses.as_deref().unwrap_or_default()),
+                "spellcasting": stat_block
+                    .spells
+                    .as_ref()
+                    .map(|spells| spellcasting_key(spells.ability))
+                    .unwrap_or_default(),
+            },
+            "details": {
+                "alignment": alignment_text(stat_block.alignment),
+                "type": {
+                    "value": stat_block.type_.to_lowercase(),
+                    "subtype": stat_block.subtype.clone().unwrap_or_default(),
+                    "swarm": "",
+                    "custom": "",
+                },
+                "cr": stat_block.cr.as_f32(),
+                "xp": { "value": stat_block.cr.xp() },
+                "biography": { "value": "" },
+            },
+            "traits": {
+                "size": size,
+                "dv": { "value": lowercase_names(&stat_block.damage_vulnerabilities), "custom": "" },
+                "dr": { "value": lowercase_names(&stat_block.damage_resistances), "custom": "" },
+                "di": { "value": lowercase_names(&stat_block.damage_immunities), "custom": "" },
+                "ci": { "value": lowercase_names(&stat_block.condition_immunities), "custom": "" },
+                "languages": {
+                    "value": [],
+                    "custom": stat_block.languages.clone().unwrap_or_default(),
+                },
+            },
+            "skills": skills(stat_block),
+            "resources": {
+                "legact": { "value": legendary_actions, "max": legendary_actions },
+                "legres": { "value": legendary_resistances, "max": legendary_resistances },
+            },
+        },
+        "items": items(stat_block),
+        "prototypeToken": {
+            "name": stat_block.name,
+            "actorLink": false,
+            "disposition": -1,
+            "width": token_size,
+            "height": token_size,
+        },
+        "flags": {
+            "encounter-architect": { "statblock_id": stat_block.id },
+        },
+    })
+}
+
+fn abilities(stat_block: &StatBlock) -> Value {
+    let mut abilities = Map::new();
+
+    for score in Score::ALL {
+        abilities.insert(
+            score_key(score).to_string(),
+            json!({
+                "value": stat_block.stats.score(score),
+                "proficient": if stat_block.save_level(score) == ProficiencyLevel::None { 0 } else { 1 },
+            }),
+        );
+    }
+
+    Value::Object(abilities)
+}
+
+fn skills(stat_block: &StatBlock) -> Value {
+    let mut skills = Map::new();
+
+    for ability in Ability::ALL {
+        let level = stat_block.skill_level(ability);
+        if level == ProficiencyLevel::None {
+            continue;
+        }
+        skills.insert(
+            skill_key(ability).to_string(),
+            json!({
+                "value": level.multiplier(),
+                "ability": score_key(ability.score()),
+            }),
+        );
+    }
+
+    Value::Object(skills)
+}
+
+// "30 ft., climb 30 ft., fly 60 ft. (hover)"
+fn movement(speed: &str) -> Value {
+    let mut movement = Map::new();
+    let speed = speed.to_lowercase();
+
+    for part in speed.split(',').map(str::trim) {
+        match MOVEMENT.iter().find(|kind| part.starts_with(*kind)) {
+            Some(kind) => {
+                if let Some(feet) = leading_number(&part[kind.len()..]) {
+                    movement.insert(kind.to_string(), json!(feet));
+                }
+            }
+            None => {
+                if let Some(feet) = leading_number(part) {
+                    movement.insert("walk".to_string(), json!(feet));
+                }
+            }
+        }
+    }
+    movement.insert("units".to_string(), json!("ft"));
+    movement.insert("hover".to_string(), json!(speed.contains("hover")));
+
+    Value::Object(movement)
+}
+
+// "darkvision 60 ft., passive Perception 9", anything Foundry has no field for goes to special
+fn senses(text: &str) -> Value {
+    let mut senses = Map::new();
+    let mut special = Vec::new();
+
+    for part in text
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let lower = part.to_lowercase();
+        match SENSES.iter().find(|sense| lower.starts_with(*sense)) {
+            Some(sense) => match number_after(&lower, sense) {
+                Some(feet) => {
+                    senses.insert(sense.to_string(), json!(feet));
+                }
+                None => special.push(part),
+            },
+            None if lower.starts_with("passive perception") => {}
+            None => special.push(part),
+        }
+    }
+    senses.insert("units".to_string(), json!("ft"));
+    senses.insert("special".to_string(), json!(special.join(", ")));
+
+    Value::Object(senses)
+}
+
+fn legendary_actions(stat_block: &StatBlock) -> i32 {
+    if stat_block.legendary_actions.is_empty() {
+        return 0;
+    }
+
+    stat_block
+        .legendary_description
+        .as_deref()
+        .and_then(|description| number_after(description, "can take "))
+        .unwrap_or(3)
+}
+
+// "Legendary Resistance (3/Day)"
+fn legendary_resistances(stat_block: &StatBlock) -> i32 {
+    stat_block
+        .traits
+        .iter()
+        .find(|statblock_trait| statblock_trait.name.starts_with("Legendary Resistance"))
+        .map(|statblock_trait| number_after(&statblock_trait.name, "(").unwrap_or(1))
+        .unwrap_or(0)
+}
+
+//? Items
+
+fn items(stat_block: &StatBlock) -> Vec<Value> {
+    let mut items: Vec<Value> = stat_block
+        .traits
+        .iter()
+        .map(|statblock_trait| {
+            feature_item(
+                &statblock_trait.name,
+                &statblock_trait.description,
+                "",
+                None,
+            )
+        })
+        .collect();
+
+    if let Some(spells) = &stat_block.spells {
+        let mut description = spellcasting_header(stat_block, spells);
+        for (label, list) in sorted_spell_lists(spells) {
+            description.push_str(&format!("\n{}: {}", label, list));
+        }
+        items.push(feature_item("Spellcasting", &description, "", None));
+    }
+
+    for (actions, activation) in [
+        (&stat_block.actions, "action"),
+        (&stat_block.bonus_actions, "bonus"),
+        (&stat_block.reactions, "reaction"),
+        (&stat_block.legendary_actions, "legendary"),
+    ] {
+        items.extend(actions.iter().map(|action| action_item(action, activation)));
+    }
+
+    // Stable ids so importing again updates the same items instead of duplicating them,
+    // seeded by what the item is so reordering or adding actions keeps the others' ids
+    let mut seeds: HashMap<String, usize> = HashMap::new();
+    for item in items.iter_mut() {
+        let seed = format!(
+            "{}:{}",
+            item["type"].as_str().unwrap_or_default(),
+            item["name"].as_str().unwrap_or_default()
+        );
+        let repeats = seeds.entry(seed.clone()).or_insert(0);
+        item["_id"] = json!(match *repeats {
+            0 => foundry_id(&seed),
+            n => foundry_id(&format!("{}:{}", seed, n)),
+        });
+        *repeats += 1;
+    }
+
+    items
+}
+
+fn feature_item(name: &str, description: &str, activation: &str, cost: Option<i32>) -> Value {
+    json!({
+        "name": name,
+        "type": "feat",
+        "system": {
+            "description": { "value": paragraphs(description) },
+            "activation": { "type": activation, "cost": cost },
+            "type": { "value": "monster" },
+        },
+    })
+}
+
+fn action_item(action: &Action, activation: &str) -> Value {
+    // "Wing Attack (Costs 2 Actions)"
+    let cost = match activation {
+        "legendary" => number_after(&action.name, "Costs ").unwrap_or(1),
+        _ => 1,
+    };
+    let mut item = feature_item(&action.name, &action.description, activation, Some(cost));
+
+    let Some(attack) = &action.attack else {
+        return item;
+    };
+
+    let system = &mut item["system"];
+    let action_type = match attack.kind {
+        Some(AttackKind::MeleeWeapon) | Some(AttackKind::MeleeOrRangedWeapon) => "mwak",
+        Some(AttackKind::RangedWeapon) => "rwak",
+        Some(AttackKind::MeleeSpell) => "msak",
+        Some(AttackKind::RangedSpell) => "rsak",
+        None if attack.saving_throw.is_some() => "save",
+        None => "other",
+    };
+    system["actionType"] = json!(action_type);
+
+    if let Some(to_hit) = attack.to_hit {
+        // A flat bonus is the whole attack bonus, Foundry adds no ability or proficiency
+        system["attack"] = json!({ "bonus": to_hit.to_string(), "flat": true });
+    }
+    system["damage"] = json!({
+        "parts": attack
+            .damage
+            .iter()
+            .map(|component| json!([component.dice, lowercase_name(&component.damage_type)]))
+            .collect::<Vec<_>>(),
+    });
+    system["range"] = match (attack.range, attack.reach) {
+        (Some(range), _) => json!({ "value": range, "long": attack.long_range, "units": "ft" }),
+        (None, Some(reach)) => json!({ "value": reach, "long": null, "units": "ft" }),
+        (None, None) => json!({ "value": null, "long": null, "units": "" }),
+    };
+    system["target"] = json!({ "value": attack.targets, "type": "creature" });
+
+    if let Some(saving_throw) = &attack.saving_throw {
+        system["save"] = json!({
+            "ability": score_key(saving_throw.score),
+            "dc": saving_throw.dc,
+            "scaling": "flat",
+        });
+    }
+    if let Some(recharge) = attack.recharge {
+        system["recharge"] = json!({ "value": recharge, "charged": true });
+    }
+    if let Some(uses) = attack.uses_per_day {
+        system["uses"] = json!({ "value": uses, "max": uses.to_string(), "per": "day" });
+    }
+
+    if attack.kind.is_some_and(|kind| {
+        matches!(
+            kind,
+            AttackKind::MeleeWeapon | AttackKind::RangedWeapon | AttackKind::MeleeOrRangedWeapon
+        )
+    }) {
+        item["type"] = json!("weapon");
+        item["system"]["type"] = json!({ "value": "natural" });
+        item["system"]["equipped"] = json!(true);
+    }
+
+    item
+}
+
+//? Helper Util
+
+fn score_key(score: Score) -> &'static str {
+    match score {
+        Score::Strength => "str",
+        Score::Dexterity => "dex",
+        Score::Constitution => "con",
+        Score::Intelligence => "int",
+        Score::Wisdom => "wis",
+        Score::Charisma => "cha",
+    }
+}
+
+fn spellcasting_key(ability: SpellcastingAbility) -> &'static str {
+    match ability {
+        SpellcastingAbility::Intelligence => "int",
+        SpellcastingAbility::Wisdom => "wis",
+        SpellcastingAbility::Charisma => "cha",
+    }
+}
+
+fn skill_key(ability: Ability) -> &'static str {
+    match ability {
+        Ability::Acrobatics => "acr",
+        Ability::AnimalHandling => "ani",
+        Ability::Arcana => "arc",
+        Ability::Athletics => "ath",
+        Ability::Deception => "dec",
+        Ability::History => "his",
+        Ability::Insight => "ins",
+        Ability::Intimidation => "itm",
+        Ability::Investigation => "inv",
+        Ability::Medicine => "med",
+        Ability::Nature => "nat",
+        Ability::Perception => "prc",
+        Ability::Performance => "prf",
+        Ability::Persuasion => "per",
+        Ability::Religion => "rel",
+        Ability::SleightOfHand => "slt",
+        Ability::Stealth => "ste",
+        Ability::Survival => "sur",
+    }
+}
+
+fn size_key(size: Size) -> &'static str {
+    match size {
+        Size::Tiny => "tiny",
+        Size::Small => "sm",
+        Size::Medium => "med",
+        Size::Large => "lg",
+        Size::Huge => "huge",
+        Size::Gargantuan => "grg",
+    }
+}
+
+// Foundry's damage and condition keys are our variant names in lowercase
+fn lowercase_name(value: &impl std::fmt::Debug) -> String {
+    format!("{:?}", value).to_lowercase()
+}
+
+fn lowercase_names(values: &[impl std::fmt::Debug]) -> Vec<String> {
+    values.iter().map(lowercase_name).collect()
+}
+
+// 16 hex characters from FNV-1a, the length Foundry uses for ids
+fn foundry_id(seed: &str) -> String {
+    let hash = seed.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+fn file_stem(name: &str) -> String {
+    let stem = name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if stem.is_empty() {
+        "actor".to_string()
+    } else {
+        stem
+    }
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> Result<(), AppError> {
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| AppError::parse(format!("Failed to serialize {}: {}", path.display(), e)))?;
+
+    fs::write(path, content)
+        .map_err(|e| AppError::storage(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        database::{
+            memory_repository::InMemoryRepository,
+            repository::{EncounterRepository, StatBlockRepository},
+        },
+        types::encounter_types::{Encounter, PlayableStatBlock},
+        utils::open5e_utils::import_open5e_json,
+    };
+
+    fn fixture(path: &str) -> Value {
+        let path: PathBuf = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join(path);
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    // The actor fixtures were exported from these Open5e creatures
+    fn open5e_statblock(file: &str, name: &str) -> StatBlock {
+        import_open5e_json(&fixture(&format!("open5e/{}", file)))
+            .unwrap()
+            .imported
+            .into_iter()
+            .map(|imported| imported.stat_block)
+            .find(|stat_block| stat_block.name == name)
+            .unwrap()
+    }
+
+    fn instance(statblock_id: i64, name: Option<&str>) -> PlayableStatBlock {
+        PlayableStatBlock {
+            id: None,
+            max_hp: None,
+            current_hp: 0,
+            temporary_hp: 0,
+            initiative: None,
+            name: name.map(str::to_string),
+            conditions: vec![],
+            statblock_id,
+            encounter_id: 1,
+        }
+    }
+
+    #[test]
+    fn actors_match_the_fixtures() {
+        for (source, name, actor) in [
+            ("goblin.json", "Goblin", "goblin.json"),
+            ("lich.json", "Lich", "lich.json"),
+            (
+                "results-page.json",
+                "Young Red Dragon",
+                "young-red-dragon.json",
+            ),
+        ] {
+            assert_eq!(
+                statblock_to_foundry(&open5e_statblock(source, name)),
+                fixture(&format!("foundry/{}", actor)),
+                "{}",
+                actor
+            );
+        }
+    }
+
+    // Our fixtures are our own output, this checks the fields that matter against an actor
+    // exported from the dnd5e system itself
+    #[test]
+    fn actor_matches_a_dnd5e_export() {
+        let stat_block = open5e_statblock("results-page.json", "Young Red Dragon");
+        let actor = statblock_to_foundry(&stat_block);
+        let expected = fixture("foundry/dnd5e/young-red-dragon.json");
+        let (system, expected_system) = (&actor["system"], &expected["system"]);
+
+        for score in Score::ALL {
+            let key = score_key(score);
+            for field in ["value", "proficient"] {
+                assert_eq!(
+                    system["abilities"][key][field], expected_system["abilities"][key][field],
+                    "{}.{}",
+                    key, field
+                );
+            }
+        }
+
+        for key in ["dr", "di", "dv", "ci"] {
+            assert_eq!(
+                sorted_strings(&system["traits"][key]["value"]),
+                sorted_strings(&expected_system["traits"][key]["value"]),
+                "{}",
+                key
+            );
+        }
+
+        let (hp, expected_hp) = (
+            &system["attributes"]["hp"],
+            &expected_system["attributes"]["hp"],
+        );
+        assert_eq!(hp["value"], expected_hp["value"]);
+        assert_eq!(hp["max"], expected_hp["max"]);
+        assert_eq!(
+            without_spaces(&hp["formula"]),
+            without_spaces(&expected_hp["formula"])
+        );
+        for field in ["flat", "calc"] {
+            assert_eq!(
+                system["attributes"]["ac"][field],
+                expected_system["attributes"]["ac"][field]
+            );
+        }
+
+        // dnd5e keeps the recharge out of the name and writes the ability modifier as @mod
+        let mut compared = 0;
+        for item in actor["items"].as_array().unwrap() {
+            let name = item["name"].as_str().unwrap();
+            let name = name.split(" (").next().unwrap();
+            let expected_item = expected["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|expected_item| expected_item["name"] == name)
+                .unwrap_or_else(|| panic!("{} is missing from the dnd5e export", name));
+
+            let modifier = Score::ALL
+                .into_iter()
+                .find(|score| expected_item["system"]["ability"] == score_key(*score))
+                .map(|score| stat_block.stats.modifier(score))
+                .unwrap_or_default();
+            let expected_parts: Vec<Value> = expected_item["system"]["damage"]["parts"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|part| {
+                    let formula = part[0].as_str().unwrap();
+                    json!([formula.replace("@mod", &modifier.to_string()), part[1]])
+                })
+                .collect();
+
+            assert_eq!(
+                item["system"]["damage"]["parts"],
+                json!(expected_parts),
+                "{}",
+                name
+            );
+            compared += 1;
+        }
+        assert_eq!(compared, 2);
+    }
+
+    fn sorted_strings(values: &Value) -> Vec<String> {
+        let mut values: Vec<String> = values
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|value| value.as_str().unwrap().to_string())
+            .collect();
+        values.sort();
+        values
+    }
+
+    fn without_spaces(value: &Value) -> String {
+        value.as_str().unwrap().replace(' ', "")
+    }
+
+    #[tokio::test]
+    async fn manifest_counts_the_tokens_of_each_statblock() {
+        let repository = InMemoryRepository::new();
+        let mut goblin = open5e_statblock("goblin.json", "Goblin");
+        let mut dragon = open5e_statblock("results-page.json", "Young Red Dragon");
+        let goblin_id = repository
+            .save_statblock(&mut goblin, None)
+            .await
+            .unwrap()
+            .id;
+        let dragon_id = repository
+            .save_statblock(&mut dragon, None)
+            .await
+            .unwrap()
+            .id;
+
+        let encounter_id = repository
+            .save_encounter(
+                Encounter {
+                    id: None,
+                    name: "Goblin Ambush".to_string(),
+                    user_id: "user".to_string(),
+                    last_modified: chrono::Utc::now(),
+                    hp_mode: None,
+                },
+                None,
+            )
+            .await
+            .unwrap()
+            .id;
+        let instances = [
+            instance(goblin_id, None),
+            instance(dragon_id, None),
+            instance(goblin_id, Some("Goblin Archer")),
+            instance(goblin_id, None),
+        ]
+        .into_iter()
+        .map(|mut playable| {
+            playable.encounter_id = encounter_id;
+            playable
+        })
+        .collect();
+        repository
+            .save_playable_statblocks(instances)
+            .await
+            .unwrap();
+
+        let (encounter, creatures) = fetch_encounter_creatures(&repository, encounter_id)
+            .await
+            .unwrap();
+        let export = encounter_manifest(&encounter.name, &creatures);
+
+        // The fixture was written with other ids than the in-memory repository hands out
+        let mut expected = fixture("foundry/encounter.json");
+        expected["actors"][0]["statblock_id"] = json!(goblin_id);
+        expected["actors"][1]["statblock_id"] = json!(dragon_id);
+        assert_eq!(serde_json::to_value(&export).unwrap(), expected);
+    }
+
+    #[test]
+    fn statblocks_sharing_a_name_get_their_own_file() {
+        let creature = |id: i64, instances: usize| {
+            let mut stat_block = open5e_statblock("goblin.json", "Goblin");
+            stat_block.id = Some(id);
+            EncounterCreature {
+                stat_block,
+                instances: (0..instances).map(|_| instance(id, None)).collect(),
+            }
+        };
+
+        let export = encounter_manifest("Goblin Ambush", &[creature(12, 2), creature(13, 1)]);
+
+        let files: Vec<(&str, u32)> = export
+            .actors
+            .iter()
+            .map(|actor| (actor.file.as_str(), actor.tokens))
+            .collect();
+        assert_eq!(files, [("goblin.json", 2), ("goblin-13.json", 1)]);
+    }
+}
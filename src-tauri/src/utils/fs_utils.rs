@@ -2,9 +2,9 @@ use std::{fs, path::PathBuf};
 
 use serde::Deserialize;
 
-use crate::types::{encounter_types::Encounter, statblock_types::StatBlock};
+use crate::types::{encounter_types::Encounter, error_types::AppError, statblock_types::StatBlock};
 
-fn load_data<T: for<'de> Deserialize<'de>>(folder_name: &str) -> Result<Vec<T>, String> {
+fn load_data<T: for<'de> Deserialize<'de>>(folder_name: &str) -> Result<Vec<T>, AppError> {
     let folder = PathBuf::from(format!("../{}", folder_name));
 
     if !folder.exists() {
@@ -13,13 +13,15 @@ fn load_data<T: for<'de> Deserialize<'de>>(folder_name: &str) -> Result<Vec<T>,
 
     let mut data = Vec::new();
 
-    for entry in fs::read_dir(folder).map_err(|e| e.to_string())? {
-        let entry = entry.map_err(|e| e.to_string())?;
+    for entry in fs::read_dir(folder).map_err(|e| AppError::storage(e.to_string()))? {
+        let entry = entry.map_err(|e| AppError::storage(e.to_string()))?;
         let path = entry.path();
 
         if path.extension().map_or(false, |ext| ext == "json") {
-            let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-            let json: T = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+            let content =
+                fs::read_to_string(&path).map_err(|e| AppError::storage(e.to_string()))?;
+            let json: T = serde_json::from_str(&content)
+                .map_err(|e| AppError::parse(format!("{}: {}", path.display(), e)))?;
             data.push(json);
         }
     }
@@ -28,13 +30,13 @@ fn load_data<T: for<'de> Deserialize<'de>>(folder_name: &str) -> Result<Vec<T>,
 }
 
 #[tauri::command]
-pub fn load_encounters() -> Result<Vec<Encounter>, String> {
+pub fn load_encounters() -> Result<Vec<Encounter>, AppError> {
     let encounters: Vec<Encounter> = load_data("encounters")?;
     Ok(encounters)
 }
 
 #[tauri::command]
-pub fn load_statblocks() -> Result<Vec<StatBlock>, String> {
+pub fn load_statblocks() -> Result<Vec<StatBlock>, AppError> {
     let statblocks: Vec<StatBlock> = load_data("statblocks")?;
     Ok(statblocks)
 }
@@ -0,0 +1,180 @@
+use crate::{
+    database::repository::SupabaseRepository,
+    types::{
+        action_types::Action,
+        error_types::AppError,
+        statblock_types::{Score, StatBlock},
+        trait_types::Trait,
+    },
+    utils::export_utils::{
+        challenge_text, condition_text, damage_text, fetch_encounter_creatures, hit_points_text,
+        languages_text, saves_text, score_abbreviation, senses_text, signed, skills_text,
+        sorted_spell_lists, spellcasting_header, type_line,
+    },
+};
+
+//? Commands
+
+#[tauri::command]
+pub fn export_statblock_homebrewery(stat_block: StatBlock) -> Result<String, AppError> {
+    Ok(statblock_to_homebrewery(&stat_block))
+}
+
+// One document with each distinct creature of the Encounter, listed by how many there are
+#[tauri::command]
+pub async fn export_encounter_homebrewery(
+    encounter_id: i64,
+    access_token: String,
+) -> Result<String, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
+    let (encounter, creatures) = fetch_encounter_creatures(&repository, encounter_id).await?;
+
+    let mut document = format!("# {}\n\n", encounter.name);
+
+    for creature in &creatures {
+        document.push_str(&format!(
+            "- {} × {}\n",
+            creature.instances.len(),
+            creature.stat_block.name
+        ));
+    }
+
+    for creature in &creatures {
+        document.push('\n');
+        document.push_str(&statblock_to_homebrewery(&creature.stat_block));
+    }
+
+    Ok(document)
+}
+
+//? Markdown
+
+// Homebrewery V3 {{monster,frame}} block
+pub fn statblock_to_homebrewery(stat_block: &StatBlock) -> String {
+    let derived = stat_block.derived_stats();
+    let mut lines = vec![
+        "{{monster,frame".to_string(),
+        format!("## {}", stat_block.name),
+        format!("*{}*", type_line(stat_block)),
+        "___".to_string(),
+        format!("**Armor Class** :: {}", stat_block.ac),
+        format!("**Hit Points** :: {}", hit_points_text(stat_block)),
+        format!("**Speed** :: {}", stat_block.speed),
+        "___".to_string(),
+        format!(
+            "| {} |",
+            Score::ALL
+                .iter()
+                .map(|score| score_abbreviation(*score).to_uppercase())
+                .collect::<Vec<_>>()
+                .join(" | ")
+        ),
+        format!("|{}", ":---:|".repeat(Score::ALL.len())),
+        format!(
+            "| {} |",
+            derived
+                .modifiers
+                .iter()
+                .map(|modifier| format!("{} ({})", modifier.value, signed(modifier.modifier)))
+                .collect::<Vec<_>>()
+                .join(" | ")
+        ),
+        "___".to_string(),
+    ];
+
+    let properties = [
+        ("Saving Throws", saves_text(stat_block)),
+        ("Skills", skills_text(stat_block)),
+        (
+            "Damage Vulnerabilities",
+            damage_text(&stat_block.damage_vulnerabilities),
+        ),
+        (
+            "Damage Resistances",
+            damage_text(&stat_block.damage_resistances),
+        ),
+        (
+            "Damage Immunities",
+            damage_text(&stat_block.damage_immunities),
+        ),
+        (
+            "Condition Immunities",
+            condition_text(&stat_block.condition_immunities),
+        ),
+        ("Senses", Some(senses_text(stat_block))),
+        ("Languages", Some(languages_text(stat_block))),
+    ];
+    for (label, value) in properties {
+        if let Some(value) = value {
+            lines.push(format!("**{}** :: {}", label, value));
+        }
+    }
+    lines.push(format!(
+        "**Challenge** :: {} {{{{bonus **Proficiency Bonus** {}}}}}",
+        challenge_text(stat_block),
+        signed(derived.proficiency_bonus as i8)
+    ));
+    lines.push("___".to_string());
+
+    let mut features: Vec<String> = stat_block.traits.iter().map(trait_text).collect();
+    if let Some(spells) = &stat_block.spells {
+        let mut spellcasting = format!(
+            "***Spellcasting.*** {}",
+            spellcasting_header(stat_block, spells)
+        );
+        for (label, list) in sorted_spell_lists(spells) {
+            spellcasting.push_str(&format!("\n\n{}: *{}*", label, list));
+        }
+        features.push(spellcasting);
+    }
+    lines.push(features.join("\n:\n"));
+
+    push_actions(&mut lines, "Actions", None, &stat_block.actions);
+    push_actions(&mut lines, "Bonus Actions", None, &stat_block.bonus_actions);
+    push_actions(&mut lines, "Reactions", None, &stat_block.reactions);
+    push_actions(
+        &mut lines,
+        "Legendary Actions",
+        stat_block.legendary_description.as_deref(),
+        &stat_block.legendary_actions,
+    );
+
+    lines.push("}}".to_string());
+    lines.retain(|line| !line.is_empty());
+    lines.join("\n") + "\n"
+}
+
+fn push_actions(
+    lines: &mut Vec<String>,
+    label: &str,
+    description: Option<&str>,
+    actions: &[Action],
+) {
+    if actions.is_empty() {
+        return;
+    }
+
+    lines.push(format!("### {}", label));
+    if let Some(description) = description.filter(|description| !description.is_empty()) {
+        lines.push(format!("{}\n", description.trim()));
+    }
+    lines.push(
+        actions
+            .iter()
+            .map(action_text)
+            .collect::<Vec<_>>()
+            .join("\n:\n"),
+    );
+}
+
+fn trait_text(statblock_trait: &Trait) -> String {
+    format!(
+        "***{}.*** {}",
+        statblock_trait.name,
+        statblock_trait.description.trim()
+    )
+}
+
+fn action_text(action: &Action) -> String {
+    format!("***{}.*** {}", action.name, action.description.trim())
+}
@@ -0,0 +1,259 @@
+use std::fs;
+
+use crate::{
+    database::repository::SupabaseRepository,
+    types::{
+        action_types::Action,
+        error_types::AppError,
+        export_types::EncounterCreature,
+        statblock_types::{Score, StatBlock},
+        trait_types::Trait,
+    },
+    utils::export_utils::{
+        challenge_text, condition_text, damage_text, fetch_encounter_creatures, hit_points_text,
+        languages_text, saves_text, score_abbreviation, senses_text, signed, skills_text,
+        sorted_spell_lists, spellcasting_header, type_line,
+    },
+};
+
+// Inlined so the page prints and shares without anything next to it
+const STYLE: &str = r#"
+body { margin: 0; padding: 24px; background: #fff; font-family: "Scaly Sans", "Noto Sans", Helvetica, Arial, sans-serif; font-size: 13.5px; color: #000; }
+h1 { font-family: "Mrs Eaves", "Libre Baskerville", Georgia, serif; font-variant: small-caps; color: #58180d; margin: 0 0 8px; }
+.roster { margin: 0 0 16px; padding-left: 20px; }
+.sheet { columns: 2; column-gap: 24px; }
+.statblock { width: 400px; margin: 0 auto 24px; padding: 8px 12px; background: #fdf1dc; border-top: 4px solid #e69a28; border-bottom: 4px solid #e69a28; box-shadow: 0 0 6px #b0a48e; line-height: 1.35; break-inside: avoid; page-break-inside: avoid; }
+.sheet .statblock { margin: 0 0 24px; display: inline-block; box-sizing: border-box; width: 100%; }
+.statblock h2 { font-family: "Mrs Eaves", "Libre Baskerville", Georgia, serif; font-variant: small-caps; font-size: 24px; color: #7a200d; margin: 0; }
+.statblock h3 { font-family: "Mrs Eaves", "Libre Baskerville", Georgia, serif; font-variant: small-caps; font-size: 18px; font-weight: normal; color: #7a200d; border-bottom: 1px solid #7a200d; margin: 12px 0 6px; }
+.statblock .type { font-style: italic; margin: 0 0 4px; }
+.statblock .rule { height: 5px; margin: 6px 0; border: 0; background: #922610; clip-path: polygon(0 0, 100% 50%, 0 100%); }
+.statblock .property { color: #7a200d; margin: 0; }
+.statblock .property b { color: #7a200d; }
+.statblock .abilities { width: 100%; color: #7a200d; text-align: center; border-collapse: collapse; }
+.statblock .abilities th { font-weight: bold; }
+.statblock p { margin: 0 0 6px; }
+.statblock .spells { margin-left: 16px; }
+@media print { body { padding: 0; } .statblock { box-shadow: none; } }
+"#;
+
+//? Commands
+
+#[tauri::command]
+pub fn render_statblock_html(stat_block: StatBlock) -> Result<String, AppError> {
+    Ok(html_document(
+        &stat_block.name,
+        &statblock_to_html(&stat_block),
+    ))
+}
+
+// Writes every creature of the Encounter to one printable page at path
+#[tauri::command]
+pub async fn export_encounter_html(
+    encounter_id: i64,
+    access_token: String,
+    path: String,
+) -> Result<(), AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
+    let (encounter, creatures) = fetch_encounter_creatures(&repository, encounter_id).await?;
+
+    fs::write(&path, encounter_sheet(&encounter.name, &creatures))
+        .map_err(|e| AppError::storage(format!("Failed to write {}: {}", path, e)))
+}
+
+//? HTML
+
+pub fn encounter_sheet(title: &str, creatures: &[EncounterCreature]) -> String {
+    let mut body = format!("<h1>{}</h1>\n<ul class=\"roster\">\n", escape(title));
+
+    for creature in creatures {
+        body.push_str(&format!(
+            "<li>{} × {}</li>\n",
+            creature.instances.len(),
+            escape(&creature.stat_block.name)
+        ));
+    }
+    body.push_str("</ul>\n<div class=\"sheet\">\n");
+
+    for creature in creatures {
+        body.push_str(&statblock_to_html(&creature.stat_block));
+    }
+    body.push_str("</div>\n");
+
+    html_document(title, &body)
+}
+
+pub fn statblock_to_html(stat_block: &StatBlock) -> String {
+    let derived = stat_block.derived_stats();
+    let rule = "<hr class=\"rule\">\n";
+
+    let mut html = format!(
+        "<article class=\"statblock\">\n<h2>{}</h2>\n<p class=\"type\">{}</p>\n{}",
+        escape(&stat_block.name),
+        escape(&type_line(stat_block)),
+        rule
+    );
+
+    html.push_str(&property("Armor Class", &stat_block.ac.to_string()));
+    html.push_str(&property("Hit Points", &hit_points_text(stat_block)));
+    html.push_str(&property("Speed", &stat_block.speed));
+    html.push_str(rule);
+
+    html.push_str("<table class=\"abilities\">\n<tr>");
+    for score in Score::ALL {
+        html.push_str(&format!(
+            "<th>{}</th>",
+            score_abbreviation(score).to_uppercase()
+        ));
+    }
+    html.push_str("</tr>\n<tr>");
+    for modifier in &derived.modifiers {
+        html.push_str(&format!(
+            "<td>{} ({})</td>",
+            modifier.value,
+            signed(modifier.modifier)
+        ));
+    }
+    html.push_str("</tr>\n</table>\n");
+    html.push_str(rule);
+
+    let properties = [
+        ("Saving Throws", saves_text(stat_block)),
+        ("Skills", skills_text(stat_block)),
+        (
+            "Damage Vulnerabilities",
+            damage_text(&stat_block.damage_vulnerabilities),
+        ),
+        (
+            "Damage Resistances",
+            damage_text(&stat_block.damage_resistances),
+        ),
+        (
+            "Damage Immunities",
+            damage_text(&stat_block.damage_immunities),
+        ),
+        (
+            "Condition Immunities",
+            condition_text(&stat_block.condition_immunities),
+        ),
+        ("Senses", Some(senses_text(stat_block))),
+        ("Languages", Some(languages_text(stat_block))),
+        ("Challenge", Some(challenge_text(stat_block))),
+        (
+            "Proficiency Bonus",
+            Some(signed(derived.proficiency_bonus as i8)),
+        ),
+    ];
+    for (label, value) in properties {
+        if let Some(value) = value {
+            html.push_str(&property(label, &value));
+        }
+    }
+    html.push_str(rule);
+
+    for statblock_trait in &stat_block.traits {
+        html.push_str(&trait_html(statblock_trait));
+    }
+    if let Some(spells) = &stat_block.spells {
+        html.push_str(&feature(
+            "Spellcasting",
+            &spellcasting_header(stat_block, spells),
+        ));
+        for (label, list) in sorted_spell_lists(spells) {
+            html.push_str(&format!(
+                "<p class=\"spells\">{}: <i>{}</i></p>\n",
+                escape(label),
+                escape(list)
+            ));
+        }
+    }
+
+    push_actions(&mut html, "Actions", None, &stat_block.actions);
+    push_actions(&mut html, "Bonus Actions", None, &stat_block.bonus_actions);
+    push_actions(&mut html, "Reactions", None, &stat_block.reactions);
+    push_actions(
+        &mut html,
+        "Legendary Actions",
+        stat_block.legendary_description.as_deref(),
+        &stat_block.legendary_actions,
+    );
+
+    html.push_str("</article>\n");
+    html
+}
+
+fn html_document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+fn push_actions(html: &mut String, label: &str, description: Option<&str>, actions: &[Action]) {
+    if actions.is_empty() {
+        return;
+    }
+
+    html.push_str(&format!("<h3>{}</h3>\n", label));
+    if let Some(description) = description.filter(|description| !description.is_empty()) {
+        html.push_str(&paragraphs(description));
+    }
+    for action in actions {
+        html.push_str(&feature(&action.name, &action.description));
+    }
+}
+
+fn property(label: &str, value: &str) -> String {
+    format!(
+        "<p class=\"property\"><b>{}</b> {}</p>\n",
+        label,
+        escape(value)
+    )
+}
+
+fn trait_html(statblock_trait: &Trait) -> String {
+    feature(&statblock_trait.name, &statblock_trait.description)
+}
+
+// The name runs into the first paragraph of the description, like a printed statblock
+fn feature(name: &str, description: &str) -> String {
+    let mut lines = description
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+
+    let mut html = format!(
+        "<p><b><i>{}.</i></b> {}</p>\n",
+        escape(name),
+        escape(lines.next().unwrap_or_default())
+    );
+    for line in lines {
+        html.push_str(&format!("<p>{}</p>\n", escape(line)));
+    }
+    html
+}
+
+pub fn paragraphs(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| format!("<p>{}</p>\n", escape(line)))
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
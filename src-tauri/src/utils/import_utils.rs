@@ -0,0 +1,446 @@
+use crate::types::{
+    action_types::{Action, Attack, AttackKind, DamageComponent, SavingThrow},
+    challenge_rating_types::ChallengeRating,
+    condition_types::ConditionType,
+    damage_types::DamageType,
+    proficiency_types::ProficiencyLevel,
+    spell_types::SpellcastingAbility,
+    statblock_types::{Ability, Alignment, Score, Size, StatBlock, Stats},
+};
+use crate::utils::dice_utils::DiceExpression;
+
+// Shared by the 5e.tools and Open5e importers, every parser takes the names as printed in a statblock
+
+//? StatBlock
+
+// Everything an importer doesn't find keeps the same defaults as a new StatBlock in the form
+pub fn empty_statblock(name: &str) -> StatBlock {
+    StatBlock {
+        id: None,
+        name: name.to_string(),
+        size: Size::Medium,
+        type_: String::new(),
+        subtype: None,
+        alignment: Alignment::Unaligned,
+        ac: 10,
+        hp: 1,
+        initiative: ProficiencyLevel::None,
+        hit_dice: String::new(),
+        speed: String::new(),
+        stats: Stats {
+            strength: 10,
+            dexterity: 10,
+            constitution: 10,
+            intelligence: 10,
+            wisdom: 10,
+            charisma: 10,
+        },
+        saves: vec![],
+        skill_saves: vec![],
+        senses: None,
+        languages: None,
+        damage_vulnerabilities: vec![],
+        damage_resistances: vec![],
+        damage_immunities: vec![],
+        condition_immunities: vec![],
+        cr: ChallengeRating::Zero,
+        traits: vec![],
+        spells: None,
+        actions: vec![],
+        legendary_actions: vec![],
+        legendary_description: None,
+        bonus_actions: vec![],
+        reactions: vec![],
+        last_modified: String::new(),
+        user_id: String::new(),
+    }
+}
+
+// A listed bonus of at least twice the proficiency bonus over the modifier counts as expertise
+pub fn proficiency_from_bonus(bonus: i8, modifier: i8, proficiency_bonus: u8) -> ProficiencyLevel {
+    if bonus - modifier >= 2 * proficiency_bonus as i8 {
+        ProficiencyLevel::Expertise
+    } else {
+        ProficiencyLevel::Proficient
+    }
+}
+
+// Builds an Action and reads its attack from the description when it has one
+pub fn parse_action(name: &str, description: &str) -> Action {
+    Action {
+        name: name.to_string(),
+        description: description.to_string(),
+        attack: parse_attack(name, description),
+    }
+}
+
+//? Names
+
+pub fn parse_size(name: &str) -> Option<Size> {
+    match name.trim().to_lowercase().as_str() {
+        "t" | "tiny" => Some(Size::Tiny),
+        "s" | "small" => Some(Size::Small),
+        "m" | "medium" => Some(Size::Medium),
+        "l" | "large" => Some(Size::Large),
+        "h" | "huge" => Some(Size::Huge),
+        "g" | "gargantuan" => Some(Size::Gargantuan),
+        _ => None,
+    }
+}
+
+// Only the nine alignments and unaligned, "any alignment" and the like have no equivalent
+pub fn parse_alignment(text: &str) -> Option<Alignment> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| {
+            word.trim_matches(|c: char| !c.is_alphabetic())
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+    match words.as_slice() {
+        ["unaligned"] => Some(Alignment::Unaligned),
+        ["neutral"] | ["true", "neutral"] | ["neutral", "neutral"] => Some(Alignment::TrueNeutral),
+        ["lawful", "good"] => Some(Alignment::LawfulGood),
+        ["neutral", "good"] => Some(Alignment::NeutralGood),
+        ["chaotic", "good"] => Some(Alignment::ChaoticGood),
+        ["lawful", "neutral"] => Some(Alignment::LawfulNeutral),
+        ["chaotic", "neutral"] => Some(Alignment::ChaoticNeutral),
+        ["lawful", "evil"] => Some(Alignment::LawfulEvil),
+        ["neutral", "evil"] => Some(Alignment::NeutralEvil),
+        ["chaotic", "evil"] => Some(Alignment::ChaoticEvil),
+        _ => None,
+    }
+}
+
+pub fn parse_score(name: &str) -> Option<Score> {
+    match name.trim().to_lowercase().as_str() {
+        "str" | "strength" => Some(Score::Strength),
+        "dex" | "dexterity" => Some(Score::Dexterity),
+        "con" | "constitution" => Some(Score::Constitution),
+        "int" | "intelligence" => Some(Score::Intelligence),
+        "wis" | "wisdom" => Some(Score::Wisdom),
+        "cha" | "charisma" => Some(Score::Charisma),
+        _ => None,
+    }
+}
+
+// Accepts "Sleight of Hand", "sleight_of_hand" and "sleightOfHand" alike
+pub fn parse_ability(name: &str) -> Option<Ability> {
+    let key: String = name
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .collect::<String>()
+        .to_lowercase();
+
+    Ability::ALL
+        .into_iter()
+        .find(|ability| format!("{:?}", ability).to_lowercase() == key)
+}
+
+pub fn parse_damage_type(name: &str) -> Option<DamageType> {
+    match name.trim().to_lowercase().as_str() {
+        "acid" => Some(DamageType::Acid),
+        "bludgeoning" => Some(DamageType::Bludgeoning),
+        "cold" => Some(DamageType::Cold),
+        "fire" => Some(DamageType::Fire),
+        "force" => Some(DamageType::Force),
+        "lightning" => Some(DamageType::Lightning),
+        "necrotic" => Some(DamageType::Necrotic),
+        "piercing" => Some(DamageType::Piercing),
+        "poison" => Some(DamageType::Poison),
+        "psychic" => Some(DamageType::Psychic),
+        "radiant" => Some(DamageType::Radiant),
+        "slashing" => Some(DamageType::Slashing),
+        "thunder" => Some(DamageType::Thunder),
+        _ => None,
+    }
+}
+
+pub fn parse_condition_type(name: &str) -> Option<ConditionType> {
+    match name.trim().to_lowercase().as_str() {
+        "blinded" => Some(ConditionType::Blinded),
+        "charmed" => Some(ConditionType::Charmed),
+        "deafened" => Some(ConditionType::Deafened),
+        "exhaustion" => Some(ConditionType::Exhaustion),
+        "frightened" => Some(ConditionType::Frightened),
+        "grappled" => Some(ConditionType::Grappled),
+        "incapacitated" => Some(ConditionType::Incapacitated),
+        "invisible" => Some(ConditionType::Invisible),
+        "paralyzed" => Some(ConditionType::Paralyzed),
+        "petrified" => Some(ConditionType::Petrified),
+        "poisoned" => Some(ConditionType::Poisoned),
+        "prone" => Some(ConditionType::Prone),
+        "restrained" => Some(ConditionType::Restrained),
+        "stunned" => Some(ConditionType::Stunned),
+        "unconscious" => Some(ConditionType::Unconscious),
+        _ => None,
+    }
+}
+
+pub fn parse_spellcasting_ability(name: &str) -> Option<SpellcastingAbility> {
+    match parse_score(name)? {
+        Score::Intelligence => Some(SpellcastingAbility::Intelligence),
+        Score::Wisdom => Some(SpellcastingAbility::Wisdom),
+        Score::Charisma => Some(SpellcastingAbility::Charisma),
+        _ => None,
+    }
+}
+
+// "... spellcasting ability is Wisdom (spell save DC 13) ..."
+pub fn spellcasting_ability_in(text: &str) -> Option<SpellcastingAbility> {
+    let text = text.to_lowercase();
+    let index = text.find("spellcasting ability is")?;
+
+    text[index + "spellcasting ability is".len()..]
+        .split_whitespace()
+        .next()
+        .and_then(parse_spellcasting_ability)
+}
+
+// Free text such as "poison; bludgeoning, piercing, and slashing from nonmagical attacks".
+// Returns the damage types and every qualifier they were listed with, which a StatBlock can't hold
+pub fn parse_damage_text(text: &str) -> (Vec<DamageType>, Vec<String>) {
+    let mut damage_types = Vec::new();
+    let mut qualifiers = Vec::new();
+
+    for group in text.split(';') {
+        let words: Vec<&str> = group.split_whitespace().collect();
+        let mut qualifier_start = None;
+
+        for (index, word) in words.iter().enumerate() {
+            let word = word.trim_matches(|c: char| !c.is_alphabetic());
+            match parse_damage_type(word) {
+                Some(damage_type) if qualifier_start.is_none() => {
+                    if !damage_types.contains(&damage_type) {
+                        damage_types.push(damage_type);
+                    }
+                }
+                None if word.is_empty() || word == "and" || word == "or" => {}
+                _ => {
+                    qualifier_start.get_or_insert(index);
+                }
+            }
+        }
+
+        if let Some(start) = qualifier_start {
+            qualifiers.push(words[start..].join(" "));
+        }
+    }
+
+    (damage_types, qualifiers)
+}
+
+//? Attack
+
+// Reads the attack line of a plain text action, e.g.
+// "Melee Weapon Attack: +4 to hit, reach 5 ft., one target. Hit: 5 (1d6 + 2) slashing damage."
+// Save-only actions need at least one damage roll to count, anything else stays description only
+pub fn parse_attack(name: &str, description: &str) -> Option<Attack> {
+    let text = description.to_lowercase();
+
+    let kind = attack_kind(&text);
+    let saving_throw = saving_throw(&text);
+    let damage = damage_components(&text);
+
+    if kind.is_none() && (saving_throw.is_none() || damage.is_empty()) {
+        return None;
+    }
+
+    let to_hit = kind.and_then(|_| {
+        number_after(&text, "attack: ")
+            .or_else(|| number_after(&text, "attack roll: "))
+            .and_then(|to_hit| i8::try_from(to_hit).ok())
+    });
+
+    let (range, long_range) = match text.find("range ") {
+        Some(index) => range_after(&text[index + "range ".len()..]),
+        None => (None, None),
+    };
+
+    let name = name.to_lowercase();
+
+    Some(Attack {
+        kind,
+        to_hit,
+        reach: number_after(&text, "reach ").and_then(|reach| u16::try_from(reach).ok()),
+        range,
+        long_range,
+        targets: targets(&text),
+        damage,
+        saving_throw,
+        recharge: recharge(&name),
+        uses_per_day: number_before(&name, "/day").and_then(|uses| u8::try_from(uses).ok()),
+    })
+}
+
+fn attack_kind(text: &str) -> Option<AttackKind> {
+    const KINDS: [(&str, AttackKind); 9] = [
+        (
+            "melee or ranged weapon attack",
+            AttackKind::MeleeOrRangedWeapon,
+        ),
+        (
+            "melee or ranged attack roll",
+            AttackKind::MeleeOrRangedWeapon,
+        ),
+        ("melee or ranged spell attack", AttackKind::MeleeSpell),
+        ("melee weapon attack", AttackKind::MeleeWeapon),
+        ("ranged weapon attack", AttackKind::RangedWeapon),
+        ("melee spell attack", AttackKind::MeleeSpell),
+        ("ranged spell attack", AttackKind::RangedSpell),
+        ("melee attack roll", AttackKind::MeleeWeapon),
+        ("ranged attack roll", AttackKind::RangedWeapon),
+    ];
+
+    KINDS
+        .into_iter()
+        .find(|(label, _)| text.contains(label))
+        .map(|(_, kind)| kind)
+}
+
+// "DC 13 Dexterity saving throw", half damage when the text says so
+fn saving_throw(text: &str) -> Option<SavingThrow> {
+    let index = text.find("dc ")?;
+    let after = &text[index + "dc ".len()..];
+    let dc = leading_number(after).and_then(|dc| u8::try_from(dc).ok())?;
+
+    let score = after
+        .split_whitespace()
+        .nth(1)
+        .and_then(|word| parse_score(word.trim_matches(|c: char| !c.is_alphabetic())))?;
+
+    if !after.contains("saving throw") {
+        return None;
+    }
+
+    Some(SavingThrow {
+        score,
+        dc,
+        half_on_success: text.contains("half as much") || text.contains("half damage"),
+    })
+}
+
+// Every "(2d6 + 3) fire damage" in the text, flat damage without dice is left in the description
+fn damage_components(text: &str) -> Vec<DamageComponent> {
+    let mut damage = Vec::new();
+    let mut rest = text;
+
+    while let Some(open) = rest.find('(') {
+        let Some(close) = rest[open..].find(')').map(|close| open + close) else {
+            break;
+        };
+        let dice = rest[open + 1..close].trim();
+        let after = rest[close + 1..].trim_start();
+        rest = &rest[close + 1..];
+
+        if !dice.contains('d') || dice.parse::<DiceExpression>().is_err() {
+            continue;
+        }
+
+        let mut words = after.split_whitespace();
+        let (Some(damage_type), Some("damage")) = (
+            words.next().and_then(|word| {
+                parse_damage_type(word.trim_matches(|c: char| !c.is_alphabetic()))
+            }),
+            words
+                .next()
+                .map(|word| word.trim_matches(|c: char| !c.is_alphabetic())),
+        ) else {
+            continue;
+        };
+
+        damage.push(DamageComponent {
+            dice: dice.to_string(),
+            damage_type,
+        });
+
+        // "..., or 6 (1d8 + 2) piercing damage if used with two hands" replaces the damage instead of adding to it
+        let after_damage = after.split_once("damage").map_or("", |(_, rest)| rest);
+        if after_damage
+            .trim_start_matches([',', ' '])
+            .starts_with("or ")
+        {
+            break;
+        }
+    }
+
+    damage
+}
+
+fn targets(text: &str) -> u8 {
+    const COUNTS: [(&str, u8); 5] = [
+        ("one target", 1),
+        ("two targets", 2),
+        ("three targets", 3),
+        ("four targets", 4),
+        ("five targets", 5),
+    ];
+
+    COUNTS
+        .into_iter()
+        .find(|(label, _)| text.contains(label))
+        .map_or(1, |(_, count)| count)
+}
+
+// "(Recharge 5-6)" or "(Recharge 6)" in the action's name
+fn recharge(name: &str) -> Option<u8> {
+    let index = name.find("recharge")?;
+    let after = name[index + "recharge".len()..].trim_start();
+
+    match leading_number(after) {
+        Some(recharge) => u8::try_from(recharge).ok().filter(|r| (2..=6).contains(r)),
+        None => Some(6),
+    }
+}
+
+// "80/320 ft." as the normal and long range
+fn range_after(text: &str) -> (Option<u16>, Option<u16>) {
+    let range = leading_number(text).and_then(|range| u16::try_from(range).ok());
+    let long_range = text
+        .split_whitespace()
+        .next()
+        .and_then(|range| range.split_once('/'))
+        .and_then(|(_, long_range)| leading_number(long_range))
+        .and_then(|long_range| u16::try_from(long_range).ok());
+
+    (range, long_range)
+}
+
+//? Helper Util
+
+pub fn number_after(text: &str, marker: &str) -> Option<i32> {
+    let index = text.find(marker)?;
+    leading_number(&text[index + marker.len()..])
+}
+
+pub fn number_before(text: &str, marker: &str) -> Option<i32> {
+    let index = text.find(marker)?;
+    let digits: String = text[..index]
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+
+    digits.parse().ok()
+}
+
+// A number at the start of text, with an optional sign
+pub fn leading_number(text: &str) -> Option<i32> {
+    let text = text.trim_start();
+    let (sign, digits) = match text.strip_prefix('+') {
+        Some(rest) => (1, rest),
+        None => match text.strip_prefix(['-', '−', '–']) {
+            Some(rest) => (-1, rest),
+            None => (1, text),
+        },
+    };
+
+    let digits: String = digits.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse::<i32>().ok().map(|number| sign * number)
+}
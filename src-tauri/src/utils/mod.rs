@@ -1,3 +1,18 @@
 pub mod auth_utils;
+pub mod combat_utils;
+pub mod condition_utils;
+pub mod credential_utils;
+pub mod damage_utils;
+pub mod dice_utils;
+pub mod difficulty_utils;
+pub mod export_utils;
+pub mod fivetools_utils;
+pub mod foundry_utils;
 pub mod fs_utils;
+pub mod homebrewery_utils;
+pub mod html_utils;
+pub mod import_utils;
+pub mod open5e_utils;
+pub mod statblock_utils;
 pub mod supabase_util;
+pub mod sync_utils;
@@ -0,0 +1,743 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    database::{
+        repository::{StatBlockRepository, SupabaseRepository},
+        statblock_db::{fetch_statblocks_from, save_statblock_to},
+    },
+    types::{
+        action_types::Action,
+        challenge_rating_types::ChallengeRating,
+        damage_types::DamageType,
+        error_types::AppError,
+        import_types::{
+            BatchImportReport, CreatureWarnings, ImportFailure, ImportReport, ImportWarning,
+            ImportedStatBlock,
+        },
+        proficiency_types::{SaveProficiency, SkillProficiency},
+        spell_types::Spells,
+        statblock_types::{Score, StatBlock},
+        trait_types::Trait,
+    },
+    utils::import_utils::{
+        empty_statblock, number_after, number_before, parse_ability, parse_action, parse_alignment,
+        parse_condition_type, parse_damage_text, parse_size, proficiency_from_bonus,
+        spellcasting_ability_in,
+    },
+};
+
+// Converts Open5e monster JSON as saved from the API, a single monster, a list or a page of results.
+// Nothing is saved, the frontend reviews the StatBlocks and saves them like any other
+#[tauri::command]
+pub fn import_open5e_statblocks(content: String) -> Result<ImportReport, AppError> {
+    let json: Value = serde_json::from_str(&content)
+        .map_err(|e| AppError::parse(format!("Failed to parse Open5e JSON: {}", e)))?;
+
+    import_open5e_json(&json)
+}
+
+// Imports every .json file in directory and saves the creatures as the user's StatBlocks.
+// A creature named like an existing StatBlock is skipped, so running it twice creates nothing new
+#[tauri::command]
+pub async fn import_open5e_directory(
+    directory: String,
+    access_token: String,
+    user_id: String,
+) -> Result<BatchImportReport, AppError> {
+    let repository = SupabaseRepository::connect(&access_token).await?;
+
+    import_open5e_directory_to(&repository, &directory, &user_id).await
+}
+
+pub async fn import_open5e_directory_to(
+    repository: &impl StatBlockRepository,
+    directory: &str,
+    user_id: &str,
+) -> Result<BatchImportReport, AppError> {
+    let mut paths: Vec<_> = fs::read_dir(directory)
+        .map_err(|e| AppError::storage(format!("Failed to read {}: {}", directory, e)))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    let mut existing_names: Vec<String> = fetch_statblocks_from(repository)
+        .await?
+        .statblocks
+        .iter()
+        .map(|stat_block| stat_block.name.to_lowercase())
+        .collect();
+
+    let mut report = BatchImportReport::default();
+
+    for path in paths {
+        let file_name = file_name(&path);
+
+        let imported = match read_file(&path) {
+            Ok(imported) => imported,
+            Err(error) => {
+                report.failed += 1;
+                report.failures.push(ImportFailure {
+                    name: file_name,
+                    error,
+                });
+                continue;
+            }
+        };
+
+        for failure in imported.failed {
+            report.failed += 1;
+            report.failures.push(ImportFailure {
+                name: format!("{}: {}", file_name, failure.name),
+                error: failure.error,
+            });
+        }
+
+        for ImportedStatBlock {
+            mut stat_block,
+            warnings,
+        } in imported.imported
+        {
+            let name = stat_block.name.to_lowercase();
+            if existing_names.contains(&name) {
+                report.skipped += 1;
+                report.skipped_names.push(stat_block.name);
+                continue;
+            }
+
+            stat_block.user_id = user_id.to_string();
+            stat_block.last_modified = chrono::Utc::now().to_rfc3339();
+
+            let display_name = format!("{}: {}", file_name, stat_block.name);
+            match save_statblock_to(repository, stat_block, None).await {
+                Ok(_) => {
+                    report.created += 1;
+                    existing_names.push(name);
+                    if !warnings.is_empty() {
+                        report.warnings.push(CreatureWarnings {
+                            name: display_name,
+                            warnings,
+                        });
+                    }
+                }
+                Err(error) => {
+                    report.failed += 1;
+                    report.failures.push(ImportFailure {
+                        name: display_name,
+                        error,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+pub fn import_open5e_json(json: &Value) -> Result<ImportReport, AppError> {
+    let monsters: Vec<&Value> = match json {
+        Value::Array(monsters) => monsters.iter().collect(),
+        Value::Object(page) if page.contains_key("results") => page["results"]
+            .as_array()
+            .ok_or(AppError::validation(
+                "results",
+                "Expected a list of monsters",
+            ))?
+            .iter()
+            .collect(),
+        Value::Object(_) => vec![json],
+        _ => {
+            return Err(AppError::validation(
+                "monster",
+                "Expected a monster, a list of monsters or a page of results",
+            ))
+        }
+    };
+
+    let mut report = ImportReport::default();
+
+    for (index, monster) in monsters.into_iter().enumerate() {
+        match convert_monster(monster) {
+            Ok(imported) => report.imported.push(imported),
+            Err(error) => report.failed.push(ImportFailure {
+                name: monster
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or(format!("Monster {}", index + 1)),
+                error,
+            }),
+        }
+    }
+
+    Ok(report)
+}
+
+pub fn convert_monster(monster: &Value) -> Result<ImportedStatBlock, AppError> {
+    let Some(fields) = monster.as_object() else {
+        return Err(AppError::validation("monster", "Expected a monster object"));
+    };
+
+    let name = text(fields, "name").ok_or(AppError::validation("name", "Monster has no name"))?;
+
+    let mut stat_block = empty_statblock(&name);
+    let mut warnings = Vec::new();
+
+    match text(fields, "size").as_deref().and_then(parse_size) {
+        Some(size) => stat_block.size = size,
+        None => warnings.push(ImportWarning::new(
+            "size",
+            "Size is missing or unknown, set to Medium",
+        )),
+    }
+
+    stat_block.type_ = text(fields, "type").unwrap_or_default();
+    stat_block.subtype = text(fields, "subtype");
+
+    let alignment = text(fields, "alignment").unwrap_or_default();
+    match parse_alignment(&alignment) {
+        Some(parsed) => stat_block.alignment = parsed,
+        None => warnings.push(ImportWarning::new(
+            "alignment",
+            format!(
+                "Alignment \"{}\" has no equivalent, set to Unaligned",
+                alignment
+            ),
+        )),
+    }
+
+    match number(fields, "armor_class").and_then(|ac| u8::try_from(ac).ok()) {
+        Some(ac) => stat_block.ac = ac,
+        None => warnings.push(ImportWarning::new(
+            "armor_class",
+            "Armor class is missing, set to 10",
+        )),
+    }
+
+    match number(fields, "hit_points").and_then(|hp| u16::try_from(hp).ok()) {
+        Some(hp) => stat_block.hp = hp,
+        None => warnings.push(ImportWarning::new(
+            "hit_points",
+            "Hit points are missing, set to 1",
+        )),
+    }
+    stat_block.hit_dice = text(fields, "hit_dice").unwrap_or_default();
+
+    match fields.get("speed").and_then(Value::as_object) {
+        Some(speed) => stat_block.speed = speed_text(speed),
+        None => warnings.push(ImportWarning::new("speed", "Speed is missing")),
+    }
+
+    read_stats(fields, &mut stat_block, &mut warnings);
+
+    let cr = text(fields, "challenge_rating").unwrap_or_default();
+    match cr.parse::<ChallengeRating>() {
+        Ok(cr) => stat_block.cr = cr,
+        Err(e) => warnings.push(ImportWarning::new(
+            "challenge_rating",
+            format!("{}, set to 0", e),
+        )),
+    }
+
+    // Proficiency levels depend on the CR, so saves and skills come after it
+    read_saves(fields, &mut stat_block);
+    read_skills(fields, &mut stat_block, &mut warnings);
+
+    stat_block.senses = text(fields, "senses");
+    stat_block.languages = text(fields, "languages");
+
+    stat_block.damage_resistances = read_damage_types(fields, "damage_resistances", &mut warnings);
+    stat_block.damage_immunities = read_damage_types(fields, "damage_immunities", &mut warnings);
+    stat_block.damage_vulnerabilities =
+        read_damage_types(fields, "damage_vulnerabilities", &mut warnings);
+
+    for condition in text(fields, "condition_immunities")
+        .unwrap_or_default()
+        .split([',', ';'])
+        .map(str::trim)
+        .filter(|condition| !condition.is_empty())
+    {
+        match parse_condition_type(condition) {
+            Some(condition_type) if !stat_block.condition_immunities.contains(&condition_type) => {
+                stat_block.condition_immunities.push(condition_type)
+            }
+            Some(_) => {}
+            None => warnings.push(ImportWarning::new(
+                "condition_immunities",
+                format!("Unknown condition {}", condition),
+            )),
+        }
+    }
+
+    stat_block.traits = entries(fields, "special_abilities")
+        .into_iter()
+        .map(|(name, description)| Trait { name, description })
+        .collect();
+    stat_block.actions = actions(fields, "actions");
+    stat_block.bonus_actions = actions(fields, "bonus_actions");
+    stat_block.reactions = actions(fields, "reactions");
+    stat_block.legendary_actions = actions(fields, "legendary_actions");
+    stat_block.legendary_description = text(fields, "legendary_desc");
+
+    read_spells(fields, &mut stat_block, &mut warnings);
+
+    Ok(ImportedStatBlock {
+        stat_block,
+        warnings,
+    })
+}
+
+//? Fields
+
+fn read_file(path: &Path) -> Result<ImportReport, AppError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| AppError::storage(format!("Failed to read {}: {}", path.display(), e)))?;
+    let json: Value = serde_json::from_str(&content)
+        .map_err(|e| AppError::parse(format!("{}: {}", path.display(), e)))?;
+
+    import_open5e_json(&json)
+}
+
+// {"walk": 30, "fly": 60, "hover": true} as "30 ft., fly 60 ft. (hover)"
+fn speed_text(speed: &Map<String, Value>) -> String {
+    let hover = speed.get("hover").and_then(Value::as_bool) == Some(true);
+
+    let mut parts: Vec<String> = ["walk", "burrow", "climb", "fly", "swim"]
+        .into_iter()
+        .filter_map(|mode| {
+            let feet = speed.get(mode).and_then(Value::as_u64)?;
+            Some(match mode {
+                "walk" => format!("{} ft.", feet),
+                "fly" if hover => format!("fly {} ft. (hover)", feet),
+                mode => format!("{} {} ft.", mode, feet),
+            })
+        })
+        .collect();
+
+    if let Some(notes) = speed.get("notes").and_then(Value::as_str) {
+        parts.push(notes.to_string());
+    }
+
+    parts.join(", ")
+}
+
+fn read_stats(
+    fields: &Map<String, Value>,
+    stat_block: &mut StatBlock,
+    warnings: &mut Vec<ImportWarning>,
+) {
+    let stats = &mut stat_block.stats;
+
+    for (key, value) in [
+        ("strength", &mut stats.strength),
+        ("dexterity", &mut stats.dexterity),
+        ("constitution", &mut stats.constitution),
+        ("intelligence", &mut stats.intelligence),
+        ("wisdom", &mut stats.wisdom),
+        ("charisma", &mut stats.charisma),
+    ] {
+        match number(fields, key).and_then(|score| u8::try_from(score).ok()) {
+            Some(score) => *value = score,
+            None => warnings.push(ImportWarning::new(
+                key,
+                "Ability score is missing, set to 10",
+            )),
+        }
+    }
+}
+
+// strength_save and the like, null when the creature isn't proficient
+fn read_saves(fields: &Map<String, Value>, stat_block: &mut StatBlock) {
+    for score in Score::ALL {
+        let key = format!("{:?}_save", score).to_lowercase();
+        let Some(bonus) = number(fields, &key).and_then(|bonus| i8::try_from(bonus).ok()) else {
+            continue;
+        };
+
+        stat_block.saves.push(SaveProficiency {
+            score,
+            level: proficiency_from_bonus(
+                bonus,
+                stat_block.stats.modifier(score),
+                stat_block.proficiency_bonus(),
+            ),
+        });
+    }
+}
+
+fn read_skills(
+    fields: &Map<String, Value>,
+    stat_block: &mut StatBlock,
+    warnings: &mut Vec<ImportWarning>,
+) {
+    let Some(skills) = fields.get("skills").and_then(Value::as_object) else {
+        return;
+    };
+
+    for (key, bonus) in skills {
+        let ability = parse_ability(key);
+        let bonus = bonus.as_i64().and_then(|bonus| i8::try_from(bonus).ok());
+
+        match (ability, bonus) {
+            (Some(ability), Some(bonus)) => stat_block.skill_saves.push(SkillProficiency {
+                ability,
+                level: proficiency_from_bonus(
+                    bonus,
+                    stat_block.stats.modifier(ability.score()),
+                    stat_block.proficiency_bonus(),
+                ),
+            }),
+            _ => warnings.push(ImportWarning::new(
+                "skills",
+                format!("Skill {} couldn't be read", key),
+            )),
+        }
+    }
+}
+
+fn read_damage_types(
+    fields: &Map<String, Value>,
+    key: &str,
+    warnings: &mut Vec<ImportWarning>,
+) -> Vec<DamageType> {
+    let (damage_types, qualifiers) = parse_damage_text(&text(fields, key).unwrap_or_default());
+
+    for qualifier in qualifiers {
+        warnings.push(ImportWarning::new(
+            key,
+            format!("Listed without the qualifier \"{}\"", qualifier),
+        ));
+    }
+
+    damage_types
+}
+
+// Open5e only links the spells, their levels and uses are in the Spellcasting trait's text
+fn read_spells(
+    fields: &Map<String, Value>,
+    stat_block: &mut StatBlock,
+    warnings: &mut Vec<ImportWarning>,
+) {
+    let spell_names: Vec<String> = fields
+        .get("spell_list")
+        .and_then(Value::as_array)
+        .map(|spells| {
+            spells
+                .iter()
+                .filter_map(Value::as_str)
+                .map(spell_name)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let Some(index) = stat_block
+        .traits
+        .iter()
+        .position(|spellcasting| spellcasting.name.to_lowercase().contains("spellcasting"))
+    else {
+        if !spell_names.is_empty() {
+            warnings.push(ImportWarning::new(
+                "spell_list",
+                "Spells are listed without a Spellcasting trait and were left out",
+            ));
+        }
+        return;
+    };
+
+    let description = stat_block.traits[index].description.clone();
+    let lowercase = description.to_lowercase();
+
+    let Some(ability) = spellcasting_ability_in(&description) else {
+        warnings.push(ImportWarning::new(
+            "special_abilities",
+            "Spellcasting ability couldn't be read, the trait was kept as text",
+        ));
+        return;
+    };
+
+    // "• 1st level (4 slots): magic missile, shield" lines, all linked spells when there are none
+    let mut spells: HashMap<String, String> = description
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .map(|(label, spells)| {
+            (
+                label
+                    .trim_start_matches(|c: char| !c.is_alphanumeric())
+                    .trim()
+                    .to_string(),
+                spells.trim().to_string(),
+            )
+        })
+        .filter(|(label, spells)| !label.is_empty() && !spells.is_empty())
+        .collect();
+    if spells.is_empty() && !spell_names.is_empty() {
+        spells.insert("Spells".to_string(), spell_names.join(", "));
+    }
+
+    stat_block.spells = Some(Spells {
+        ability,
+        save_dc: number_after(&lowercase, "dc ")
+            .and_then(|dc| u8::try_from(dc).ok())
+            .unwrap_or_default(),
+        attack_bonus: number_before(&lowercase, " to hit")
+            .and_then(|bonus| u8::try_from(bonus).ok())
+            .unwrap_or_default(),
+        spells,
+    });
+    stat_block.traits.remove(index);
+}
+
+//? Helper Util
+
+// Open5e writes "" and null for missing text alike
+fn text(fields: &Map<String, Value>, key: &str) -> Option<String> {
+    fields
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+fn number(fields: &Map<String, Value>, key: &str) -> Option<i64> {
+    fields.get(key).and_then(Value::as_i64)
+}
+
+// Lists of {"name", "desc"}, or "" when the creature has none
+fn entries(fields: &Map<String, Value>, key: &str) -> Vec<(String, String)> {
+    fields
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let entry = entry.as_object()?;
+            Some((
+                text(entry, "name").unwrap_or_default(),
+                text(entry, "desc").unwrap_or_default(),
+            ))
+        })
+        .collect()
+}
+
+fn actions(fields: &Map<String, Value>, key: &str) -> Vec<Action> {
+    entries(fields, key)
+        .into_iter()
+        .map(|(name, description)| parse_action(&name, &description))
+        .collect()
+}
+
+// "https://api.open5e.com/v1/spells/magic-missile/" as "magic missile"
+fn spell_name(url: &str) -> String {
+    url.trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or(url)
+        .replace('-', " ")
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or(path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        database::memory_repository::InMemoryRepository, types::spell_types::SpellcastingAbility,
+    };
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/open5e")
+    }
+
+    fn import_fixture(file: &str) -> ImportReport {
+        read_file(&fixtures().join(file)).unwrap()
+    }
+
+    fn imported<'a>(report: &'a ImportReport, name: &str) -> &'a ImportedStatBlock {
+        report
+            .imported
+            .iter()
+            .find(|imported| imported.stat_block.name == name)
+            .unwrap()
+    }
+
+    fn warnings(imported: &ImportedStatBlock, field: &str) -> Vec<String> {
+        imported
+            .warnings
+            .iter()
+            .filter(|warning| warning.field == field)
+            .map(|warning| warning.message.clone())
+            .collect()
+    }
+
+    #[test]
+    fn every_fixture_file_imports_on_its_own() {
+        let counts: Vec<(&str, usize, usize)> = [
+            "edge-cases.json",
+            "goblin.json",
+            "lich.json",
+            "results-page.json",
+            "werewolf.json",
+        ]
+        .into_iter()
+        .map(|file| {
+            let report = import_fixture(file);
+            (file, report.imported.len(), report.failed.len())
+        })
+        .collect();
+
+        assert_eq!(
+            counts,
+            [
+                ("edge-cases.json", 3, 0),
+                ("goblin.json", 1, 0),
+                ("lich.json", 1, 0),
+                ("results-page.json", 2, 1),
+                ("werewolf.json", 1, 0),
+            ]
+        );
+        assert_eq!(
+            import_fixture("results-page.json").failed[0].name,
+            "Monster 3"
+        );
+    }
+
+    #[test]
+    fn truncated_file_fails_as_a_whole() {
+        let error = read_file(&fixtures().join("truncated.json")).unwrap_err();
+
+        assert!(matches!(error, AppError::Parse { .. }), "{:?}", error);
+    }
+
+    #[test]
+    fn qualified_damage_types_import_with_a_warning() {
+        let lich = import_fixture("lich.json");
+        let lich = imported(&lich, "Lich");
+        assert_eq!(
+            lich.stat_block.damage_immunities,
+            [
+                DamageType::Poison,
+                DamageType::Bludgeoning,
+                DamageType::Piercing,
+                DamageType::Slashing
+            ]
+        );
+        assert_eq!(
+            warnings(lich, "damage_immunities"),
+            ["Listed without the qualifier \"from nonmagical attacks\""]
+        );
+        assert!(warnings(lich, "damage_resistances").is_empty());
+
+        let werewolf = import_fixture("werewolf.json");
+        assert_eq!(
+            warnings(imported(&werewolf, "Werewolf"), "damage_immunities"),
+            ["Listed without the qualifier \"from nonmagical attacks not made with silvered weapons\""]
+        );
+
+        // Resistances without a qualifier import cleanly
+        let page = import_fixture("results-page.json");
+        let swarm = imported(&page, "Swarm of Bats");
+        assert_eq!(swarm.stat_block.damage_resistances.len(), 3);
+        assert!(warnings(swarm, "damage_resistances").is_empty());
+    }
+
+    #[test]
+    fn spell_levels_come_from_the_spellcasting_trait() {
+        let report = import_fixture("lich.json");
+        let lich = &imported(&report, "Lich").stat_block;
+        let spells = lich.spells.as_ref().unwrap();
+
+        assert_eq!(spells.ability, SpellcastingAbility::Intelligence);
+        assert_eq!(spells.save_dc, 20);
+        assert_eq!(spells.attack_bonus, 12);
+        assert_eq!(spells.spells.len(), 5);
+        assert_eq!(spells.spells["9th level (1 slot)"], "power word kill");
+        assert_eq!(
+            spells.spells["Cantrips (at will)"],
+            "mage hand, prestidigitation, ray of frost"
+        );
+        // The trait becomes the spells, it isn't listed twice
+        assert!(lich
+            .traits
+            .iter()
+            .all(|lich_trait| lich_trait.name != "Spellcasting"));
+    }
+
+    #[test]
+    fn spells_only_linked_in_the_spell_list_are_kept_by_name() {
+        let report = import_fixture("edge-cases.json");
+        let spells = imported(&report, "Acolyte")
+            .stat_block
+            .spells
+            .clone()
+            .unwrap();
+
+        assert_eq!(spells.ability, SpellcastingAbility::Wisdom);
+        assert_eq!(spells.save_dc, 12);
+        assert_eq!(
+            spells.spells["Spells"],
+            "light, sacred flame, thaumaturgy, bless, cure wounds, sanctuary"
+        );
+    }
+
+    #[tokio::test]
+    async fn importing_the_folder_twice_creates_each_creature_once() {
+        let repository = InMemoryRepository::new();
+        let directory = fixtures().display().to_string();
+
+        let first = import_open5e_directory_to(&repository, &directory, "user")
+            .await
+            .unwrap();
+        assert_eq!((first.created, first.skipped, first.failed), (8, 0, 2));
+        let failures: Vec<&str> = first
+            .failures
+            .iter()
+            .map(|failure| failure.name.as_str())
+            .collect();
+        assert_eq!(failures, ["results-page.json: Monster 3", "truncated.json"]);
+
+        let warned: Vec<&str> = first
+            .warnings
+            .iter()
+            .map(|creature| creature.name.as_str())
+            .collect();
+        assert_eq!(
+            warned,
+            [
+                "edge-cases.json: Acolyte",
+                "edge-cases.json: Sprite",
+                "edge-cases.json: Homebrew Ooze",
+                "lich.json: Lich",
+                "werewolf.json: Werewolf"
+            ]
+        );
+        let lich = &first.warnings[3].warnings;
+        assert!(lich.contains(&ImportWarning::new(
+            "damage_immunities",
+            "Listed without the qualifier \"from nonmagical attacks\""
+        )));
+
+        let statblocks = repository.fetch_statblocks().await.unwrap();
+        assert_eq!(statblocks.len(), 8);
+        assert!(statblocks
+            .iter()
+            .all(|stat_block| stat_block.user_id == "user"));
+
+        let second = import_open5e_directory_to(&repository, &directory, "user")
+            .await
+            .unwrap();
+        assert_eq!((second.created, second.skipped, second.failed), (0, 8, 2));
+        // Skipped creatures aren't imported, so they have nothing to warn about
+        assert!(second.warnings.is_empty());
+        assert_eq!(repository.fetch_statblocks().await.unwrap().len(), 8);
+    }
+}
@@ -0,0 +1,9 @@
+use crate::types::{
+    error_types::AppError,
+    statblock_types::{DerivedStats, StatBlock},
+};
+
+#[tauri::command]
+pub fn derive_statblock_stats(stat_block: StatBlock) -> Result<DerivedStats, AppError> {
+    Ok(stat_block.derived_stats())
+}
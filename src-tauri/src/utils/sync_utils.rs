@@ -0,0 +1,337 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tauri::Emitter;
+
+use crate::{
+    database::{
+        encounter_db::fetch_playable_statblocks_from,
+        local_db::{
+            fail_sync_operation, fetch_local_encounter, fetch_local_encounter_players,
+            fetch_local_playable_statblocks, fetch_local_statblocks_by_ids,
+            fetch_pending_sync_operations, fetch_sync_state, fetch_user_sync_operations,
+            open_user_store, queue_sync_operation, remap_temporary_id, remove_sync_operation,
+            save_local_encounter, save_local_statblock, save_sync_state,
+        },
+        repository::{EncounterRepository, Repository, StatBlockRepository, SupabaseRepository},
+    },
+    types::{
+        combat_types::CombatantRef,
+        conflict_types::FieldDifference,
+        error_types::AppError,
+        sync_types::{SyncOperation, SyncStatus},
+    },
+    utils::{
+        auth_utils::{get_stored_value, token_user_id},
+        supabase_util::is_offline,
+    },
+};
+
+static SYNC_APP: OnceLock<tauri::AppHandle> = OnceLock::new();
+static SYNCING: AtomicBool = AtomicBool::new(false);
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(30);
+const LAST_SUCCESS_KEY: &str = "last_success";
+
+//? Commands
+
+#[tauri::command]
+pub async fn get_sync_status() -> Result<SyncStatus, AppError> {
+    sync_status()
+}
+
+#[tauri::command]
+pub async fn sync_now(access_token: String) -> Result<SyncStatus, AppError> {
+    replay_pending_changes(&access_token).await
+}
+
+// Drops a change that can't be replayed, the local copy is left as it is
+#[tauri::command]
+pub async fn discard_sync_operation(operation_id: i64) -> Result<SyncStatus, AppError> {
+    remove_sync_operation(operation_id)?;
+    emit_sync_status();
+    sync_status()
+}
+
+//? Helper Util
+
+// Replays queued changes in the background whenever Supabase is reachable again
+pub fn start_sync_worker(app: tauri::AppHandle) {
+    let _ = SYNC_APP.set(app.clone());
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(SYNC_INTERVAL).await;
+
+            // Nobody logged in means there is no store to replay from
+            let Ok(access_token) = get_stored_value(app.clone(), "access_token".to_string()).await
+            else {
+                continue;
+            };
+            if token_user_id(&access_token)
+                .and_then(|user_id| open_user_store(&user_id))
+                .is_err()
+            {
+                continue;
+            }
+
+            let has_pending = fetch_pending_sync_operations()
+                .map(|pending| !pending.is_empty())
+                .unwrap_or(false);
+            if !has_pending || is_offline().await {
+                continue;
+            }
+
+            if let Err(e) = replay_pending_changes(&access_token).await {
+                eprintln!("Background sync failed: {}", e);
+            }
+        }
+    });
+}
+
+pub fn queue_offline_change(operation: SyncOperation) -> Result<(), AppError> {
+    queue_sync_operation(operation)?;
+    emit_sync_status();
+    Ok(())
+}
+
+fn sync_status() -> Result<SyncStatus, AppError> {
+    let pending = fetch_pending_sync_operations()?;
+
+    Ok(SyncStatus {
+        pending: pending.len() as u32,
+        syncing: SYNCING.load(Ordering::SeqCst),
+        last_success: fetch_sync_state(LAST_SUCCESS_KEY)?
+            .and_then(|value| DateTime::parse_from_rfc3339(&value).ok())
+            .map(|value| value.with_timezone(&Utc)),
+        errors: pending
+            .into_iter()
+            .filter(|queued| queued.last_error.is_some())
+            .collect(),
+    })
+}
+
+fn emit_sync_status() {
+    let Some(app) = SYNC_APP.get() else {
+        return;
+    };
+
+    match sync_status() {
+        Ok(status) => {
+            if let Err(e) = app.emit("sync-status", status) {
+                eprintln!("Failed to emit sync-status event: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to read sync status: {}", e),
+    }
+}
+
+async fn replay_pending_changes(access_token: &str) -> Result<SyncStatus, AppError> {
+    if SYNCING.swap(true, Ordering::SeqCst) {
+        return sync_status();
+    }
+    emit_sync_status();
+
+    let result = replay_queue(access_token).await;
+
+    SYNCING.store(false, Ordering::SeqCst);
+    emit_sync_status();
+
+    result?;
+    sync_status()
+}
+
+async fn replay_queue(access_token: &str) -> Result<(), AppError> {
+    let repository = SupabaseRepository::connect(access_token).await?;
+    // Another user's changes must never be sent with this session
+    let user_id = token_user_id(access_token)?;
+    let mut replayed_ids = Vec::new();
+    let mut failed = false;
+
+    // Replaying can remap ids in later operations, so the queue is re-read after each one
+    while let Some(queued) = fetch_user_sync_operations(&user_id)?
+        .into_iter()
+        .find(|queued| !replayed_ids.contains(&queued.id))
+    {
+        replayed_ids.push(queued.id);
+
+        match replay_operation(&repository, &queued.operation).await {
+            Ok(()) => {
+                remove_sync_operation(queued.id)?;
+                refresh_after_replay(&repository, &queued.operation).await;
+            }
+            Err(_) if repository.is_offline().await => return Ok(()),
+            Err(e) => {
+                fail_sync_operation(queued.id, &e.to_string())?;
+                failed = true;
+            }
+        }
+    }
+
+    if !failed {
+        save_sync_state(LAST_SUCCESS_KEY, &Utc::now().to_rfc3339())?;
+    }
+
+    Ok(())
+}
+
+async fn replay_operation(
+    repository: &(impl StatBlockRepository + EncounterRepository),
+    operation: &SyncOperation,
+) -> Result<(), AppError> {
+    match operation {
+        SyncOperation::SaveStatBlock {
+            statblock_id,
+            base_last_modified,
+        } => {
+            let Some(mut stat_block) = fetch_local_statblocks_by_ids(&[*statblock_id])?
+                .into_iter()
+                .next()
+            else {
+                return Ok(());
+            };
+
+            let response = repository
+                .save_statblock(&mut stat_block, base_last_modified.as_deref())
+                .await?;
+            if let Some(conflict) = &response.conflict {
+                return Err(changed_while_offline("StatBlock", &conflict.differences));
+            }
+            if *statblock_id < 0 {
+                remap_temporary_id("StatBlock", *statblock_id, response.id)?;
+            }
+            // The next offline edit has to start from the last_modified the server stamped
+            save_local_statblock(&mut stat_block)?;
+        }
+        SyncOperation::DeleteStatBlock { statblock_id } => {
+            repository.delete_statblock(*statblock_id).await?;
+        }
+        SyncOperation::SaveEncounter {
+            encounter_id,
+            base_last_modified,
+        } => {
+            let Some(mut encounter) = fetch_local_encounter(*encounter_id)? else {
+                return Ok(());
+            };
+
+            let response = repository
+                .save_encounter(encounter.clone(), base_last_modified.as_deref())
+                .await?;
+            if let Some(conflict) = &response.conflict {
+                return Err(changed_while_offline("Encounter", &conflict.differences));
+            }
+            if *encounter_id < 0 {
+                remap_temporary_id("Encounter", *encounter_id, response.id)?;
+            }
+            encounter.id = Some(response.id);
+            encounter.last_modified = response.last_modified;
+            save_local_encounter(&mut encounter)?;
+        }
+        SyncOperation::DeleteEncounter { encounter_id } => {
+            repository.delete_encounter(*encounter_id).await?;
+        }
+        SyncOperation::SaveEncounterPlayers { encounter_id } => {
+            require_synced("Encounter", *encounter_id)?;
+
+            let encounter_players = fetch_local_encounter_players(*encounter_id)?;
+            repository.save_encounter_players(encounter_players).await?;
+        }
+        SyncOperation::SavePlayableStatBlocks { encounter_id } => {
+            require_synced("Encounter", *encounter_id)?;
+
+            let mut playable_stat_blocks = fetch_local_playable_statblocks(*encounter_id)?;
+            for playable in playable_stat_blocks.iter_mut() {
+                require_synced("StatBlock", playable.statblock_id)?;
+                // Local-only instances are inserted fresh and get their id from Supabase
+                if playable.id.is_some_and(|id| id < 0) {
+                    playable.id = None;
+                }
+            }
+
+            repository
+                .save_playable_statblocks(playable_stat_blocks)
+                .await?;
+        }
+        SyncOperation::UpdateCombatant {
+            encounter_id,
+            combatant_key,
+        } => {
+            let combatant = CombatantRef::parse(combatant_key)?;
+            let body = match &combatant {
+                // Already carried over by the PlayableStatBlock save that creates it
+                CombatantRef::Monster(id) if *id < 0 => return Ok(()),
+                CombatantRef::Monster(id) => fetch_local_playable_statblocks(*encounter_id)?
+                    .into_iter()
+                    .find(|playable| playable.id == Some(*id))
+                    .map(|playable| {
+                        serde_json::json!({
+                            "current_hp": playable.current_hp,
+                            "temporary_hp": playable.temporary_hp,
+                            "conditions": playable.conditions,
+                        })
+                    }),
+                CombatantRef::Player(name) => fetch_local_encounter_players(*encounter_id)?
+                    .into_iter()
+                    .find(|player| &player.name == name)
+                    .map(|player| {
+                        serde_json::json!({
+                            "current_hp": player.current_hp,
+                            "temporary_hp": player.temporary_hp,
+                            "conditions": player.conditions,
+                        })
+                    }),
+            };
+
+            let Some(body) = body else {
+                return Ok(());
+            };
+
+            require_synced("Encounter", *encounter_id)?;
+
+            repository
+                .patch_combatant(&combatant, *encounter_id, &body)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// Supabase gives every PlayableStatBlock a new id on save, so the local copy is refreshed once nothing else is queued
+async fn refresh_after_replay(repository: &impl EncounterRepository, operation: &SyncOperation) {
+    if let SyncOperation::SavePlayableStatBlocks { encounter_id } = operation {
+        if let Err(e) = fetch_playable_statblocks_from(repository, *encounter_id).await {
+            eprintln!("Failed to refresh PlayableStatBlocks: {}", e);
+        }
+    }
+}
+
+// An update made offline is refused if someone else changed the row since we last fetched it
+fn changed_while_offline(table_name: &str, differences: &[FieldDifference]) -> AppError {
+    let fields = differences
+        .iter()
+        .map(|difference| difference.field.as_str())
+        .collect::<Vec<&str>>()
+        .join(", ");
+
+    AppError::conflict(format!(
+        "{} was changed on the server while offline, conflicting fields: {}",
+        table_name, fields
+    ))
+}
+
+fn require_synced(table_name: &str, id: i64) -> Result<(), AppError> {
+    if id < 0 {
+        return Err(AppError::validation(
+            "id",
+            format!("{} {} has not been synced to Supabase yet", table_name, id),
+        ));
+    }
+    Ok(())
+}
@@ -12,8 +12,8 @@ import { darkTheme } from "./theme";
 function App() {
     return (
       <ThemeProvider theme={darkTheme}>
-        <AuthProvider>
-            <Router>
+        <Router>
+            <AuthProvider>
               <MainLayout>
                 <Routes>
                   <Route path="/" Component={Home} />
@@ -23,8 +23,8 @@ function App() {
                   <Route path="/statblocks/create" Component={CreateStatBlock} />
                 </Routes>
               </MainLayout>
-            </Router>
-        </AuthProvider>
+            </AuthProvider>
+        </Router>
       </ThemeProvider>
     );
 }
@@ -0,0 +1,86 @@
+import { Box, Button, Dialog, DialogActions, DialogContent, DialogContentText, DialogTitle, Divider, ToggleButton, ToggleButtonGroup, Typography } from "@mui/material";
+import { useEffect, useState } from "react";
+import { ConflictChoice, FieldDifference } from "../types/conflict";
+import { formatConflictValue, mergeConflict } from "../utils/conflictUtils";
+
+interface ConflictDialogProps<T> {
+	conflict: { local: T, server: T, differences: FieldDifference[] } | null;
+	// What is being saved, e.g. "StatBlock"
+	label: string;
+	onUseServer: (server: T) => void;
+	onSaveMerged: (merged: T) => void;
+	onCancel: () => void;
+}
+
+function ConflictDialog<T>({ conflict, label, onUseServer, onSaveMerged, onCancel }: ConflictDialogProps<T>) {
+	const [choices, setChoices] = useState<Record<string, ConflictChoice>>({});
+
+	// Every field starts on the local edit, the user picks which server values to take
+	useEffect(() => {
+		setChoices(Object.fromEntries(
+			(conflict?.differences ?? []).map(difference => [difference.field, "local"])
+		));
+	}, [conflict]);
+
+	if (!conflict) return null;
+
+	const handleSaveMerged = () => {
+		onSaveMerged(mergeConflict(conflict.local, conflict.server, conflict.differences, choices));
+	}
+
+	return (
+		<Dialog open={!!conflict} onClose={onCancel} maxWidth="md" fullWidth>
+			<DialogTitle>{label} changed since you opened it</DialogTitle>
+			<DialogContent>
+				<DialogContentText sx={{ mb: '1rem' }}>
+					Someone saved this {label} while you were editing. Pick which version of each field to keep.
+				</DialogContentText>
+				{
+					conflict.differences.map(difference => (
+						<Box key={difference.field}>
+							<Box sx={{
+								display: 'flex',
+								flexDirection: 'row',
+								alignItems: 'center',
+								gap: '1rem',
+								padding: '0.5rem 0'
+							}}>
+								<Typography variant="body2" sx={{ flex: 1, fontWeight: 'bold' }}>
+									{difference.field}
+								</Typography>
+								<ToggleButtonGroup
+									exclusive
+									size="small"
+									value={choices[difference.field] ?? "local"}
+									onChange={(_, choice: ConflictChoice | null) => {
+										if (choice) setChoices(prev => ({ ...prev, [difference.field]: choice }));
+									}}
+									sx={{ flex: 3 }}
+								>
+									<ToggleButton value="local" sx={{ flex: 1, textTransform: 'none' }}>
+										Mine: {formatConflictValue(difference.local)}
+									</ToggleButton>
+									<ToggleButton value="server" sx={{ flex: 1, textTransform: 'none' }}>
+										Server: {formatConflictValue(difference.server)}
+									</ToggleButton>
+								</ToggleButtonGroup>
+							</Box>
+							<Divider />
+						</Box>
+					))
+				}
+			</DialogContent>
+			<DialogActions>
+				<Button onClick={onCancel}>Cancel</Button>
+				<Button variant="outlined" onClick={() => onUseServer(conflict.server)}>
+					Use Server Version
+				</Button>
+				<Button variant="contained" onClick={handleSaveMerged}>
+					Save Merged
+				</Button>
+			</DialogActions>
+		</Dialog>
+	)
+}
+
+export default ConflictDialog;
@@ -8,17 +8,14 @@ import { invoke } from '@tauri-apps/api/core';
 import { useEffect, useState } from 'react';
 import { useAuth } from '../context/AuthContext';
 import { Encounter, EncounterPlayer, PlayableStatBlock } from '../types/encounter';
+import { errorMessage } from '../utils/errorUtils';
 
 interface FetchPlayableStatBlocksForEncounterResponse {
 	playable_stat_blocks: PlayableStatBlock[];
-	status: number;
-	message: string;
 }
 
 interface FetchEncounterPlayersForEncounterResponse {
 	encounter_players: EncounterPlayer[];
-	status: number;
-	message: string;
 }
 
 interface EncounterCardProps {
@@ -40,36 +37,32 @@ export default function EncounterCard({ encounter, informative = false, handleEd
 	useEffect(() => {
 		const fetchPlayableStatBlocks = async () => {
 			if (!encounter.id) return;
-			const accessToken = await getAccessToken();
+			try {
+				const accessToken = await getAccessToken();
 
-			const playableStatBlockResponse = await invoke<FetchPlayableStatBlocksForEncounterResponse>(
-				"fetch_playable_statblocks_for_encounter",
-				{
-					encounterId: encounter.id,
-					accessToken
-				}
-			);
-			
-			if (playableStatBlockResponse.status !== 200) {
-				console.error(playableStatBlockResponse.message);
-			}
-			
-			setPlayableStatBlocks(playableStatBlockResponse.playable_stat_blocks);
-			
-			const encounterPlayersResponse = await invoke<FetchEncounterPlayersForEncounterResponse>(
-				"fetch_encounter_players_for_encounter",
-				{
-					encounterId: encounter.id,
-					accessToken
-				}
-			);
-			
-			if (encounterPlayersResponse.status !== 200) {
-				console.error(encounterPlayersResponse.message);
+				const playableStatBlockResponse = await invoke<FetchPlayableStatBlocksForEncounterResponse>(
+					"fetch_playable_statblocks_for_encounter",
+					{
+						encounterId: encounter.id,
+						accessToken
+					}
+				);
+
+				setPlayableStatBlocks(playableStatBlockResponse.playable_stat_blocks);
+
+				const encounterPlayersResponse = await invoke<FetchEncounterPlayersForEncounterResponse>(
+					"fetch_encounter_players_for_encounter",
+					{
+						encounterId: encounter.id,
+						accessToken
+					}
+				);
+
+				setEncounterPlayers(encounterPlayersResponse.encounter_players);
+				setLoading(false);
+			} catch(e) {
+				console.error(errorMessage(e, "Failed to load the encounter"));
 			}
-			
-			setEncounterPlayers(encounterPlayersResponse.encounter_players);
-			setLoading(false);
 		}
 
 		fetchPlayableStatBlocks();
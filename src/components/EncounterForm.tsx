@@ -4,9 +4,12 @@ import { invoke } from "@tauri-apps/api/core";
 import { Dispatch, SetStateAction, useCallback, useEffect, useMemo, useState } from "react";
 import { useAuth } from "../context/AuthContext";
 import { useEncounter } from "../context/CreateEncounterContext";
-import { PlayableStatBlock } from "../types/encounter";
+import { EncounterConflict } from "../types/conflict";
+import { Encounter, PlayableStatBlock } from "../types/encounter";
 import { FetchStatBlockResponse, StatBlock } from "../types/statBlock";
 import { calcEncounterDifficulty, calcEncounterXP, generateEmptyEncounter } from "../utils/encounterUtils";
+import { errorMessage } from "../utils/errorUtils";
+import ConflictDialog from "./ConflictDialog";
 import EncounterFormCreatureSection from "./encounterForm/EncounterFormCreatureSection";
 import EncounterFormPlayerSection from "./encounterForm/EncounterFormPlayerSection";
 
@@ -17,9 +20,10 @@ interface EncounterFormProps {
 
 interface SaveEncounterResponse {
 	id: number,
-    status: number,
     message: string,
     was_updated: boolean,
+    last_modified: string,
+    conflict?: EncounterConflict,
 }
 
 interface SavePlayableStatBlocksResponse {
@@ -45,6 +49,7 @@ function EncounterForm({ setOpen, onSave }: EncounterFormProps) {
 	const [openPlayerCreation, setOpenPlayerCreation] = useState<boolean>(false);
 	const [statBlocks, setStatBlocks] = useState<StatBlock[]>([]);
 	const [loading, setLoading] = useState<boolean>(true);
+	const [conflict, setConflict] = useState<EncounterConflict | null>(null);
 	const theme = useTheme();
 
 	const { user, getAccessToken } = useAuth();
@@ -57,8 +62,8 @@ function EncounterForm({ setOpen, onSave }: EncounterFormProps) {
 	
 				setStatBlocks(response.statblocks);
 				setLoading(false);
-			} catch(e: any) {
-				console.error(e);
+			} catch(e) {
+				console.error(errorMessage(e, "Failed to load StatBlocks"));
 			}
 		};
 
@@ -76,24 +81,41 @@ function EncounterForm({ setOpen, onSave }: EncounterFormProps) {
 
 		if (Object.keys(newErrors).length) return;
 
-		// Save Encounter
+		await saveEncounter({ ...encounter, user_id: user.uuid }, encounter.last_modified || null);
+	}
 
+	// lastSeen is the last_modified the server returned when this Encounter was loaded
+	const saveEncounter = async (toSave: Encounter, lastSeen: string | null) => {
 		setLoading(true);
 
-		const accessToken = await getAccessToken();
-
-		encounter.last_modified = new Date().toISOString();
-		encounter.user_id = user.uuid;
+		try {
+			const accessToken = await getAccessToken();
 
-		const encounterResponse = await invoke<SaveEncounterResponse>("save_encounter", { encounter, accessToken });
+			// A new Encounter has no last_modified until the server stamps its first save
+			const { last_modified, ...unsaved } = toSave;
+			const encounterResponse = await invoke<SaveEncounterResponse>("save_encounter", {
+				encounter: last_modified ? toSave : unsaved,
+				accessToken,
+				lastSeen
+			});
 
-		if (encounterResponse.status === 500) return;
+			if (encounterResponse.conflict) {
+				setConflict(encounterResponse.conflict);
+				setLoading(false);
+				return;
+			}
+			setConflict(null);
 
-		playableStatBlocks.forEach(statBlock => statBlock.encounter_id = encounterResponse.id);
-		encounterPlayers.forEach(player => player.encounter_id = encounterResponse.id);
+			playableStatBlocks.forEach(statBlock => statBlock.encounter_id = encounterResponse.id);
+			encounterPlayers.forEach(player => player.encounter_id = encounterResponse.id);
 
-		await invoke<SavePlayableStatBlocksResponse>("save_playable_statblocks", { playableStatBlocks, accessToken });
-		await invoke<SaveEncounterPlayersResponse>("save_encounter_players", { encounterPlayers, accessToken });
+			await invoke<SavePlayableStatBlocksResponse>("save_playable_statblocks", { playableStatBlocks, accessToken });
+			await invoke<SaveEncounterPlayersResponse>("save_encounter_players", { encounterPlayers, accessToken });
+		} catch (e) {
+			setErrors({ name: errorMessage(e, "Encounter save failed") });
+			setLoading(false);
+			return;
+		}
 
 		// Reset state
 		setPlayableStatBlocks([]);
@@ -165,6 +187,16 @@ function EncounterForm({ setOpen, onSave }: EncounterFormProps) {
 			maxWidth: '700px',
 			overflowY: 'auto'
 		}}>
+			<ConflictDialog
+				conflict={conflict}
+				label="Encounter"
+				onUseServer={(server) => {
+					setEncounter(server);
+					setConflict(null);
+				}}
+				onSaveMerged={(merged) => saveEncounter(merged, conflict?.server.last_modified ?? null)}
+				onCancel={() => setConflict(null)}
+			/>
 			<TextField
 				required
 				id="encounter_name"
@@ -4,20 +4,25 @@ import { invoke } from "@tauri-apps/api/core";
 import { useState } from "react";
 import { useAuth } from "../../context/AuthContext";
 import { useCreateStatBlock } from "../../context/CreateStatBlockContext";
-import { Alignment, CR_VALUES, Size } from "../../types/statBlock";
+import { StatBlockConflict } from "../../types/conflict";
+import { Alignment, CR_VALUES, Size, StatBlock } from "../../types/statBlock";
+import { errorMessage, isAppError } from "../../utils/errorUtils";
 import { updateField, updateIntegerField } from "../../utils/statBlockUtils";
+import ConflictDialog from "../ConflictDialog";
 
 type SaveStatBlockResponse = {
 	id: number;
-	status: number;
     message: string;
     was_updated: boolean;
+    last_modified: string;
+    conflict?: StatBlockConflict;
 }
 
 function UpperStatBlockForm() {
 	const { statBlock, setStatBlock, errors, setErrors } = useCreateStatBlock();
 	const { user, getAccessToken } = useAuth();
 	const [saving, setSaving] = useState<boolean>();
+	const [conflict, setConflict] = useState<StatBlockConflict | null>(null);
 
 	const input_validation = (): boolean => {
 		const newErrors: Record<string, string> = {};
@@ -34,32 +39,50 @@ function UpperStatBlockForm() {
 		return !!Object.keys(newErrors).length;
 	}
 
-	const handleSave = async () => {
-		if (input_validation()) return;
-		if (!user) return;
+	// lastSeen is the last_modified the server returned when this copy was loaded or last saved,
+	// a merged copy is saved against the server version it was merged with
+	const saveStatBlock = async (toSave: StatBlock, lastSeen: string | null) => {
 		setSaving(true);
 
-		let updatedStatBlock = {
-			...statBlock,
-			last_modified: new Date().toISOString(),
-			user_id: user.uuid
-		};
+		try {
+			const accessToken = await getAccessToken();
 
-		const accessToken = await getAccessToken();
+			const res = await invoke<SaveStatBlockResponse>("save_statblock", { statBlock: toSave, accessToken, lastSeen });
 
-		const res = await invoke<SaveStatBlockResponse>("save_statblock", { statBlock: updatedStatBlock, accessToken: accessToken });
+			if (res.conflict) {
+				setConflict(res.conflict);
+			} else {
+				setConflict(null);
+				// The server stamps last_modified, keep its value for the next save
+				setStatBlock({ ...toSave, id: res.id, last_modified: res.last_modified });
+			}
+		} catch (e) {
+			const field = isAppError(e) && e.code === "Validation" ? e.details.field : "name";
+			setErrors({ [field]: errorMessage(e, "StatBlock save failed") });
+		} finally {
+			setSaving(false);
+		}
+	};
 
-		console.log(res.message);
+	const handleSave = async () => {
+		if (input_validation()) return;
+		if (!user) return;
 
-		if (!res.was_updated) {
-			updatedStatBlock.id = res.id;
-			setStatBlock(updatedStatBlock);
-		}
-		setSaving(false);
+		await saveStatBlock({ ...statBlock, user_id: user.uuid }, statBlock.last_modified || null);
 	};
 
 	return (
 		<>
+			<ConflictDialog
+				conflict={conflict}
+				label="StatBlock"
+				onUseServer={(server) => {
+					setStatBlock(server);
+					setConflict(null);
+				}}
+				onSaveMerged={(merged) => saveStatBlock(merged, conflict?.server.last_modified ?? null)}
+				onCancel={() => setConflict(null)}
+			/>
 			<Box sx={{
 					display: 'flex',
 					flexDirection: 'row',
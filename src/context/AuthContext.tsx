@@ -1,7 +1,10 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { createContext, FC, ReactNode, useContext, useEffect, useRef, useState } from "react";
+import { useNavigate } from "react-router-dom";
 import { AuthResponse, LoginRequest, RegisterRequest, RegisterResult, User } from "../types/auth";
+import { AppError } from "../types/error";
+import { errorMessage, hasErrorCode } from "../utils/errorUtils";
 
 interface AuthContextType {
 	user: User | null;
@@ -31,6 +34,7 @@ export const AuthProvider: FC<AuthProviderProps> = ({ children }) => {
 		reject: (error: Error) => void;
 	} | null>(null);
 	const tokenRefreshTimer = useRef<number | null>(null);
+	const navigate = useNavigate();
 
 	// Clear timer on unmount
 	useEffect(() => {
@@ -47,6 +51,21 @@ export const AuthProvider: FC<AuthProviderProps> = ({ children }) => {
 		setupOAuthListener();
 	}, []);
 
+	// The backend gives up on the session when Supabase rejects the refresh token
+	useEffect(() => {
+		const unlisten = listen<AppError>('auth-refresh-failed', async (event) => {
+			console.error('Session refresh failed:', event.payload);
+			await clearStoredAuth();
+			setUser(null);
+			setError('Your session expired, please log in again.');
+			navigate('/auth');
+		});
+
+		return () => {
+			unlisten.then(stopListening => stopListening());
+		};
+	}, []);
+
 		const scheduleTokenRefresh = (expiresIn?: number) => {
 		// Clear existing timer
 		if (tokenRefreshTimer.current) {
@@ -70,38 +89,22 @@ export const AuthProvider: FC<AuthProviderProps> = ({ children }) => {
 		try {
 			console.log("Refreshing access token...");
 
-			const refreshToken = await invoke<string>("get_stored_value", { key: "refresh_token" });
-
-			if (!refreshToken) {
-				console.warn("No refresh token available");
-				await logout();
-				return;
-			}
-
-			const response: AuthResponse = await invoke('refresh_access_token', {
-				refreshToken
-			});
-
-			if (response.error) {
-				throw new Error(response.error);
-			}
+			const accessToken = await invoke<string>("get_stored_value", { key: "access_token" });
 
-			if (response.access_token) {
-				await invoke('store_value', { key: 'access_token', value: response.access_token});
+			// The backend stores the new tokens, and refreshes only once if a request got there first
+			await invoke<string>('refresh_session_tokens', { accessToken });
 
-				if (response.refresh_token) {
-					await invoke('store_value', { key: 'refresh_token', value: response.refresh_token});
-				}
+			const expiresAt = parseInt(await invoke<string>('get_stored_value', { key: 'token_expires_at' }));
+			scheduleTokenRefresh(Math.floor((expiresAt - Date.now()) / 1000));
 
-				const expiresAt = Date.now() + (response.expires_in || 3600) * 1000;
-				await invoke('store_value', { key: 'token_expires_at', value: expiresAt.toString()});
-
-				scheduleTokenRefresh(response.expires_in);
-
-				console.log('Access token refreshed successfully');
-			}
+			console.log('Access token refreshed successfully');
 		} catch (error) {
 			console.error("Token refresh failed:", error);
+			// Offline the stored session is kept, the next refresh tries again
+			if (hasErrorCode(error, "Network")) {
+				scheduleTokenRefresh(60);
+				return;
+			}
 			await logout();
 		}
 	}
@@ -212,12 +215,12 @@ export const AuthProvider: FC<AuthProviderProps> = ({ children }) => {
 			}
 		} catch (error) {
 			console.error('Failed to handle OAuth success:', error);
-			const errorMessage = error instanceof Error ? error.message : 'OAuth authentication failed';
-			setError(errorMessage);
+			const message = errorMessage(error, 'OAuth authentication failed');
+			setError(message);
 			
 			// Reject the OAuth promise if it exists
 			if (oauthPromise) {
-				oauthPromise.reject(new Error(errorMessage));
+				oauthPromise.reject(new Error(message));
 				setOAuthPromise(null);
 			}
 		} finally {
@@ -314,10 +317,10 @@ export const AuthProvider: FC<AuthProviderProps> = ({ children }) => {
 
 			await saveTokensToStorage(response);
 		} catch (error) {
-			const errorMessage = error instanceof Error ? error.message : 'Login failed';
-			setError(errorMessage);
+			const message = errorMessage(error, 'Login failed');
+			setError(message);
 			console.error('Email login failed:', error);
-			throw new Error(errorMessage);
+			throw new Error(message);
 		} finally {
 			setIsLoading(false);
 		}
@@ -357,10 +360,10 @@ export const AuthProvider: FC<AuthProviderProps> = ({ children }) => {
 
 			throw new Error("Unknown registration result");
 		} catch (error) {
-			const errorMessage = error instanceof Error ? error.message : 'Registration failed';
-			setError(errorMessage);
+			const message = errorMessage(error, 'Registration failed');
+			setError(message);
 			console.error('Email registration failed:', error);
-			throw new Error(errorMessage);
+			throw new Error(message);
 		} finally {
 			setIsLoading(false);
 		}
@@ -397,10 +400,10 @@ export const AuthProvider: FC<AuthProviderProps> = ({ children }) => {
 			});
 		} catch (error) {
 			setIsLoading(false);
-			const errorMessage = error instanceof Error ? error.message : 'Discord login failed';
-			setError(errorMessage);
+			const message = errorMessage(error, 'Discord login failed');
+			setError(message);
 			console.error('Discord login failed:', error);
-			throw new Error(errorMessage);
+			throw new Error(message);
 		}
 	};
 
@@ -423,10 +426,10 @@ export const AuthProvider: FC<AuthProviderProps> = ({ children }) => {
 			await clearStoredAuth();
 			setUser(null);
 		} catch (error) {
-			const errorMessage = error instanceof Error ? error.message : 'Logout failed';
-			setError(errorMessage);
+			const message = errorMessage(error, 'Logout failed');
+			setError(message);
 			console.error('Logout failed:', error);
-			throw new Error(errorMessage);
+			throw new Error(message);
 		} finally {
 			setIsLoading(false);
 		}
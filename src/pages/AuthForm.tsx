@@ -114,7 +114,7 @@ function AuthForm() {
 					return;
 				}
 			}
-		} catch (error: any) {
+		} catch (error) {
 			console.error(`${activeTab} failed`, error);
 		}
 	};
@@ -126,7 +126,7 @@ function AuthForm() {
 			if (provider === 'discord') {
 				await loginWithDiscord();
 			}
-		} catch (error: any) {
+		} catch (error) {
 			console.error(`${provider} login failed:`, error);
 		}
 	};
@@ -140,7 +140,7 @@ function AuthForm() {
 				password: '',
 				confirmPassword: ''
 			});
-		} catch (error: any) {
+		} catch (error) {
 			console.error("Logout failed:", error);
 		}
 	};
@@ -5,6 +5,7 @@ import { Dispatch, SetStateAction, useEffect, useState } from "react";
 import EncounterCard from "../components/EncounterCard";
 import { useAuth } from "../context/AuthContext";
 import { Encounter, EncounterPlayer, PlayableStatBlock } from "../types/encounter";
+import { errorMessage } from "../utils/errorUtils";
 import CreateEncounter from "./CreateEncounter";
 
 interface EncounterSearchHeaderProps {
@@ -15,8 +16,6 @@ interface EncounterSearchHeaderProps {
 
 interface FetchEncountersResponse {
 	encounters: Encounter[];
-	status: number;
-	message: string;
 }
 
 interface EditEncounter {
@@ -78,14 +77,16 @@ function EncounterSearch() {
 	const { getAccessToken } = useAuth();
 
 	const fetchEncounters = async () => {
-		const accessToken = await getAccessToken();
+		try {
+			const accessToken = await getAccessToken();
 
-		const response = await invoke<FetchEncountersResponse>("fetch_encounters", { accessToken });
+			const response = await invoke<FetchEncountersResponse>("fetch_encounters", { accessToken });
 
-		if (response.status !== 200) console.log(response.message);
-
-		setEncounters(response.encounters);
-		setLoading(false);
+			setEncounters(response.encounters);
+			setLoading(false);
+		} catch(e) {
+			console.error(errorMessage(e, "Failed to load encounters"));
+		}
 	};
 
 	useEffect(() => {
@@ -6,6 +6,7 @@ import { useNavigate } from "react-router-dom";
 import StatBlockCard from "../components/StatblockCard";
 import { useAuth } from "../context/AuthContext";
 import { FetchStatBlockResponse, StatBlock } from "../types/statBlock";
+import { errorMessage } from "../utils/errorUtils";
 
 interface StatBlockHeaderProps {
 	search: string;
@@ -72,8 +73,8 @@ function StatBlockSearch() {
 	
 				setStatBlocks(response.statblocks);
 				setLoading(false);
-			} catch(e: any) {
-				console.error(e);
+			} catch(e) {
+				console.error(errorMessage(e, "Failed to load StatBlocks"));
 			}
 		};
 
@@ -0,0 +1,24 @@
+import { Encounter } from "./encounter";
+import { StatBlock } from "./statBlock";
+
+export interface FieldDifference {
+	// Dotted path into the saved object, e.g. "stats.strength" or "actions[1].description"
+	field: string;
+	// null when the field only exists on the other side
+	local: unknown;
+	server: unknown;
+}
+
+export interface StatBlockConflict {
+	local: StatBlock;
+	server: StatBlock;
+	differences: FieldDifference[];
+}
+
+export interface EncounterConflict {
+	local: Encounter;
+	server: Encounter;
+	differences: FieldDifference[];
+}
+
+export type ConflictChoice = "local" | "server";
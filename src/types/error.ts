@@ -0,0 +1,12 @@
+// Mirrors AppError in error_types.rs, every command rejects with one of these
+export type AppError =
+	| { code: "Network", details: { message: string } }
+	| { code: "Unauthorized", details: { message: string } }
+	| { code: "NotFound", details: { message: string } }
+	| { code: "Conflict", details: { message: string } }
+	| { code: "Validation", details: { field: string, reason: string } }
+	| { code: "RateLimited", details: { message: string } }
+	| { code: "Parse", details: { message: string } }
+	| { code: "Storage", details: { message: string } };
+
+export type AppErrorCode = AppError["code"];
@@ -201,6 +201,4 @@ export const ACTION_KEY_LABELS: Record<StatBlockActionKey, string> = {
 
 export interface FetchStatBlockResponse {
 	statblocks: StatBlock[];
-	status: number;
-	message: string; 
 }
\ No newline at end of file
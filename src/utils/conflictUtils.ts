@@ -0,0 +1,64 @@
+import { ConflictChoice, FieldDifference } from "../types/conflict";
+
+type PathKey = string | number;
+
+// "actions[1].description" -> ["actions", 1, "description"]
+const parseField = (field: string): PathKey[] => {
+	return field.split(".").flatMap(part => {
+		const [key, ...indexes] = part.split("[");
+		return [
+			...(key ? [key] : []),
+			...indexes.map(index => Number.parseInt(index))
+		];
+	});
+}
+
+const valueAt = (source: unknown, path: PathKey[]): unknown => {
+	return path.reduce<any>((value, key) => value?.[key], source);
+}
+
+// Starts from the local copy and takes the server's value for every field chosen as "server"
+export const mergeConflict = <T>(
+	local: T,
+	server: T,
+	differences: FieldDifference[],
+	choices: Record<string, ConflictChoice>
+): T => {
+	const merged = structuredClone(local) as any;
+	const shortened: unknown[][] = [];
+
+	differences
+		.filter(difference => choices[difference.field] === "server")
+		.forEach(difference => {
+			const path = parseField(difference.field);
+			const key = path[path.length - 1];
+			const parent = valueAt(merged, path.slice(0, -1)) as any;
+			if (parent === undefined || parent === null) return;
+
+			const value = valueAt(server, path);
+			if (value !== undefined && value !== null) {
+				parent[key] = structuredClone(value);
+			} else if (Array.isArray(parent)) {
+				// Removed after every choice is applied so the other indexes still line up
+				parent[key as number] = undefined;
+				shortened.push(parent);
+			} else {
+				delete parent[key];
+			}
+		});
+
+	shortened.forEach(items => {
+		for (let index = items.length - 1; index >= 0; index--) {
+			if (items[index] === undefined) items.splice(index, 1);
+		}
+	});
+
+	return merged;
+}
+
+export const formatConflictValue = (value: unknown): string => {
+	if (value === undefined || value === null) return "(none)";
+	if (typeof value === "string") return value;
+
+	return JSON.stringify(value);
+}
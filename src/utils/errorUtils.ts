@@ -0,0 +1,24 @@
+import { AppError, AppErrorCode } from "../types/error";
+
+export const isAppError = (error: unknown): error is AppError => {
+	return typeof error === "object"
+		&& error !== null
+		&& "code" in error
+		&& "details" in error;
+}
+
+export const hasErrorCode = (error: unknown, code: AppErrorCode): boolean => {
+	return isAppError(error) && error.code === code;
+}
+
+// Commands reject with an AppError, anything thrown on the frontend is an Error
+export const errorMessage = (error: unknown, fallback: string): string => {
+	if (isAppError(error)) {
+		return error.code === "Validation"
+			? `${error.details.field}: ${error.details.reason}`
+			: error.details.message;
+	}
+	if (error instanceof Error) return error.message;
+
+	return fallback;
+}
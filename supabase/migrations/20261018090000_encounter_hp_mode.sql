@@ -0,0 +1,8 @@
+-- How hit points are assigned to new PlayableStatBlocks in an encounter
+alter table public."Encounter"
+    add column if not exists hp_mode text not null default 'Average'
+    check (hp_mode in ('Average', 'Rolled', 'Max'));
+
+-- Hit point maximum chosen when the instance was created, null for legacy rows
+alter table public."PlayableStatBlock"
+    add column if not exists max_hp integer check (max_hp > 0);
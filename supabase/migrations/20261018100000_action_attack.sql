@@ -0,0 +1,5 @@
+-- Structured attack data for actions, null for flavor-only and legacy rows
+alter table public."Action" add column if not exists attack jsonb;
+alter table public."BonusAction" add column if not exists attack jsonb;
+alter table public."Reaction" add column if not exists attack jsonb;
+alter table public."LegendaryAction" add column if not exists attack jsonb;
@@ -0,0 +1,6 @@
+-- Conditions currently affecting a creature during combat, see ActiveCondition
+alter table public."EncounterPlayer"
+    add column if not exists conditions jsonb not null default '[]'::jsonb;
+
+alter table public."PlayableStatBlock"
+    add column if not exists conditions jsonb not null default '[]'::jsonb;
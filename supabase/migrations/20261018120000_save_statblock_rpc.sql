@@ -0,0 +1,121 @@
+-- Writes a StatBlock and every child row in one transaction, see save_statblock in statblock_db.rs
+-- statblock holds the StatBlock columns, children holds the rows of each child table keyed by table name.
+-- last_modified is always set here, whatever the client sent.
+-- Returns {"id", "last_modified"} of the saved row, or null when target_id doesn't exist or was modified since last_seen.
+drop function if exists public.save_statblock(jsonb, jsonb, bigint, timestamptz);
+
+create function public.save_statblock(
+    statblock jsonb,
+    children jsonb,
+    target_id bigint default null,
+    last_seen timestamptz default null
+) returns jsonb
+language plpgsql
+security invoker
+set search_path = public
+as $$
+declare
+    saved_id bigint;
+    saved_last_modified "StatBlock".last_modified%type;
+begin
+    if target_id is null then
+        insert into "StatBlock" (
+            name, size, creature_type, subtype, alignment, ac, hp, initiative, hit_dice, speed,
+            senses, languages, strength, dexterity, constitution, intelligence, wisdom, charisma,
+            cr, last_modified, legendary_description, user_id, spellcasting_ability, save_dc,
+            spell_attack_bonus
+        )
+        select
+            r.name, r.size, r.creature_type, r.subtype, r.alignment, r.ac, r.hp, r.initiative,
+            r.hit_dice, r.speed, r.senses, r.languages, r.strength, r.dexterity, r.constitution,
+            r.intelligence, r.wisdom, r.charisma, r.cr, now(), r.legendary_description,
+            r.user_id, r.spellcasting_ability, r.save_dc, r.spell_attack_bonus
+        from jsonb_populate_record(null::"StatBlock", statblock) r
+        returning id, last_modified into saved_id, saved_last_modified;
+    else
+        update "StatBlock" s set (
+            name, size, creature_type, subtype, alignment, ac, hp, initiative, hit_dice, speed,
+            senses, languages, strength, dexterity, constitution, intelligence, wisdom, charisma,
+            cr, last_modified, legendary_description, user_id, spellcasting_ability, save_dc,
+            spell_attack_bonus
+        ) = (
+            r.name, r.size, r.creature_type, r.subtype, r.alignment, r.ac, r.hp, r.initiative,
+            r.hit_dice, r.speed, r.senses, r.languages, r.strength, r.dexterity, r.constitution,
+            r.intelligence, r.wisdom, r.charisma, r.cr, now(), r.legendary_description,
+            r.user_id, r.spellcasting_ability, r.save_dc, r.spell_attack_bonus
+        )
+        from jsonb_populate_record(null::"StatBlock", statblock) r
+        where s.id = target_id
+            and (last_seen is null or s.last_modified::timestamptz = last_seen)
+        returning s.id, s.last_modified into saved_id, saved_last_modified;
+
+        if saved_id is null then
+            return null;
+        end if;
+    end if;
+
+    delete from "Action" where statblock_id = saved_id;
+    insert into "Action" (statblock_id, name, description, attack)
+    select saved_id, r.name, r.description, r.attack
+    from jsonb_populate_recordset(null::"Action", coalesce(children -> 'Action', '[]')) r;
+
+    delete from "BonusAction" where statblock_id = saved_id;
+    insert into "BonusAction" (statblock_id, name, description, attack)
+    select saved_id, r.name, r.description, r.attack
+    from jsonb_populate_recordset(null::"BonusAction", coalesce(children -> 'BonusAction', '[]')) r;
+
+    delete from "Reaction" where statblock_id = saved_id;
+    insert into "Reaction" (statblock_id, name, description, attack)
+    select saved_id, r.name, r.description, r.attack
+    from jsonb_populate_recordset(null::"Reaction", coalesce(children -> 'Reaction', '[]')) r;
+
+    delete from "LegendaryAction" where statblock_id = saved_id;
+    insert into "LegendaryAction" (statblock_id, name, description, attack)
+    select saved_id, r.name, r.description, r.attack
+    from jsonb_populate_recordset(null::"LegendaryAction", coalesce(children -> 'LegendaryAction', '[]')) r;
+
+    delete from "Trait" where statblock_id = saved_id;
+    insert into "Trait" (statblock_id, name, description)
+    select saved_id, r.name, r.description
+    from jsonb_populate_recordset(null::"Trait", coalesce(children -> 'Trait', '[]')) r;
+
+    delete from "DamageResistance" where statblock_id = saved_id;
+    insert into "DamageResistance" (statblock_id, damage_type)
+    select saved_id, r.damage_type
+    from jsonb_populate_recordset(null::"DamageResistance", coalesce(children -> 'DamageResistance', '[]')) r;
+
+    delete from "DamageImmunity" where statblock_id = saved_id;
+    insert into "DamageImmunity" (statblock_id, damage_type)
+    select saved_id, r.damage_type
+    from jsonb_populate_recordset(null::"DamageImmunity", coalesce(children -> 'DamageImmunity', '[]')) r;
+
+    delete from "DamageVulnerability" where statblock_id = saved_id;
+    insert into "DamageVulnerability" (statblock_id, damage_type)
+    select saved_id, r.damage_type
+    from jsonb_populate_recordset(null::"DamageVulnerability", coalesce(children -> 'DamageVulnerability', '[]')) r;
+
+    delete from "ConditionImmunity" where statblock_id = saved_id;
+    insert into "ConditionImmunity" (statblock_id, condition_type)
+    select saved_id, r.condition_type
+    from jsonb_populate_recordset(null::"ConditionImmunity", coalesce(children -> 'ConditionImmunity', '[]')) r;
+
+    delete from "SaveProficiency" where statblock_id = saved_id;
+    insert into "SaveProficiency" (statblock_id, score, level)
+    select saved_id, r.score, r.level
+    from jsonb_populate_recordset(null::"SaveProficiency", coalesce(children -> 'SaveProficiency', '[]')) r;
+
+    delete from "SkillProficiency" where statblock_id = saved_id;
+    insert into "SkillProficiency" (statblock_id, ability, level)
+    select saved_id, r.ability, r.level
+    from jsonb_populate_recordset(null::"SkillProficiency", coalesce(children -> 'SkillProficiency', '[]')) r;
+
+    delete from "Spells" where statblock_id = saved_id;
+    insert into "Spells" (statblock_id, name, spell_list)
+    select saved_id, r.name, r.spell_list
+    from jsonb_populate_recordset(null::"Spells", coalesce(children -> 'Spells', '[]')) r;
+
+    return jsonb_build_object('id', saved_id, 'last_modified', saved_last_modified);
+end;
+$$;
+
+grant execute on function public.save_statblock(jsonb, jsonb, bigint, timestamptz) to authenticated;
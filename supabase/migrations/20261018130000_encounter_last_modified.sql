@@ -0,0 +1,17 @@
+-- The server stamps last_modified on every Encounter write, so clients with skewed clocks can't
+-- move it backwards and the value returned from a save is the one the next last_seen must match.
+create or replace function public.set_last_modified()
+returns trigger
+language plpgsql
+as $$
+begin
+    new.last_modified := now();
+    return new;
+end;
+$$;
+
+drop trigger if exists set_encounter_last_modified on "Encounter";
+
+create trigger set_encounter_last_modified
+    before insert or update on "Encounter"
+    for each row execute function public.set_last_modified();